@@ -0,0 +1,18 @@
+-- Upgrade an existing bookstore database to the single-row audit chain head.
+--
+-- AuditService::append locks this row to serialize appends, so it must exist
+-- and hold the hash of the newest audit log (or the genesis hash when the
+-- log is empty) before the server takes writes.
+
+CREATE TABLE IF NOT EXISTS `audit_chain_head` (
+  `chain_id` tinyint unsigned NOT NULL,
+  `hash` char(64) COLLATE utf8mb4_general_ci NOT NULL,
+  PRIMARY KEY (`chain_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+
+INSERT INTO audit_chain_head (chain_id, hash)
+SELECT 1, COALESCE(
+  (SELECT hash FROM audit_logs ORDER BY audit_log_id DESC LIMIT 1),
+  '0000000000000000000000000000000000000000000000000000000000000000'
+)
+ON DUPLICATE KEY UPDATE hash = VALUES(hash);
@@ -1057,35 +1057,273 @@ CREATE TABLE `admins` (
 
 LOCK TABLES `admins` WRITE;
 /*!40000 ALTER TABLE `admins` DISABLE KEYS */;
-INSERT INTO `admins` VALUES (1,'root','tN88Bj4=','active','admin'),(2,'reinerina','45h9W2VXv8K8bg==','active','admin'),(3,'test','tN88Bj4A','active','admin');
+INSERT INTO `admins` VALUES (1,'root','pbkdf2-sha256$600000$UQPivuYdamejKNRdKMQBag==$/eRUrdE7/D5vdNEQhID1lZhkWFKtDpY/jB+BBuP4C0M=','active','admin'),(2,'reinerina','pbkdf2-sha256$600000$vFXNUtJVcunomhq0fswdZw==$NIHgTWrOvbaUbnToQ9ILXBkvX61cxGYdMS8NIsRxGJw=','active','admin'),(3,'test','pbkdf2-sha256$600000$8u1D7Tt8InQcFmpq4ZfTkQ==$J+RNZgKkZjirEpqsSSAK+m0RuSThvi1RjwogKsV/uvM=','active','admin');
 /*!40000 ALTER TABLE `admins` ENABLE KEYS */;
 UNLOCK TABLES;
 
 --
--- Table structure for table `authed_customers`
+-- Table structure for table `admin_login_challenges`
 --
 
-DROP TABLE IF EXISTS `authed_customers`;
+DROP TABLE IF EXISTS `admin_login_challenges`;
 /*!40101 SET @saved_cs_client     = @@character_set_client */;
 /*!50503 SET character_set_client = utf8mb4 */;
-CREATE TABLE `authed_customers` (
-  `customer_id` int unsigned NOT NULL,
+CREATE TABLE `admin_login_challenges` (
+  `challenge_id` int unsigned NOT NULL AUTO_INCREMENT,
+  `admin_id` int unsigned NOT NULL,
+  `challenge_hash` char(64) COLLATE utf8mb4_general_ci NOT NULL,
+  `created_at` datetime NOT NULL,
+  `expires_at` datetime NOT NULL,
+  `used_at` datetime DEFAULT NULL,
+  `attempts` int unsigned NOT NULL DEFAULT '0',
+  PRIMARY KEY (`challenge_id`),
+  UNIQUE KEY `challenge_hash` (`challenge_hash`),
+  KEY `admin_login_challenge_admin_id` (`admin_id`),
+  CONSTRAINT `admin_login_challenge_admin_id` FOREIGN KEY (`admin_id`) REFERENCES `admins` (`admin_id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `admin_login_challenges`
+--
+
+LOCK TABLES `admin_login_challenges` WRITE;
+/*!40000 ALTER TABLE `admin_login_challenges` DISABLE KEYS */;
+/*!40000 ALTER TABLE `admin_login_challenges` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `admin_mfa`
+--
+
+DROP TABLE IF EXISTS `admin_mfa`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `admin_mfa` (
+  `admin_id` int unsigned NOT NULL,
+  `secret` varchar(255) COLLATE utf8mb4_general_ci NOT NULL,
+  `is_enabled` tinyint unsigned NOT NULL DEFAULT '0',
+  `last_step` bigint DEFAULT NULL,
+  `created_at` datetime NOT NULL,
+  `enabled_at` datetime DEFAULT NULL,
+  PRIMARY KEY (`admin_id`),
+  CONSTRAINT `admin_mfa_admin_id` FOREIGN KEY (`admin_id`) REFERENCES `admins` (`admin_id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `admin_mfa`
+--
+
+LOCK TABLES `admin_mfa` WRITE;
+/*!40000 ALTER TABLE `admin_mfa` DISABLE KEYS */;
+/*!40000 ALTER TABLE `admin_mfa` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `admin_recovery_codes`
+--
+
+DROP TABLE IF EXISTS `admin_recovery_codes`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `admin_recovery_codes` (
+  `code_id` int unsigned NOT NULL AUTO_INCREMENT,
+  `admin_id` int unsigned NOT NULL,
+  `code_hash` char(64) COLLATE utf8mb4_general_ci NOT NULL,
+  `used_at` datetime DEFAULT NULL,
+  PRIMARY KEY (`code_id`),
+  KEY `admin_recovery_code_admin_id` (`admin_id`),
+  CONSTRAINT `admin_recovery_code_admin_id` FOREIGN KEY (`admin_id`) REFERENCES `admins` (`admin_id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `admin_recovery_codes`
+--
+
+LOCK TABLES `admin_recovery_codes` WRITE;
+/*!40000 ALTER TABLE `admin_recovery_codes` DISABLE KEYS */;
+/*!40000 ALTER TABLE `admin_recovery_codes` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `admin_roles`
+--
+
+DROP TABLE IF EXISTS `admin_roles`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `admin_roles` (
+  `admin_id` int unsigned NOT NULL,
+  `role_id` int unsigned NOT NULL,
+  PRIMARY KEY (`admin_id`,`role_id`),
+  KEY `admin_role_role_id` (`role_id`),
+  CONSTRAINT `admin_role_admin_id` FOREIGN KEY (`admin_id`) REFERENCES `admins` (`admin_id`) ON DELETE CASCADE ON UPDATE CASCADE,
+  CONSTRAINT `admin_role_role_id` FOREIGN KEY (`role_id`) REFERENCES `roles` (`role_id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `admin_roles`
+--
+
+LOCK TABLES `admin_roles` WRITE;
+/*!40000 ALTER TABLE `admin_roles` DISABLE KEYS */;
+INSERT INTO `admin_roles` VALUES (1,1),(2,1),(3,1);
+/*!40000 ALTER TABLE `admin_roles` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `admin_sessions`
+--
+
+DROP TABLE IF EXISTS `admin_sessions`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `admin_sessions` (
+  `session_id` int unsigned NOT NULL AUTO_INCREMENT,
+  `admin_id` int unsigned NOT NULL,
   `token` varchar(255) CHARACTER SET utf8mb4 COLLATE utf8mb4_general_ci NOT NULL,
+  `created_at` datetime NOT NULL,
   `last_used` datetime NOT NULL,
-  `is_online` tinyint unsigned NOT NULL DEFAULT '0',
-  PRIMARY KEY (`customer_id`),
-  CONSTRAINT `authed_customer_id` FOREIGN KEY (`customer_id`) REFERENCES `customers` (`customer_id`) ON DELETE CASCADE ON UPDATE CASCADE
+  `is_online` tinyint unsigned NOT NULL DEFAULT '1',
+  PRIMARY KEY (`session_id`),
+  UNIQUE KEY `token` (`token`),
+  KEY `admin_session_admin_id` (`admin_id`),
+  CONSTRAINT `admin_session_admin_id` FOREIGN KEY (`admin_id`) REFERENCES `admins` (`admin_id`) ON DELETE CASCADE ON UPDATE CASCADE
 ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
 /*!40101 SET character_set_client = @saved_cs_client */;
 
 --
--- Dumping data for table `authed_customers`
+-- Dumping data for table `admin_sessions`
 --
 
-LOCK TABLES `authed_customers` WRITE;
-/*!40000 ALTER TABLE `authed_customers` DISABLE KEYS */;
-INSERT INTO `authed_customers` VALUES (1,'CghunEsrEGfhn2Se+gLJFdaycrt6AOgMR+CvF5IwnfsrU8Q62NNLQaRc/UIW','2025-01-01 18:29:34',0),(2,'hiqs1HNswcQpQFJucvkkJQNW0Pyi1J96EoqOx2ofNmryULTys+pSUlAaaA7Zeb+hQeE=','2025-01-01 18:30:06',1);
-/*!40000 ALTER TABLE `authed_customers` ENABLE KEYS */;
+LOCK TABLES `admin_sessions` WRITE;
+/*!40000 ALTER TABLE `admin_sessions` DISABLE KEYS */;
+/*!40000 ALTER TABLE `admin_sessions` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `api_key_permissions`
+--
+
+DROP TABLE IF EXISTS `api_key_permissions`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `api_key_permissions` (
+  `api_key_id` int unsigned NOT NULL,
+  `permission_id` int unsigned NOT NULL,
+  PRIMARY KEY (`api_key_id`,`permission_id`),
+  KEY `api_key_permission_permission_id` (`permission_id`),
+  CONSTRAINT `api_key_permission_api_key_id` FOREIGN KEY (`api_key_id`) REFERENCES `api_keys` (`api_key_id`) ON DELETE CASCADE ON UPDATE CASCADE,
+  CONSTRAINT `api_key_permission_permission_id` FOREIGN KEY (`permission_id`) REFERENCES `permissions` (`permission_id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `api_key_permissions`
+--
+
+LOCK TABLES `api_key_permissions` WRITE;
+/*!40000 ALTER TABLE `api_key_permissions` DISABLE KEYS */;
+/*!40000 ALTER TABLE `api_key_permissions` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `api_keys`
+--
+
+DROP TABLE IF EXISTS `api_keys`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `api_keys` (
+  `api_key_id` int unsigned NOT NULL AUTO_INCREMENT,
+  `admin_id` int unsigned NOT NULL,
+  `key_name` varchar(100) COLLATE utf8mb4_general_ci NOT NULL,
+  `key_prefix` varchar(16) COLLATE utf8mb4_general_ci NOT NULL,
+  `key_hash` char(64) COLLATE utf8mb4_general_ci NOT NULL,
+  `created_at` datetime NOT NULL,
+  `expires_at` datetime NOT NULL,
+  `last_used_at` datetime DEFAULT NULL,
+  `revoked_at` datetime DEFAULT NULL,
+  PRIMARY KEY (`api_key_id`),
+  UNIQUE KEY `key_hash` (`key_hash`),
+  KEY `api_key_admin_id` (`admin_id`),
+  CONSTRAINT `api_key_admin_id` FOREIGN KEY (`admin_id`) REFERENCES `admins` (`admin_id`) ON DELETE RESTRICT ON UPDATE RESTRICT
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `api_keys`
+--
+
+LOCK TABLES `api_keys` WRITE;
+/*!40000 ALTER TABLE `api_keys` DISABLE KEYS */;
+/*!40000 ALTER TABLE `api_keys` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `audit_chain_head`
+--
+
+DROP TABLE IF EXISTS `audit_chain_head`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `audit_chain_head` (
+  `chain_id` tinyint unsigned NOT NULL,
+  `hash` char(64) COLLATE utf8mb4_general_ci NOT NULL,
+  PRIMARY KEY (`chain_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `audit_chain_head`
+--
+
+LOCK TABLES `audit_chain_head` WRITE;
+/*!40000 ALTER TABLE `audit_chain_head` DISABLE KEYS */;
+INSERT INTO `audit_chain_head` VALUES (1,'0000000000000000000000000000000000000000000000000000000000000000');
+/*!40000 ALTER TABLE `audit_chain_head` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `audit_logs`
+--
+
+DROP TABLE IF EXISTS `audit_logs`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `audit_logs` (
+  `audit_log_id` int unsigned NOT NULL AUTO_INCREMENT,
+  `admin_id` int unsigned NOT NULL,
+  `api_key_id` int unsigned DEFAULT NULL,
+  `action` varchar(50) COLLATE utf8mb4_general_ci NOT NULL,
+  `entity_type` varchar(50) COLLATE utf8mb4_general_ci NOT NULL,
+  `entity_id` int unsigned NOT NULL,
+  `before_value` text COLLATE utf8mb4_general_ci,
+  `after_value` text COLLATE utf8mb4_general_ci,
+  `created_at` datetime NOT NULL,
+  `prev_hash` char(64) COLLATE utf8mb4_general_ci NOT NULL,
+  `hash` char(64) COLLATE utf8mb4_general_ci NOT NULL,
+  PRIMARY KEY (`audit_log_id`),
+  UNIQUE KEY `hash` (`hash`),
+  KEY `audit_log_admin_id` (`admin_id`),
+  KEY `audit_log_api_key_id` (`api_key_id`),
+  KEY `audit_log_entity` (`entity_type`,`entity_id`),
+  KEY `audit_log_created_at` (`created_at`),
+  CONSTRAINT `audit_log_admin_id` FOREIGN KEY (`admin_id`) REFERENCES `admins` (`admin_id`) ON DELETE RESTRICT ON UPDATE RESTRICT,
+  CONSTRAINT `audit_log_api_key_id` FOREIGN KEY (`api_key_id`) REFERENCES `api_keys` (`api_key_id`) ON DELETE RESTRICT ON UPDATE RESTRICT
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `audit_logs`
+--
+
+LOCK TABLES `audit_logs` WRITE;
+/*!40000 ALTER TABLE `audit_logs` DISABLE KEYS */;
+/*!40000 ALTER TABLE `audit_logs` ENABLE KEYS */;
 UNLOCK TABLES;
 
 --
@@ -1398,8 +1636,9 @@ CREATE TABLE `books` (
   PRIMARY KEY (`book_id`,`isbn`,`title`) USING BTREE,
   UNIQUE KEY `book_id` (`book_id`) USING BTREE,
   KEY `publisher_id` (`publisher_id`),
-  KEY `isbn` (`isbn`) USING BTREE,
+  UNIQUE KEY `isbn` (`isbn`) USING BTREE,
   FULLTEXT KEY `title` (`title`) /*!50100 WITH PARSER `ngram` */ ,
+  FULLTEXT KEY `catalog` (`catalog`) /*!50100 WITH PARSER `ngram` */ ,
   CONSTRAINT `publisher_id` FOREIGN KEY (`publisher_id`) REFERENCES `publishers` (`publisher_id`) ON DELETE CASCADE ON UPDATE CASCADE
 ) ENGINE=InnoDB AUTO_INCREMENT=11 DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
 /*!40101 SET character_set_client = @saved_cs_client */;
@@ -1410,7 +1649,7 @@ CREATE TABLE `books` (
 
 LOCK TABLES `books` WRITE;
 /*!40000 ALTER TABLE `books` DISABLE KEYS */;
-INSERT INTO `books` VALUES (1,'9789888864690','Butterworths Hong Kong Employment Law Handbook 8th ed',1,2409.14,'','/assets/images/9789888864690.png',1),(2,'9781509972913','Chinese Civil Code: Specific Parts',2,2398.50,'','/assets/images/9781509972913.png',0),(4,'9780593499573','Foundation',3,3910.00,'','',0),(6,'9780593499573','Foundation and Empire',3,3716.00,'','',0),(7,'9780593499573','Second Foundation',3,3873.00,'','',0),(10,'9780743484862','As You Like It',4,3498.54,'','/assets/images/9780743484862.png',1);
+INSERT INTO `books` VALUES (1,'9789888864690','Butterworths Hong Kong Employment Law Handbook 8th ed',1,2409.14,'','/assets/images/9789888864690.png',1),(2,'9781509972913','Chinese Civil Code: Specific Parts',2,2398.50,'','/assets/images/9781509972913.png',0),(4,'9780593499573','Foundation',3,3910.00,'','',0),(6,'9780553293371','Foundation and Empire',3,3716.00,'','',0),(7,'9780553293364','Second Foundation',3,3873.00,'','',0),(10,'9780743484862','As You Like It',4,3498.54,'','/assets/images/9780743484862.png',1);
 /*!40000 ALTER TABLE `books` ENABLE KEYS */;
 UNLOCK TABLES;
 
@@ -1441,6 +1680,100 @@ INSERT INTO `credit_rules` VALUES (1,10.0,0.00,200.00,1000.00),(2,15.0,0.00,500.
 /*!40000 ALTER TABLE `credit_rules` ENABLE KEYS */;
 UNLOCK TABLES;
 
+--
+-- Table structure for table `customer_refresh_tokens`
+--
+
+DROP TABLE IF EXISTS `customer_refresh_tokens`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `customer_refresh_tokens` (
+  `refresh_token_id` int unsigned NOT NULL AUTO_INCREMENT,
+  `session_id` int unsigned NOT NULL,
+  `token_hash` char(64) COLLATE utf8mb4_general_ci NOT NULL,
+  `created_at` datetime NOT NULL,
+  `used_at` datetime DEFAULT NULL,
+  PRIMARY KEY (`refresh_token_id`),
+  UNIQUE KEY `token_hash` (`token_hash`),
+  KEY `refresh_token_session_id` (`session_id`),
+  CONSTRAINT `refresh_token_session_id` FOREIGN KEY (`session_id`) REFERENCES `customer_sessions` (`session_id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `customer_refresh_tokens`
+--
+
+LOCK TABLES `customer_refresh_tokens` WRITE;
+/*!40000 ALTER TABLE `customer_refresh_tokens` DISABLE KEYS */;
+/*!40000 ALTER TABLE `customer_refresh_tokens` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `customer_sessions`
+--
+
+DROP TABLE IF EXISTS `customer_sessions`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `customer_sessions` (
+  `session_id` int unsigned NOT NULL AUTO_INCREMENT,
+  `customer_id` int unsigned NOT NULL,
+  `token` varchar(255) CHARACTER SET utf8mb4 COLLATE utf8mb4_general_ci NOT NULL,
+  `user_agent` varchar(255) COLLATE utf8mb4_general_ci NOT NULL DEFAULT '',
+  `created_at` datetime NOT NULL,
+  `last_used` datetime NOT NULL,
+  `expires_at` datetime NOT NULL,
+  `is_online` tinyint unsigned NOT NULL DEFAULT '1',
+  PRIMARY KEY (`session_id`),
+  UNIQUE KEY `token` (`token`),
+  KEY `customer_session_customer_id` (`customer_id`),
+  CONSTRAINT `customer_session_customer_id` FOREIGN KEY (`customer_id`) REFERENCES `customers` (`customer_id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `customer_sessions`
+--
+
+LOCK TABLES `customer_sessions` WRITE;
+/*!40000 ALTER TABLE `customer_sessions` DISABLE KEYS */;
+/*!40000 ALTER TABLE `customer_sessions` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `customer_status_history`
+--
+
+DROP TABLE IF EXISTS `customer_status_history`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `customer_status_history` (
+  `history_id` int unsigned NOT NULL AUTO_INCREMENT,
+  `customer_id` int unsigned NOT NULL,
+  `admin_id` int unsigned DEFAULT NULL,
+  `old_status` enum('active','cancelled','banned') COLLATE utf8mb4_general_ci NOT NULL,
+  `new_status` enum('active','cancelled','banned') COLLATE utf8mb4_general_ci NOT NULL,
+  `reason` varchar(255) COLLATE utf8mb4_general_ci NOT NULL DEFAULT '',
+  `expires_at` datetime DEFAULT NULL,
+  `created_at` datetime NOT NULL,
+  PRIMARY KEY (`history_id`),
+  KEY `status_history_customer_id` (`customer_id`),
+  KEY `status_history_admin_id` (`admin_id`),
+  CONSTRAINT `status_history_admin_id` FOREIGN KEY (`admin_id`) REFERENCES `admins` (`admin_id`) ON DELETE SET NULL ON UPDATE CASCADE,
+  CONSTRAINT `status_history_customer_id` FOREIGN KEY (`customer_id`) REFERENCES `customers` (`customer_id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `customer_status_history`
+--
+
+LOCK TABLES `customer_status_history` WRITE;
+/*!40000 ALTER TABLE `customer_status_history` DISABLE KEYS */;
+/*!40000 ALTER TABLE `customer_status_history` ENABLE KEYS */;
+UNLOCK TABLES;
+
 --
 -- Table structure for table `customers`
 --
@@ -1460,6 +1793,8 @@ CREATE TABLE `customers` (
   `total_purchase` decimal(10,2) unsigned NOT NULL DEFAULT '0.00',
   `overdraft_limit` decimal(10,2) unsigned NOT NULL DEFAULT '0.00',
   `status` enum('active','cancelled','banned') COLLATE utf8mb4_general_ci NOT NULL DEFAULT 'active',
+  `status_expires_at` datetime DEFAULT NULL,
+  `email_verified_at` datetime DEFAULT NULL,
   PRIMARY KEY (`customer_id`),
   UNIQUE KEY `username` (`username`),
   FULLTEXT KEY `username_full_text` (`username`) /*!50100 WITH PARSER `ngram` */ ,
@@ -1473,10 +1808,41 @@ CREATE TABLE `customers` (
 
 LOCK TABLES `customers` WRITE;
 /*!40000 ALTER TABLE `customers` DISABLE KEYS */;
-INSERT INTO `customers` VALUES (1,'root','6T6Z8vqX','root','','111@gmail.com',9744070.31,1,255929.69,0.00,'active'),(2,'reinerina','vnnYr6HAcdpAkw==','reinerina','Palais Mermonia','furina@fontaine.com',155008.37,1,44991.63,0.00,'active');
+INSERT INTO `customers` VALUES (1,'root','pbkdf2-sha256$600000$GXgwxY3eEE4jZvz56OFs9Q==$9LaMuQxNoDnPsAcGTGMWypSayK+0GTOkzfQCIHH1VWA=','root','','111@gmail.com',9744070.31,1,255929.69,0.00,'active',NULL,'2025-01-01 00:00:00'),(2,'reinerina','pbkdf2-sha256$600000$inVPkKpuuO96Cxqyd/L9CQ==$MJxHHBb+/9OTyyQtsAiboR63BiP3vdZQWvruWayepYc=','reinerina','Palais Mermonia','furina@fontaine.com',155008.37,1,44991.63,0.00,'active',NULL,'2025-01-01 00:00:00');
 /*!40000 ALTER TABLE `customers` ENABLE KEYS */;
 UNLOCK TABLES;
 
+--
+-- Table structure for table `email_verification_tokens`
+--
+
+DROP TABLE IF EXISTS `email_verification_tokens`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `email_verification_tokens` (
+  `verification_token_id` int unsigned NOT NULL AUTO_INCREMENT,
+  `customer_id` int unsigned NOT NULL,
+  `email` varchar(255) COLLATE utf8mb4_general_ci NOT NULL,
+  `token_hash` char(64) COLLATE utf8mb4_general_ci NOT NULL,
+  `created_at` datetime NOT NULL,
+  `expires_at` datetime NOT NULL,
+  `used_at` datetime DEFAULT NULL,
+  PRIMARY KEY (`verification_token_id`),
+  UNIQUE KEY `token_hash` (`token_hash`),
+  KEY `verification_token_customer_id` (`customer_id`),
+  CONSTRAINT `verification_token_customer_id` FOREIGN KEY (`customer_id`) REFERENCES `customers` (`customer_id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `email_verification_tokens`
+--
+
+LOCK TABLES `email_verification_tokens` WRITE;
+/*!40000 ALTER TABLE `email_verification_tokens` DISABLE KEYS */;
+/*!40000 ALTER TABLE `email_verification_tokens` ENABLE KEYS */;
+UNLOCK TABLES;
+
 --
 -- Table structure for table `keywords`
 --
@@ -1527,6 +1893,33 @@ INSERT INTO `loactions` VALUES (1,'Base');
 /*!40000 ALTER TABLE `loactions` ENABLE KEYS */;
 UNLOCK TABLES;
 
+--
+-- Table structure for table `login_failures`
+--
+
+DROP TABLE IF EXISTS `login_failures`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `login_failures` (
+  `scope` enum('customer','admin') COLLATE utf8mb4_general_ci NOT NULL,
+  `kind` enum('username','ip') COLLATE utf8mb4_general_ci NOT NULL,
+  `subject` varchar(255) COLLATE utf8mb4_general_ci NOT NULL,
+  `failures` int unsigned NOT NULL DEFAULT '0',
+  `last_failure` datetime NOT NULL,
+  `locked_until` datetime DEFAULT NULL,
+  PRIMARY KEY (`scope`,`kind`,`subject`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `login_failures`
+--
+
+LOCK TABLES `login_failures` WRITE;
+/*!40000 ALTER TABLE `login_failures` DISABLE KEYS */;
+/*!40000 ALTER TABLE `login_failures` ENABLE KEYS */;
+UNLOCK TABLES;
+
 --
 -- Table structure for table `order_items`
 --
@@ -1588,6 +1981,62 @@ INSERT INTO `orders` VALUES (1,1,'2024-12-12 16:34:00','','paid','pending'),(2,1
 /*!40000 ALTER TABLE `orders` ENABLE KEYS */;
 UNLOCK TABLES;
 
+--
+-- Table structure for table `password_reset_tokens`
+--
+
+DROP TABLE IF EXISTS `password_reset_tokens`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `password_reset_tokens` (
+  `reset_token_id` int unsigned NOT NULL AUTO_INCREMENT,
+  `customer_id` int unsigned NOT NULL,
+  `token_hash` char(64) COLLATE utf8mb4_general_ci NOT NULL,
+  `created_at` datetime NOT NULL,
+  `expires_at` datetime NOT NULL,
+  `used_at` datetime DEFAULT NULL,
+  PRIMARY KEY (`reset_token_id`),
+  UNIQUE KEY `token_hash` (`token_hash`),
+  KEY `reset_token_customer_id` (`customer_id`),
+  CONSTRAINT `reset_token_customer_id` FOREIGN KEY (`customer_id`) REFERENCES `customers` (`customer_id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `password_reset_tokens`
+--
+
+LOCK TABLES `password_reset_tokens` WRITE;
+/*!40000 ALTER TABLE `password_reset_tokens` DISABLE KEYS */;
+/*!40000 ALTER TABLE `password_reset_tokens` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `permissions`
+--
+
+DROP TABLE IF EXISTS `permissions`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `permissions` (
+  `permission_id` int unsigned NOT NULL AUTO_INCREMENT,
+  `permission_name` varchar(50) COLLATE utf8mb4_general_ci NOT NULL,
+  `description` varchar(255) COLLATE utf8mb4_general_ci NOT NULL DEFAULT '',
+  PRIMARY KEY (`permission_id`),
+  UNIQUE KEY `permission_name` (`permission_name`)
+) ENGINE=InnoDB AUTO_INCREMENT=20 DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `permissions`
+--
+
+LOCK TABLES `permissions` WRITE;
+/*!40000 ALTER TABLE `permissions` DISABLE KEYS */;
+INSERT INTO `permissions` VALUES (1,'catalog.read','View books and their stock locations'),(2,'catalog.write','Add and update books, authors, keywords and publishers'),(3,'stock.read','View stock locations'),(4,'stock.adjust','Change and transfer stock'),(5,'customer.read','List and search customers'),(6,'customer.balance','Set customer account balances'),(7,'customer.credit','Set customer credit levels'),(8,'customer.status','Change customer account status'),(9,'order.read','View customer orders'),(10,'order.ship','Ship orders and update shipping status'),(11,'shortage.read','View shortage records'),(12,'shortage.write','Create shortage records'),(13,'procurement.read','View purchase orders'),(14,'procurement.create','Create purchase orders'),(15,'procurement.approve','Confirm and complete purchase orders'),(16,'supplier.read','View supplier catalogs'),(17,'supplier.write','Add, update and delete suppliers'),(18,'admin.manage','Manage staff accounts, roles, sessions and lockouts'),(19,'audit.read','Query and verify the audit log');
+/*!40000 ALTER TABLE `permissions` ENABLE KEYS */;
+UNLOCK TABLES;
+
 --
 -- Table structure for table `price_inquiries`
 --
@@ -1696,6 +2145,59 @@ INSERT INTO `purchase_orders` VALUES (1,'2024-12-24 09:39:46','2024-12-31 09:39:
 /*!40000 ALTER TABLE `purchase_orders` ENABLE KEYS */;
 UNLOCK TABLES;
 
+--
+-- Table structure for table `role_permissions`
+--
+
+DROP TABLE IF EXISTS `role_permissions`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `role_permissions` (
+  `role_id` int unsigned NOT NULL,
+  `permission_id` int unsigned NOT NULL,
+  PRIMARY KEY (`role_id`,`permission_id`),
+  KEY `role_permission_permission_id` (`permission_id`),
+  CONSTRAINT `role_permission_permission_id` FOREIGN KEY (`permission_id`) REFERENCES `permissions` (`permission_id`) ON DELETE CASCADE ON UPDATE CASCADE,
+  CONSTRAINT `role_permission_role_id` FOREIGN KEY (`role_id`) REFERENCES `roles` (`role_id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `role_permissions`
+--
+
+LOCK TABLES `role_permissions` WRITE;
+/*!40000 ALTER TABLE `role_permissions` DISABLE KEYS */;
+INSERT INTO `role_permissions` VALUES (1,1),(1,2),(1,3),(1,4),(1,5),(1,6),(1,7),(1,8),(1,9),(1,10),(1,11),(1,12),(1,13),(1,14),(1,15),(1,16),(1,17),(1,18),(1,19),(2,1),(2,2),(2,3),(2,4),(2,5),(2,9),(2,10),(2,11),(2,12),(2,13),(2,14),(2,16);
+/*!40000 ALTER TABLE `role_permissions` ENABLE KEYS */;
+UNLOCK TABLES;
+
+--
+-- Table structure for table `roles`
+--
+
+DROP TABLE IF EXISTS `roles`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `roles` (
+  `role_id` int unsigned NOT NULL AUTO_INCREMENT,
+  `role_name` varchar(50) COLLATE utf8mb4_general_ci NOT NULL,
+  `description` varchar(255) COLLATE utf8mb4_general_ci NOT NULL DEFAULT '',
+  PRIMARY KEY (`role_id`),
+  UNIQUE KEY `role_name` (`role_name`)
+) ENGINE=InnoDB AUTO_INCREMENT=3 DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `roles`
+--
+
+LOCK TABLES `roles` WRITE;
+/*!40000 ALTER TABLE `roles` DISABLE KEYS */;
+INSERT INTO `roles` VALUES (1,'admin','Full access to every administrative operation'),(2,'staff','Day-to-day catalog, stock, order and procurement work');
+/*!40000 ALTER TABLE `roles` ENABLE KEYS */;
+UNLOCK TABLES;
+
 --
 -- Table structure for table `series`
 --
@@ -1748,6 +2250,30 @@ INSERT INTO `series_books` VALUES (1,4,1),(1,6,2),(1,7,3);
 /*!40000 ALTER TABLE `series_books` ENABLE KEYS */;
 UNLOCK TABLES;
 
+--
+-- Table structure for table `settings`
+--
+
+DROP TABLE IF EXISTS `settings`;
+/*!40101 SET @saved_cs_client     = @@character_set_client */;
+/*!50503 SET character_set_client = utf8mb4 */;
+CREATE TABLE `settings` (
+  `setting_name` varchar(64) COLLATE utf8mb4_general_ci NOT NULL,
+  `setting_value` varchar(255) COLLATE utf8mb4_general_ci NOT NULL,
+  PRIMARY KEY (`setting_name`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+/*!40101 SET character_set_client = @saved_cs_client */;
+
+--
+-- Dumping data for table `settings`
+--
+
+LOCK TABLES `settings` WRITE;
+/*!40000 ALTER TABLE `settings` DISABLE KEYS */;
+INSERT INTO `settings` VALUES ('admin_mfa_required','false');
+/*!40000 ALTER TABLE `settings` ENABLE KEYS */;
+UNLOCK TABLES;
+
 --
 -- Table structure for table `shortages`
 --
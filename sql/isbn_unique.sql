@@ -0,0 +1,45 @@
+-- Upgrade an existing bookstore database to unique ISBNs.
+--
+-- sql/bookstore.sql now declares `UNIQUE KEY isbn` on books and ships
+-- books 6 and 7 with their correct ISBNs (9780553293371, 9780553293364);
+-- older dumps gave both the same value. The server stores and looks up
+-- every ISBN as a 13-digit ISBN-13. Run these steps in order.
+
+-- 1. Strip separators so the same ISBN written two ways compares equal.
+UPDATE books SET isbn = UPPER(REPLACE(REPLACE(isbn, '-', ''), ' ', ''));
+
+-- 2. Convert valid ISBN-10 values to their 978-prefixed ISBN-13.
+UPDATE books
+SET isbn = CONCAT('978', LEFT(isbn, 9), MOD(10 - MOD(38
+    + 3 * SUBSTRING(isbn, 1, 1) + SUBSTRING(isbn, 2, 1)
+    + 3 * SUBSTRING(isbn, 3, 1) + SUBSTRING(isbn, 4, 1)
+    + 3 * SUBSTRING(isbn, 5, 1) + SUBSTRING(isbn, 6, 1)
+    + 3 * SUBSTRING(isbn, 7, 1) + SUBSTRING(isbn, 8, 1)
+    + 3 * SUBSTRING(isbn, 9, 1), 10), 10))
+WHERE isbn REGEXP '^[0-9]{9}[0-9X]$'
+AND MOD(10 * SUBSTRING(isbn, 1, 1) + 9 * SUBSTRING(isbn, 2, 1)
+    + 8 * SUBSTRING(isbn, 3, 1) + 7 * SUBSTRING(isbn, 4, 1)
+    + 6 * SUBSTRING(isbn, 5, 1) + 5 * SUBSTRING(isbn, 6, 1)
+    + 4 * SUBSTRING(isbn, 7, 1) + 3 * SUBSTRING(isbn, 8, 1)
+    + 2 * SUBSTRING(isbn, 9, 1) + IF(RIGHT(isbn, 1) = 'X', 10, RIGHT(isbn, 1)), 11) = 0;
+
+-- 3. Fix the two seed books if they still share an ISBN.
+UPDATE books SET isbn = '9780553293371' WHERE book_id = 6 AND isbn = '9780593499573';
+UPDATE books SET isbn = '9780553293364' WHERE book_id = 7 AND isbn = '9780593499573';
+
+-- 4. List rows that are still not a 13-digit ISBN, such as ISBN-10 values
+--    with a wrong check digit. Correct them by hand until this returns no
+--    rows; they cannot be found by ISBN lookup otherwise.
+SELECT book_id, isbn
+FROM books
+WHERE isbn NOT REGEXP '^97[89][0-9]{10}$';
+
+-- 5. List any remaining duplicates. Give each extra book its real ISBN
+--    (or merge it into the first one) until this returns no rows.
+SELECT isbn, GROUP_CONCAT(book_id ORDER BY book_id) AS book_ids
+FROM books
+GROUP BY isbn
+HAVING COUNT(*) > 1;
+
+-- 6. Enforce uniqueness.
+ALTER TABLE books ADD UNIQUE KEY `isbn` (`isbn`) USING BTREE;
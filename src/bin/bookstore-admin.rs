@@ -0,0 +1,82 @@
+use bookstore::repo::AdminRepo;
+use bookstore::service::AdminService;
+use bookstore::utils::{database_opts_from_env, generate_secret};
+use mysql_async::{Conn, Pool};
+use std::env;
+
+const USAGE: &str =
+    "usage: bookstore-admin --username <username> [--password <password>] [--force]";
+const PASSWORD_ENV: &str = "BOOKSTORE_ADMIN_PASSWORD";
+
+struct Args {
+    username: String,
+    password: Option<String>,
+    force: bool,
+}
+
+fn parse_args() -> anyhow::Result<Args> {
+    let mut username = None;
+    let mut password = env::var(PASSWORD_ENV).ok();
+    let mut force = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--username" => username = args.next(),
+            "--password" => password = args.next(),
+            "--force" => force = true,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            _ => anyhow::bail!("unexpected argument {}\n{}", arg, USAGE),
+        }
+    }
+
+    match username {
+        Some(username) if !username.is_empty() => Ok(Args {
+            username,
+            password,
+            force,
+        }),
+        _ => anyhow::bail!("missing --username\n{}", USAGE),
+    }
+}
+
+async fn create_admin(conn: &mut Conn, args: &Args, password: &str) -> anyhow::Result<u32> {
+    if !args.force && AdminRepo::get_admin_count(conn).await? > 0 {
+        anyhow::bail!("an admin account already exists, pass --force to create another");
+    }
+    AdminService::bootstrap(conn, &args.username, password).await
+}
+
+async fn run() -> anyhow::Result<()> {
+    let args = parse_args()?;
+    let (password, generated) = match args.password.clone() {
+        Some(password) if !password.is_empty() => (password, false),
+        _ => (generate_secret(), true),
+    };
+
+    let pool = Pool::new(database_opts_from_env());
+    let mut conn = pool.get_conn().await?;
+    let result = create_admin(&mut conn, &args, &password).await;
+    drop(conn);
+    pool.disconnect().await?;
+
+    let admin_id = result?;
+    println!("created admin {} with id {}", args.username, admin_id);
+    if generated {
+        println!("password: {}", password);
+    }
+    Ok(())
+}
+
+#[actix_web::main]
+async fn main() {
+    pretty_env_logger::init();
+
+    if let Err(e) = run().await {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
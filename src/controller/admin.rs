@@ -1,6 +1,13 @@
-use crate::service::{AdminService, StockService};
-use crate::utils::Token;
-use actix_web::{post, web, HttpResponse, Responder};
+use crate::controller::auth::{client_ip, login_failed};
+use crate::entity::{AdminRole, AdminStatus, AuditFilter, AuthedAdmin, Permission, UserStatus};
+use crate::service::{
+    AdminLogin, AdminService, ApiKeyService, AuditService, AuthService, LoginLocked, MfaService,
+    MfaSetup, RoleService, StockService, UserService,
+};
+use crate::utils::{cover_rendition, Audience, CoverStore};
+use actix_multipart::form::bytes::Bytes;
+use actix_multipart::form::MultipartForm;
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
 use mysql_async::Pool;
 use serde::{Deserialize, Serialize};
 
@@ -9,67 +16,869 @@ struct AdminRegisterRequest {
     username: String,
     password: String,
     role: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AdminRegisterResponse {
+    admin_id: u32,
+}
+
+#[post("/admin/register")]
+pub async fn admin_register(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    admin_register_request: web::Json<AdminRegisterRequest>,
+) -> impl Responder {
+    let request = admin_register_request.into_inner();
+    let username = &request.username;
+    let password = &request.password;
+    let role = &request.role;
+
+    let mut conn = pool.get_conn().await.unwrap();
+
+    match AdminService::register(&mut conn, username, password, role.parse().unwrap(), &admin).await
+    {
+        Ok(admin_id) => HttpResponse::Ok().json(AdminRegisterResponse { admin_id }),
+        Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AdminLoginRequest {
+    username: String,
+    password: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AdminLoginResponse {
+    token: Option<String>,
+    mfa_required: bool,
+    mfa_enrollment_required: bool,
+    challenge: Option<String>,
+}
+
+#[post("/admin/login")]
+pub async fn admin_login(
+    pool: web::Data<Pool>,
+    req: HttpRequest,
+    admin_login_request: web::Json<AdminLoginRequest>,
+) -> impl Responder {
+    let request = admin_login_request.into_inner();
+    let username = &request.username;
+    let password = &request.password;
+    let ip = &client_ip(&req);
+    match pool.get_conn().await {
+        Ok(mut conn) => match AdminService::login(&mut conn, username, password, ip).await {
+            Ok(AdminLogin::Token(token)) => HttpResponse::Ok().json(AdminLoginResponse {
+                token: Some(token.to_string()),
+                mfa_required: false,
+                mfa_enrollment_required: false,
+                challenge: None,
+            }),
+            Ok(AdminLogin::MfaRequired(challenge)) => HttpResponse::Ok().json(AdminLoginResponse {
+                token: None,
+                mfa_required: true,
+                mfa_enrollment_required: false,
+                challenge: Some(challenge),
+            }),
+            Ok(AdminLogin::MfaEnrollmentRequired(challenge)) => {
+                HttpResponse::Ok().json(AdminLoginResponse {
+                    token: None,
+                    mfa_required: true,
+                    mfa_enrollment_required: true,
+                    challenge: Some(challenge),
+                })
+            }
+            Err(e) => login_failed(e),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct AdminLoginMfaRequest {
+    challenge: String,
+    code: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AdminLoginMfaResponse {
     token: String,
-    tag: String,
-    nonce: String,
+    recovery_codes: Option<Vec<String>>,
+}
+
+#[post("/admin/login/mfa")]
+pub async fn admin_login_mfa(
+    pool: web::Data<Pool>,
+    req: HttpRequest,
+    admin_login_mfa_request: web::Json<AdminLoginMfaRequest>,
+) -> impl Responder {
+    let challenge = &admin_login_mfa_request.challenge;
+    let code = &admin_login_mfa_request.code;
+    let ip = &client_ip(&req);
+    match pool.get_conn().await {
+        Ok(mut conn) => match MfaService::complete_login(&mut conn, challenge, code, ip).await {
+            Ok((token, recovery_codes)) => HttpResponse::Ok().json(AdminLoginMfaResponse {
+                token: token.to_string(),
+                recovery_codes,
+            }),
+            Err(e) if e.is::<LoginLocked>() => login_failed(e),
+            Err(e) => HttpResponse::Unauthorized().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct MfaSetupResponse {
+    secret: String,
+    otpauth_uri: String,
+}
+
+impl From<MfaSetup> for MfaSetupResponse {
+    fn from(setup: MfaSetup) -> Self {
+        MfaSetupResponse {
+            secret: setup.secret,
+            otpauth_uri: setup.uri,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AdminLoginMfaSetupRequest {
+    challenge: String,
+}
+
+#[post("/admin/login/mfa/setup")]
+pub async fn admin_login_mfa_setup(
+    pool: web::Data<Pool>,
+    admin_login_mfa_setup_request: web::Json<AdminLoginMfaSetupRequest>,
+) -> impl Responder {
+    let challenge = &admin_login_mfa_setup_request.challenge;
+    match pool.get_conn().await {
+        Ok(mut conn) => match MfaService::setup_login_mfa(&mut conn, challenge).await {
+            Ok(setup) => HttpResponse::Ok().json(MfaSetupResponse::from(setup)),
+            Err(e) => HttpResponse::Unauthorized().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct MfaStatusResponse {
+    enabled: bool,
+    required: bool,
+    recovery_codes_remaining: u64,
+}
+
+#[get("/admin/mfa")]
+pub async fn admin_mfa_status(pool: web::Data<Pool>, admin: AuthedAdmin) -> impl Responder {
+    match pool.get_conn().await {
+        Ok(mut conn) => match MfaService::get_mfa_status(&mut conn, &admin).await {
+            Ok((enabled, required, recovery_codes_remaining)) => {
+                HttpResponse::Ok().json(MfaStatusResponse {
+                    enabled,
+                    required,
+                    recovery_codes_remaining,
+                })
+            }
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[post("/admin/mfa/setup")]
+pub async fn admin_mfa_setup(pool: web::Data<Pool>, admin: AuthedAdmin) -> impl Responder {
+    match pool.get_conn().await {
+        Ok(mut conn) => match MfaService::setup_mfa(&mut conn, &admin).await {
+            Ok(setup) => HttpResponse::Ok().json(MfaSetupResponse::from(setup)),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct MfaCodeRequest {
+    code: String,
+}
+
+#[derive(Debug, Serialize)]
+struct MfaRecoveryCodesResponse {
+    recovery_codes: Vec<String>,
+}
+
+#[post("/admin/mfa/enable")]
+pub async fn admin_mfa_enable(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    mfa_code_request: web::Json<MfaCodeRequest>,
+) -> impl Responder {
+    let code = &mfa_code_request.code;
+    match pool.get_conn().await {
+        Ok(mut conn) => match MfaService::enable_mfa(&mut conn, &admin, code).await {
+            Ok(recovery_codes) => {
+                HttpResponse::Ok().json(MfaRecoveryCodesResponse { recovery_codes })
+            }
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct MfaMessageResponse {
+    message: String,
+}
+
+#[post("/admin/mfa/disable")]
+pub async fn admin_mfa_disable(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    mfa_code_request: web::Json<MfaCodeRequest>,
+) -> impl Responder {
+    let code = &mfa_code_request.code;
+    match pool.get_conn().await {
+        Ok(mut conn) => match MfaService::disable_mfa(&mut conn, &admin, code).await {
+            Ok(_) => HttpResponse::Ok().json(MfaMessageResponse {
+                message: "two-factor authentication disabled".to_string(),
+            }),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[post("/admin/mfa/recovery_codes")]
+pub async fn admin_mfa_recovery_codes(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    mfa_code_request: web::Json<MfaCodeRequest>,
+) -> impl Responder {
+    let code = &mfa_code_request.code;
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match MfaService::regenerate_recovery_codes(&mut conn, &admin, code).await {
+                Ok(recovery_codes) => {
+                    HttpResponse::Ok().json(MfaRecoveryCodesResponse { recovery_codes })
+                }
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct MfaPolicyRequest {
+    required: bool,
+}
+
+#[post("/admin/mfa/policy")]
+pub async fn admin_mfa_policy(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    mfa_policy_request: web::Json<MfaPolicyRequest>,
+) -> impl Responder {
+    let required = mfa_policy_request.required;
+    match pool.get_conn().await {
+        Ok(mut conn) => match MfaService::set_mfa_required(&mut conn, &admin, required).await {
+            Ok(_) => HttpResponse::Ok().json(MfaMessageResponse {
+                message: match required {
+                    true => "two-factor authentication is now mandatory".to_string(),
+                    false => "two-factor authentication is now optional".to_string(),
+                },
+            }),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[post("/admin/staff/{id}/mfa/reset")]
+pub async fn admin_staff_mfa_reset(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
+) -> impl Responder {
+    let admin_id = id.into_inner().0;
+    match pool.get_conn().await {
+        Ok(mut conn) => match MfaService::reset_admin_mfa(&mut conn, &admin, admin_id).await {
+            Ok(_) => HttpResponse::Ok().json(MfaMessageResponse {
+                message: format!("two-factor authentication reset for admin {}", admin_id),
+            }),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AdminDetailResponse {
+    admin_id: u32,
+    username: String,
+    role: String,
+    permissions: Vec<String>,
+}
+
+#[get("/admin/detail")]
+pub async fn admin_detail(pool: web::Data<Pool>, admin: AuthedAdmin) -> impl Responder {
+    let mut conn = pool.get_conn().await.unwrap();
+
+    match AdminService::get_admin_detail(&mut conn, &admin).await {
+        Ok(detail) => HttpResponse::Ok().json(AdminDetailResponse {
+            admin_id: detail.id,
+            username: detail.username,
+            role: detail.role.to_string(),
+            permissions: admin
+                .permissions
+                .iter()
+                .map(|permission| permission.to_string())
+                .collect(),
+        }),
+        Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AdminLogoutResponse {
+    message: String,
+}
+
+#[post("/admin/logout")]
+pub async fn admin_logout(pool: web::Data<Pool>, admin: AuthedAdmin) -> impl Responder {
+    match pool.get_conn().await {
+        Ok(mut conn) => match AuthService::logout_admin(&mut conn, &admin).await {
+            Ok(_) => HttpResponse::Ok().json(AdminLogoutResponse {
+                message: "logout successfully".to_string(),
+            }),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AdminSessionItemResponse {
+    session_id: u32,
+    created_at: String,
+    last_used: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AdminSessionListResponse {
+    admin_id: u32,
+    sessions: Vec<AdminSessionItemResponse>,
+}
+
+#[get("/admin/staff/{id}/sessions")]
+pub async fn admin_session_list(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
+) -> impl Responder {
+    let admin_id = id.into_inner().0;
+    match pool.get_conn().await {
+        Ok(mut conn) => match AuthService::get_admin_sessions(&mut conn, &admin, admin_id).await {
+            Ok(sessions) => HttpResponse::Ok().json(AdminSessionListResponse {
+                admin_id,
+                sessions: sessions
+                    .into_iter()
+                    .map(|session| AdminSessionItemResponse {
+                        session_id: session.id,
+                        created_at: session.created_at.to_string(),
+                        last_used: session.last_used.to_string(),
+                    })
+                    .collect(),
+            }),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AdminSessionRevokeResponse {
+    message: String,
+}
+
+#[post("/admin/session/{id}/revoke")]
+pub async fn admin_session_revoke(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
+) -> impl Responder {
+    let session_id = id.into_inner().0;
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match AuthService::revoke_admin_session(&mut conn, &admin, session_id).await {
+                Ok(_) => HttpResponse::Ok().json(AdminSessionRevokeResponse {
+                    message: "session revoked".to_string(),
+                }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[post("/admin/staff/{id}/sessions/revoke")]
+pub async fn admin_session_revoke_all(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
+) -> impl Responder {
+    let admin_id = id.into_inner().0;
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match AuthService::revoke_admin_sessions(&mut conn, &admin, admin_id).await {
+                Ok(_) => HttpResponse::Ok().json(AdminSessionRevokeResponse {
+                    message: "all sessions revoked".to_string(),
+                }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct AdminLoginUnlockRequest {
+    audience: String,
+    username: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AdminLoginUnlockResponse {
+    message: String,
+}
+
+#[post("/admin/login/unlock")]
+pub async fn admin_login_unlock(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    admin_login_unlock_request: web::Json<AdminLoginUnlockRequest>,
+) -> impl Responder {
+    let request = admin_login_unlock_request.into_inner();
+    let audience = match request.audience.parse::<Audience>() {
+        Ok(audience) => audience,
+        Err(e) => return HttpResponse::BadRequest().json(e.to_string()),
+    };
+    let username = &request.username;
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match AuthService::unlock_login(&mut conn, &admin, audience, username).await {
+                Ok(_) => HttpResponse::Ok().json(AdminLoginUnlockResponse {
+                    message: format!("{} {} unlocked", audience, username),
+                }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct PermissionListResponse {
+    permissions: Vec<String>,
+}
+
+#[get("/admin/permission/list")]
+pub async fn admin_permission_list(_admin: AuthedAdmin) -> impl Responder {
+    HttpResponse::Ok().json(PermissionListResponse {
+        permissions: Permission::ALL
+            .iter()
+            .map(|permission| permission.to_string())
+            .collect(),
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct RoleDetailResponse {
+    role_id: u32,
+    name: String,
+    description: String,
+    permissions: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct RoleListResponse {
+    roles: Vec<RoleDetailResponse>,
+}
+
+#[get("/admin/role/list")]
+pub async fn admin_role_list(pool: web::Data<Pool>, admin: AuthedAdmin) -> impl Responder {
+    match pool.get_conn().await {
+        Ok(mut conn) => match RoleService::get_role_list(&mut conn, &admin).await {
+            Ok(roles) => HttpResponse::Ok().json(RoleListResponse {
+                roles: roles
+                    .into_iter()
+                    .map(|role| RoleDetailResponse {
+                        role_id: role.id,
+                        name: role.name,
+                        description: role.description,
+                        permissions: role
+                            .permissions
+                            .iter()
+                            .map(|permission| permission.to_string())
+                            .collect(),
+                    })
+                    .collect(),
+            }),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct RoleSaveRequest {
+    name: String,
+    description: String,
+    permissions: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct RoleSaveResponse {
+    role_id: u32,
+}
+
+#[post("/admin/role/save")]
+pub async fn admin_role_save(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    role_save_request: web::Json<RoleSaveRequest>,
+) -> impl Responder {
+    let request = role_save_request.into_inner();
+    let name = &request.name;
+    let description = &request.description;
+    let permissions = match request
+        .permissions
+        .iter()
+        .map(|permission| permission.parse::<Permission>())
+        .collect::<anyhow::Result<Vec<_>>>()
+    {
+        Ok(permissions) => permissions,
+        Err(e) => return HttpResponse::BadRequest().json(e.to_string()),
+    };
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match RoleService::save_role(&mut conn, &admin, name, description, &permissions).await {
+                Ok(role_id) => HttpResponse::Ok().json(RoleSaveResponse { role_id }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AdminRolesResponse {
+    admin_id: u32,
+    roles: Vec<String>,
+}
+
+#[get("/admin/staff/{id}/roles")]
+pub async fn admin_roles(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
+) -> impl Responder {
+    let admin_id = id.into_inner().0;
+    match pool.get_conn().await {
+        Ok(mut conn) => match RoleService::get_admin_roles(&mut conn, &admin, admin_id).await {
+            Ok(roles) => HttpResponse::Ok().json(AdminRolesResponse { admin_id, roles }),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct AdminRolesUpdateRequest {
+    roles: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct AdminRolesUpdateResponse {
+    message: String,
+}
+
+#[post("/admin/staff/{id}/roles")]
+pub async fn admin_roles_update(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
+    admin_roles_update_request: web::Json<AdminRolesUpdateRequest>,
+) -> impl Responder {
+    let admin_id = id.into_inner().0;
+    let roles = &admin_roles_update_request.roles;
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match RoleService::set_admin_roles(&mut conn, &admin, admin_id, roles).await {
+                Ok(_) => HttpResponse::Ok().json(AdminRolesUpdateResponse {
+                    message: "admin roles updated".to_string(),
+                }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct AdminPasswordChangeRequest {
+    old_password: String,
+    new_password: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AdminPasswordChangeResponse {
+    message: String,
+}
+
+#[post("/admin/password/change")]
+pub async fn admin_password_change(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    admin_password_change_request: web::Json<AdminPasswordChangeRequest>,
+) -> impl Responder {
+    let old_password = &admin_password_change_request.old_password;
+    let new_password = &admin_password_change_request.new_password;
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match AdminService::change_password(&mut conn, &admin, old_password, new_password).await
+            {
+                Ok(_) => HttpResponse::Ok().json(AdminPasswordChangeResponse {
+                    message: "password changed, other sessions logged out".to_string(),
+                }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AdminStaffItemResponse {
+    admin_id: u32,
+    username: String,
+    status: String,
+    role: String,
 }
 
 #[derive(Debug, Serialize)]
-struct AdminRegisterResponse {
+struct AdminStaffListResponse {
+    staff: Vec<AdminStaffItemResponse>,
+}
+
+#[get("/admin/staff/list")]
+pub async fn admin_staff_list(pool: web::Data<Pool>, admin: AuthedAdmin) -> impl Responder {
+    match pool.get_conn().await {
+        Ok(mut conn) => match AdminService::get_admin_list(&mut conn, &admin).await {
+            Ok(staff) => HttpResponse::Ok().json(AdminStaffListResponse {
+                staff: staff
+                    .into_iter()
+                    .map(|staff| AdminStaffItemResponse {
+                        admin_id: staff.id,
+                        username: staff.username,
+                        status: staff.status.to_string(),
+                        role: staff.role.to_string(),
+                    })
+                    .collect(),
+            }),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AdminStaffStatusResponse {
+    message: String,
+}
+
+async fn update_staff_status(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
     admin_id: u32,
+    status: AdminStatus,
+) -> HttpResponse {
+    let message = format!("admin {} is now {}", admin_id, status);
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match AdminService::update_admin_status(&mut conn, &admin, admin_id, status).await {
+                Ok(_) => HttpResponse::Ok().json(AdminStaffStatusResponse { message }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
 }
 
-#[post("/admin/register")]
-pub async fn admin_register(
+#[post("/admin/staff/{id}/deactivate")]
+pub async fn admin_staff_deactivate(
     pool: web::Data<Pool>,
-    admin_register_request: web::Json<AdminRegisterRequest>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
 ) -> impl Responder {
-    let request = admin_register_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
+    update_staff_status(pool, admin, id.into_inner().0, AdminStatus::Cancelled).await
+}
+
+#[post("/admin/staff/{id}/reactivate")]
+pub async fn admin_staff_reactivate(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
+) -> impl Responder {
+    update_staff_status(pool, admin, id.into_inner().0, AdminStatus::Active).await
+}
+
+#[derive(Deserialize)]
+struct AdminStaffRoleRequest {
+    role: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AdminStaffRoleResponse {
+    message: String,
+}
+
+#[post("/admin/staff/{id}/role")]
+pub async fn admin_staff_role(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
+    admin_staff_role_request: web::Json<AdminStaffRoleRequest>,
+) -> impl Responder {
+    let admin_id = id.into_inner().0;
+    let role = match admin_staff_role_request.role.as_str() {
+        "admin" => AdminRole::Admin,
+        "staff" => AdminRole::Staff,
+        role => return HttpResponse::BadRequest().json(format!("invalid role: {}", role)),
     };
-    let username = &request.username;
-    let password = &request.password;
-    let role = &request.role;
+    let message = format!("admin {} is now {}", admin_id, role);
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match AdminService::update_admin_role(&mut conn, &admin, admin_id, role).await {
+                Ok(_) => HttpResponse::Ok().json(AdminStaffRoleResponse { message }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
 
-    let mut conn = pool.get_conn().await.unwrap();
+#[derive(Debug, Serialize)]
+struct AdminStaffPasswordResetResponse {
+    admin_id: u32,
+    temporary_password: String,
+}
 
-    match AdminService::register(&mut conn, username, password, role.parse().unwrap(), token).await
-    {
-        Ok(admin_id) => HttpResponse::Ok().json(AdminRegisterResponse { admin_id }),
-        Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+#[post("/admin/staff/{id}/password/reset")]
+pub async fn admin_staff_password_reset(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
+) -> impl Responder {
+    let admin_id = id.into_inner().0;
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match AdminService::reset_admin_password(&mut conn, &admin, admin_id).await {
+                Ok(temporary_password) => {
+                    HttpResponse::Ok().json(AdminStaffPasswordResetResponse {
+                        admin_id,
+                        temporary_password,
+                    })
+                }
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct AdminLoginRequest {
-    username: String,
-    password: String,
+#[derive(Deserialize)]
+struct ApiKeyCreateRequest {
+    name: String,
+    scopes: Vec<String>,
+    expires_at: String,
 }
 
 #[derive(Debug, Serialize)]
-struct AdminLoginResponse {
-    token: String,
-    tag: String,
-    nonce: String,
+struct ApiKeyCreateResponse {
+    api_key_id: u32,
+    key: String,
 }
 
-#[post("/admin/login")]
-pub async fn admin_login(
+#[post("/admin/api_key/create")]
+pub async fn admin_api_key_create(
     pool: web::Data<Pool>,
-    admin_login_request: web::Json<AdminLoginRequest>,
+    admin: AuthedAdmin,
+    api_key_create_request: web::Json<ApiKeyCreateRequest>,
 ) -> impl Responder {
-    let request = admin_login_request.into_inner();
-    let username = &request.username;
-    let password = &request.password;
+    let request = api_key_create_request.into_inner();
+    match pool.get_conn().await {
+        Ok(mut conn) => match ApiKeyService::create_api_key(
+            &mut conn,
+            &admin,
+            &request.name,
+            &request.scopes,
+            &request.expires_at,
+        )
+        .await
+        {
+            Ok((api_key_id, key)) => {
+                HttpResponse::Ok().json(ApiKeyCreateResponse { api_key_id, key })
+            }
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ApiKeyItemResponse {
+    api_key_id: u32,
+    admin_id: u32,
+    name: String,
+    prefix: String,
+    scopes: Vec<String>,
+    created_at: String,
+    expires_at: String,
+    last_used_at: Option<String>,
+    revoked_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ApiKeyListResponse {
+    api_keys: Vec<ApiKeyItemResponse>,
+}
+
+#[get("/admin/api_key/list")]
+pub async fn admin_api_key_list(pool: web::Data<Pool>, admin: AuthedAdmin) -> impl Responder {
     match pool.get_conn().await {
-        Ok(mut conn) => match AdminService::login(&mut conn, username, password).await {
-            Ok(token) => HttpResponse::Ok().json(AdminLoginResponse {
-                token: token.token,
-                tag: token.tag,
-                nonce: token.nonce,
+        Ok(mut conn) => match ApiKeyService::get_api_key_list(&mut conn, &admin).await {
+            Ok(api_keys) => HttpResponse::Ok().json(ApiKeyListResponse {
+                api_keys: api_keys
+                    .into_iter()
+                    .map(|api_key| ApiKeyItemResponse {
+                        api_key_id: api_key.id,
+                        admin_id: api_key.admin_id,
+                        name: api_key.name,
+                        prefix: api_key.prefix,
+                        scopes: api_key
+                            .permissions
+                            .iter()
+                            .map(|permission| permission.to_string())
+                            .collect(),
+                        created_at: api_key.created_at.to_string(),
+                        expires_at: api_key.expires_at.to_string(),
+                        last_used_at: api_key.last_used_at.map(|at| at.to_string()),
+                        revoked_at: api_key.revoked_at.map(|at| at.to_string()),
+                    })
+                    .collect(),
             }),
             Err(e) => HttpResponse::BadRequest().json(e.to_string()),
         },
@@ -77,41 +886,131 @@ pub async fn admin_login(
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct AdminDetailRequest {
-    token: String,
-    tag: String,
-    nonce: String,
+#[derive(Debug, Serialize)]
+struct ApiKeyRevokeResponse {
+    message: String,
+}
+
+#[post("/admin/api_key/{id}/revoke")]
+pub async fn admin_api_key_revoke(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
+) -> impl Responder {
+    let api_key_id = id.into_inner().0;
+    match pool.get_conn().await {
+        Ok(mut conn) => match ApiKeyService::revoke_api_key(&mut conn, &admin, api_key_id).await {
+            Ok(_) => HttpResponse::Ok().json(ApiKeyRevokeResponse {
+                message: format!("api key {} revoked", api_key_id),
+            }),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct AuditListRequest {
+    admin_id: Option<u32>,
+    api_key_id: Option<u32>,
+    action: Option<String>,
+    entity_type: Option<String>,
+    entity_id: Option<u32>,
+    from: Option<String>,
+    to: Option<String>,
+    page: Option<u64>,
+    page_size: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
-struct AdminDetailResponse {
+struct AuditLogResponse {
+    audit_log_id: u32,
     admin_id: u32,
-    username: String,
-    role: String,
+    api_key_id: Option<u32>,
+    action: String,
+    entity_type: String,
+    entity_id: u32,
+    before: Option<String>,
+    after: Option<String>,
+    created_at: String,
+    prev_hash: String,
+    hash: String,
 }
 
-#[post("/admin/detail")]
-pub async fn admin_detail(
+#[derive(Debug, Serialize)]
+struct AuditListResponse {
+    total: u64,
+    page: u64,
+    logs: Vec<AuditLogResponse>,
+}
+
+#[get("/admin/audit/list")]
+pub async fn admin_audit_list(
     pool: web::Data<Pool>,
-    admin_detail_request: web::Json<AdminDetailRequest>,
+    admin: AuthedAdmin,
+    audit_list_request: web::Query<AuditListRequest>,
 ) -> impl Responder {
-    let request = admin_detail_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
+    let request = audit_list_request.into_inner();
+    let filter = AuditFilter {
+        admin_id: request.admin_id,
+        api_key_id: request.api_key_id,
+        action: request.action,
+        entity_type: request.entity_type,
+        entity_id: request.entity_id,
+        from: request.from,
+        to: request.to,
     };
+    let page = request.page.unwrap_or(1).max(1);
+    let page_size = request.page_size.unwrap_or(20);
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match AuditService::get_audit_logs(&mut conn, &admin, &filter, page, page_size).await {
+                Ok((logs, total)) => HttpResponse::Ok().json(AuditListResponse {
+                    total,
+                    page,
+                    logs: logs
+                        .into_iter()
+                        .map(|log| AuditLogResponse {
+                            audit_log_id: log.id,
+                            admin_id: log.admin_id,
+                            api_key_id: log.api_key_id,
+                            action: log.action,
+                            entity_type: log.entity_type,
+                            entity_id: log.entity_id,
+                            before: log.before_value,
+                            after: log.after_value,
+                            created_at: log.created_at.to_string(),
+                            prev_hash: log.prev_hash,
+                            hash: log.hash,
+                        })
+                        .collect(),
+                }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
 
-    let mut conn = pool.get_conn().await.unwrap();
+#[derive(Debug, Serialize)]
+struct AuditVerifyResponse {
+    valid: bool,
+    checked: u64,
+    broken_at: Option<u32>,
+}
 
-    match AdminService::get_admin_detail(&mut conn, token).await {
-        Ok(admin) => HttpResponse::Ok().json(AdminDetailResponse {
-            admin_id: admin.id,
-            username: admin.username,
-            role: admin.role.to_string(),
-        }),
-        Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+#[get("/admin/audit/verify")]
+pub async fn admin_audit_verify(pool: web::Data<Pool>, admin: AuthedAdmin) -> impl Responder {
+    match pool.get_conn().await {
+        Ok(mut conn) => match AuditService::verify_chain(&mut conn, &admin).await {
+            Ok((checked, broken_at)) => HttpResponse::Ok().json(AuditVerifyResponse {
+                valid: broken_at.is_none(),
+                checked,
+                broken_at,
+            }),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
     }
 }
 
@@ -175,30 +1074,17 @@ struct BookDetailResponse {
     is_onstore: bool,
 }
 
-#[derive(Debug, Deserialize)]
-struct BookDetailRequest {
-    token: String,
-    tag: String,
-    nonce: String,
-    book_id: u32,
-}
-
-#[post("/admin/book/detail")]
+#[get("/admin/book/{id}/detail")]
 pub async fn admin_book_detail(
     pool: web::Data<Pool>,
-    book_detail_request: web::Json<BookDetailRequest>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
 ) -> impl Responder {
-    let request = book_detail_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
-    let book_id = request.book_id;
+    let book_id = id.into_inner().0;
 
     let mut conn = pool.get_conn().await.unwrap();
 
-    match AdminService::get_book_detail(&mut conn, token, book_id).await {
+    match AdminService::get_book_detail(&mut conn, &admin, book_id).await {
         Ok((book, locations)) => HttpResponse::Ok().json(BookDetailResponse {
             book_id: book.id,
             isbn: book.isbn,
@@ -262,13 +1148,6 @@ pub async fn admin_book_detail(
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct LocationListRequest {
-    token: String,
-    tag: String,
-    nonce: String,
-}
-
 #[derive(Debug, Serialize)]
 struct LocationListItemResponse {
     id: u32,
@@ -280,21 +1159,11 @@ struct LocationListResponse {
     locations: Vec<LocationListItemResponse>,
 }
 
-#[post("/admin/location/list")]
-pub async fn admin_location_list(
-    pool: web::Data<Pool>,
-    location_list_request: web::Json<LocationListRequest>,
-) -> impl Responder {
-    let request = location_list_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
-
+#[get("/admin/location/list")]
+pub async fn admin_location_list(pool: web::Data<Pool>, admin: AuthedAdmin) -> impl Responder {
     let mut conn = pool.get_conn().await.unwrap();
 
-    match StockService::get_location_list(&mut conn, token).await {
+    match StockService::get_location_list(&mut conn, &admin).await {
         Ok(locations) => HttpResponse::Ok().json(LocationListResponse {
             locations: locations
                 .into_iter()
@@ -310,9 +1179,6 @@ pub async fn admin_location_list(
 
 #[derive(Debug, Deserialize)]
 struct StockChangeRequest {
-    token: String,
-    tag: String,
-    nonce: String,
     book_id: u32,
     location_id: u32,
     quantity: i32,
@@ -326,21 +1192,17 @@ struct StockChangeResponse {
 #[post("/admin/stock/change")]
 pub async fn admin_stock_change(
     pool: web::Data<Pool>,
+    admin: AuthedAdmin,
     stock_change_request: web::Json<StockChangeRequest>,
 ) -> impl Responder {
     let request = stock_change_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
     let book_id = request.book_id;
     let location_id = request.location_id;
     let quantity = request.quantity;
 
     let mut conn = pool.get_conn().await.unwrap();
 
-    match StockService::change_stock(&mut conn, token, book_id, location_id, quantity).await {
+    match StockService::change_stock(&mut conn, &admin, book_id, location_id, quantity).await {
         Ok(_) => HttpResponse::Ok().json(StockChangeResponse {
             message: "stock change successfully".to_string(),
         }),
@@ -350,9 +1212,6 @@ pub async fn admin_stock_change(
 
 #[derive(Debug, Deserialize)]
 struct StockTransferRequest {
-    token: String,
-    tag: String,
-    nonce: String,
     book_id: u32,
     from_location_id: u32,
     to_location_id: u32,
@@ -367,14 +1226,10 @@ struct StockTransferResponse {
 #[post("/admin/stock/transfer")]
 pub async fn admin_stock_transfer(
     pool: web::Data<Pool>,
+    admin: AuthedAdmin,
     stock_transfer_request: web::Json<StockTransferRequest>,
 ) -> impl Responder {
     let request = stock_transfer_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
     let book_id = request.book_id;
     let from_location_id = request.from_location_id;
     let to_location_id = request.to_location_id;
@@ -384,7 +1239,7 @@ pub async fn admin_stock_transfer(
 
     match StockService::transfer_stock(
         &mut conn,
-        token,
+        &admin,
         book_id,
         from_location_id,
         to_location_id,
@@ -401,9 +1256,6 @@ pub async fn admin_stock_transfer(
 
 #[derive(Debug, Deserialize)]
 struct StockAutoRequest {
-    token: String,
-    tag: String,
-    nonce: String,
     book_id: u32,
     location_id: u32,
 }
@@ -416,20 +1268,16 @@ struct StockAutoResponse {
 #[post("/admin/stock/add")]
 pub async fn admin_stock_add(
     pool: web::Data<Pool>,
+    admin: AuthedAdmin,
     stock_auto_request: web::Json<StockAutoRequest>,
 ) -> impl Responder {
     let request = stock_auto_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
     let book_id = request.book_id;
     let location_id = request.location_id;
 
     let mut conn = pool.get_conn().await.unwrap();
 
-    match StockService::change_stock(&mut conn, token, book_id, location_id, 0).await {
+    match StockService::change_stock(&mut conn, &admin, book_id, location_id, 0).await {
         Ok(_) => HttpResponse::Ok().json(StockAutoResponse {
             message: "stock add automatically successfully".to_string(),
         }),
@@ -439,9 +1287,6 @@ pub async fn admin_stock_add(
 
 #[derive(Debug, Deserialize)]
 struct BookUpdateRequest {
-    token: String,
-    tag: String,
-    nonce: String,
     book_id: u32,
     isbn: String,
     title: String,
@@ -464,14 +1309,11 @@ struct BookUpdateResponse {
 #[post("/admin/book/update")]
 pub async fn admin_book_update(
     pool: web::Data<Pool>,
+    store: web::Data<CoverStore>,
+    admin: AuthedAdmin,
     book_update_request: web::Json<BookUpdateRequest>,
 ) -> impl Responder {
     let request = book_update_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
     let book_id = request.book_id;
     let isbn = &request.isbn;
     let title = &request.title;
@@ -489,7 +1331,8 @@ pub async fn admin_book_update(
 
     match AdminService::update_book(
         &mut conn,
-        token,
+        &admin,
+        store.get_ref(),
         book_id,
         isbn,
         title,
@@ -512,11 +1355,58 @@ pub async fn admin_book_update(
     }
 }
 
+#[derive(Debug, MultipartForm)]
+struct BookCoverForm {
+    #[multipart(limit = "5MiB")]
+    cover: Bytes,
+}
+
+#[derive(Debug, Serialize)]
+struct BookCoverResponse {
+    message: String,
+    cover: String,
+    medium: String,
+    thumbnail: String,
+}
+
+#[post("/admin/book/{id}/cover")]
+pub async fn admin_book_cover(
+    pool: web::Data<Pool>,
+    store: web::Data<CoverStore>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
+    form: MultipartForm<BookCoverForm>,
+) -> impl Responder {
+    let book_id = id.into_inner().0;
+    let cover = form.into_inner().cover;
+    let content_type = cover
+        .content_type
+        .map(|content_type| content_type.essence_str().to_string())
+        .unwrap_or_default();
+    let mut conn = pool.get_conn().await.unwrap();
+
+    match AdminService::update_book_cover(
+        &mut conn,
+        &admin,
+        store.get_ref(),
+        book_id,
+        &content_type,
+        cover.data,
+    )
+    .await
+    {
+        Ok(cover) => HttpResponse::Ok().json(BookCoverResponse {
+            message: "book cover update successfully".to_string(),
+            medium: cover_rendition(&cover, "medium"),
+            thumbnail: cover_rendition(&cover, "thumb"),
+            cover,
+        }),
+        Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct BookAddRequest {
-    token: String,
-    tag: String,
-    nonce: String,
     isbn: String,
     title: String,
     authors: Vec<u32>,
@@ -538,14 +1428,10 @@ struct BookAddResponse {
 #[post("/admin/book/add")]
 pub async fn admin_book_add(
     pool: web::Data<Pool>,
+    admin: AuthedAdmin,
     book_add_request: web::Json<BookAddRequest>,
 ) -> impl Responder {
     let request = book_add_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
     let isbn = &request.isbn;
     let title = &request.title;
     let authors = &request.authors;
@@ -562,7 +1448,7 @@ pub async fn admin_book_add(
 
     match AdminService::add_book(
         &mut conn,
-        token,
+        &admin,
         isbn,
         title,
         authors,
@@ -584,13 +1470,6 @@ pub async fn admin_book_add(
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct CustomerListRequest {
-    token: String,
-    tag: String,
-    nonce: String,
-}
-
 #[derive(Debug, Serialize)]
 struct CustomerListItemResponse {
     user_id: u32,
@@ -607,21 +1486,11 @@ struct CustomerListResponse {
     customers: Vec<CustomerListItemResponse>,
 }
 
-#[post("/admin/customer/list")]
-pub async fn admin_customer_list(
-    pool: web::Data<Pool>,
-    customer_list_request: web::Json<CustomerListRequest>,
-) -> impl Responder {
-    let request = customer_list_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
-
+#[get("/admin/customer/list")]
+pub async fn admin_customer_list(pool: web::Data<Pool>, admin: AuthedAdmin) -> impl Responder {
     let mut conn = pool.get_conn().await.unwrap();
 
-    match AdminService::get_customer_list(&mut conn, token).await {
+    match AdminService::get_customer_list(&mut conn, &admin).await {
         Ok(customers) => HttpResponse::Ok().json(CustomerListResponse {
             customers: customers
                 .into_iter()
@@ -642,9 +1511,6 @@ pub async fn admin_customer_list(
 
 #[derive(Debug, Deserialize)]
 struct CustomerBalanceRequest {
-    token: String,
-    tag: String,
-    nonce: String,
     user_id: u32,
     balance: String,
 }
@@ -657,20 +1523,16 @@ struct CustomerBalanceResponse {
 #[post("/admin/customer/balance")]
 pub async fn admin_customer_balance(
     pool: web::Data<Pool>,
+    admin: AuthedAdmin,
     customer_balance_request: web::Json<CustomerBalanceRequest>,
 ) -> impl Responder {
     let request = customer_balance_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
     let user_id = request.user_id;
     let balance = request.balance.parse().unwrap();
 
     let mut conn = pool.get_conn().await.unwrap();
 
-    match AdminService::set_customer_balance(&mut conn, token, user_id, balance).await {
+    match AdminService::set_customer_balance(&mut conn, &admin, user_id, balance).await {
         Ok(_) => HttpResponse::Ok().json(CustomerBalanceResponse {
             message: "customer balance set successfully".to_string(),
         }),
@@ -680,9 +1542,6 @@ pub async fn admin_customer_balance(
 
 #[derive(Debug, Deserialize)]
 struct CustomerCreditRequest {
-    token: String,
-    tag: String,
-    nonce: String,
     user_id: u32,
     credit_level: u32,
 }
@@ -695,20 +1554,16 @@ struct CustomerCreditResponse {
 #[post("/admin/customer/credit")]
 pub async fn admin_customer_credit(
     pool: web::Data<Pool>,
+    admin: AuthedAdmin,
     customer_credit_request: web::Json<CustomerCreditRequest>,
 ) -> impl Responder {
     let request = customer_credit_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
     let user_id = request.user_id;
     let credit_level = request.credit_level;
 
     let mut conn = pool.get_conn().await.unwrap();
 
-    match AdminService::set_customer_credit_level(&mut conn, token, user_id, credit_level).await {
+    match AdminService::set_customer_credit_level(&mut conn, &admin, user_id, credit_level).await {
         Ok(_) => HttpResponse::Ok().json(CustomerCreditResponse {
             message: "customer credit level set successfully".to_string(),
         }),
@@ -717,10 +1572,155 @@ pub async fn admin_customer_credit(
 }
 
 #[derive(Debug, Deserialize)]
-struct CustomerOrderListRequest {
-    token: String,
-    tag: String,
-    nonce: String,
+struct CustomerBanRequest {
+    reason: String,
+    expires_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CustomerStatusRequest {
+    reason: String,
+}
+
+#[derive(Debug, Serialize)]
+struct CustomerStatusResponse {
+    message: String,
+}
+
+async fn update_customer_status(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    customer_id: u32,
+    status: UserStatus,
+    reason: &str,
+    expires_at: Option<&str>,
+) -> HttpResponse {
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match UserService::update_user_status(
+                &mut conn,
+                &admin,
+                customer_id,
+                status,
+                reason,
+                expires_at,
+            )
+            .await
+            {
+                Ok(_) => HttpResponse::Ok().json(CustomerStatusResponse {
+                    message: format!("customer {} is now {}", customer_id, status),
+                }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[post("/admin/customer/{id}/ban")]
+pub async fn admin_customer_ban(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
+    customer_ban_request: web::Json<CustomerBanRequest>,
+) -> impl Responder {
+    let request = customer_ban_request.into_inner();
+    update_customer_status(
+        pool,
+        admin,
+        id.into_inner().0,
+        UserStatus::Banned,
+        &request.reason,
+        request.expires_at.as_deref(),
+    )
+    .await
+}
+
+#[post("/admin/customer/{id}/unban")]
+pub async fn admin_customer_unban(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
+    customer_status_request: web::Json<CustomerStatusRequest>,
+) -> impl Responder {
+    let request = customer_status_request.into_inner();
+    update_customer_status(
+        pool,
+        admin,
+        id.into_inner().0,
+        UserStatus::Active,
+        &request.reason,
+        None,
+    )
+    .await
+}
+
+#[post("/admin/customer/{id}/cancel")]
+pub async fn admin_customer_cancel(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
+    customer_status_request: web::Json<CustomerStatusRequest>,
+) -> impl Responder {
+    let request = customer_status_request.into_inner();
+    update_customer_status(
+        pool,
+        admin,
+        id.into_inner().0,
+        UserStatus::Cancelled,
+        &request.reason,
+        None,
+    )
+    .await
+}
+
+#[derive(Debug, Serialize)]
+struct CustomerStatusHistoryItemResponse {
+    history_id: u32,
+    admin_id: Option<u32>,
+    old_status: String,
+    new_status: String,
+    reason: String,
+    expires_at: Option<String>,
+    created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+struct CustomerStatusHistoryResponse {
+    customer_id: u32,
+    history: Vec<CustomerStatusHistoryItemResponse>,
+}
+
+#[get("/admin/customer/{id}/status/history")]
+pub async fn admin_customer_status_history(
+    pool: web::Data<Pool>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
+) -> impl Responder {
+    let customer_id = id.into_inner().0;
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match UserService::get_user_status_history(&mut conn, &admin, customer_id).await {
+                Ok(history) => HttpResponse::Ok().json(CustomerStatusHistoryResponse {
+                    customer_id,
+                    history: history
+                        .into_iter()
+                        .map(|change| CustomerStatusHistoryItemResponse {
+                            history_id: change.id,
+                            admin_id: change.admin_id,
+                            old_status: change.old_status.to_string(),
+                            new_status: change.new_status.to_string(),
+                            reason: change.reason,
+                            expires_at: change.expires_at.map(|expires_at| expires_at.to_string()),
+                            created_at: change.created_at.to_string(),
+                        })
+                        .collect(),
+                }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -750,21 +1750,11 @@ struct CustomerOrderListResponse {
     orders: Vec<CustomerOrderListItemResponse>,
 }
 
-#[post("/admin/order/list")]
-pub async fn admin_order_list(
-    pool: web::Data<Pool>,
-    custom_order_list_request: web::Json<CustomerOrderListRequest>,
-) -> impl Responder {
-    let request = custom_order_list_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
-
+#[get("/admin/order/list")]
+pub async fn admin_order_list(pool: web::Data<Pool>, admin: AuthedAdmin) -> impl Responder {
     let mut conn = pool.get_conn().await.unwrap();
 
-    match AdminService::get_customer_order_list(&mut conn, token).await {
+    match AdminService::get_customer_order_list(&mut conn, &admin).await {
         Ok(orders) => HttpResponse::Ok().json(CustomerOrderListResponse {
             orders: orders
                 .into_iter()
@@ -797,9 +1787,6 @@ pub async fn admin_order_list(
 
 #[derive(Debug, Deserialize)]
 struct ShipOrderAutoRequest {
-    token: String,
-    tag: String,
-    nonce: String,
     order_id: u32,
 }
 
@@ -811,19 +1798,15 @@ struct ShipOrderAutoResponse {
 #[post("/admin/order/ship/auto")]
 pub async fn admin_order_ship_auto(
     pool: web::Data<Pool>,
+    admin: AuthedAdmin,
     ship_order_auto_request: web::Json<ShipOrderAutoRequest>,
 ) -> impl Responder {
     let request = ship_order_auto_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
     let order_id = request.order_id;
 
     let mut conn = pool.get_conn().await.unwrap();
 
-    match AdminService::ship_order_auto(&mut conn, token, order_id).await {
+    match AdminService::ship_order_auto(&mut conn, &admin, order_id).await {
         Ok(_) => HttpResponse::Ok().json(ShipOrderAutoResponse {
             message: "order ship automatically successfully".to_string(),
         }),
@@ -831,13 +1814,6 @@ pub async fn admin_order_ship_auto(
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct ShortageListRequest {
-    token: String,
-    tag: String,
-    nonce: String,
-}
-
 #[derive(Debug, Serialize)]
 struct ShortageListItemResponse {
     shortage_id: u32,
@@ -850,21 +1826,11 @@ struct ShortageListResponse {
     shortages: Vec<ShortageListItemResponse>,
 }
 
-#[post("/admin/shortage/list")]
-pub async fn admin_shortage_list(
-    pool: web::Data<Pool>,
-    shortage_list_request: web::Json<ShortageListRequest>,
-) -> impl Responder {
-    let request = shortage_list_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
-
+#[get("/admin/shortage/list")]
+pub async fn admin_shortage_list(pool: web::Data<Pool>, admin: AuthedAdmin) -> impl Responder {
     let mut conn = pool.get_conn().await.unwrap();
 
-    match AdminService::get_shortage_list(&mut conn, token).await {
+    match AdminService::get_shortage_list(&mut conn, &admin).await {
         Ok(shortages) => HttpResponse::Ok().json(ShortageListResponse {
             shortages: shortages
                 .into_iter()
@@ -879,14 +1845,6 @@ pub async fn admin_shortage_list(
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct ShortageDetailRequest {
-    token: String,
-    tag: String,
-    nonce: String,
-    shortage_id: u32,
-}
-
 #[derive(Debug, Serialize)]
 struct ShortageDetailResponse {
     shortage_id: u32,
@@ -895,22 +1853,17 @@ struct ShortageDetailResponse {
     items: Vec<(u32, u32, u32, u32, u32)>,
 }
 
-#[post("/admin/shortage/detail")]
+#[get("/admin/shortage/{id}/detail")]
 pub async fn admin_shortage_detail(
     pool: web::Data<Pool>,
-    shortage_detail_request: web::Json<ShortageDetailRequest>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
 ) -> impl Responder {
-    let request = shortage_detail_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
-    let shortage_id = request.shortage_id;
+    let shortage_id = id.into_inner().0;
 
     let mut conn = pool.get_conn().await.unwrap();
 
-    match AdminService::get_shortage_detail(&mut conn, token, shortage_id).await {
+    match AdminService::get_shortage_detail(&mut conn, &admin, shortage_id).await {
         Ok(shortage) => HttpResponse::Ok().json(ShortageDetailResponse {
             shortage_id: shortage.id,
             registration_date: shortage.registration_date.to_string(),
@@ -935,9 +1888,6 @@ pub async fn admin_shortage_detail(
 
 #[derive(Debug, Deserialize)]
 struct UserSearchRequest {
-    token: String,
-    tag: String,
-    nonce: String,
     search: String,
     mode: String,
 }
@@ -958,23 +1908,19 @@ struct UserSearchResponse {
     users: Vec<UserSearchItemResponse>,
 }
 
-#[post("/admin/customer/search")]
+#[get("/admin/customer/search")]
 pub async fn admin_user_search(
     pool: web::Data<Pool>,
-    user_search_request: web::Json<UserSearchRequest>,
+    admin: AuthedAdmin,
+    user_search_request: web::Query<UserSearchRequest>,
 ) -> impl Responder {
     let request = user_search_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
     let search = &request.search;
     let mode = &request.mode;
 
     let mut conn = pool.get_conn().await.unwrap();
 
-    match AdminService::search_user(&mut conn, token, search, mode).await {
+    match AdminService::search_user(&mut conn, &admin, search, mode).await {
         Ok(users) => HttpResponse::Ok().json(UserSearchResponse {
             users: users
                 .into_iter()
@@ -0,0 +1,138 @@
+use crate::entity::{AuthedAdmin, AuthedCustomer};
+use crate::service::{AdminService, ApiKeyService, AuthService, LoginLocked};
+use crate::utils::{Token, API_KEY_PREFIX};
+use actix_web::dev::Payload;
+use actix_web::error::InternalError;
+use actix_web::http::header;
+use actix_web::{web, FromRequest, HttpRequest, HttpResponse};
+use mysql_async::{Conn, Pool};
+use serde::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+
+fn bearer(req: &HttpRequest) -> anyhow::Result<String> {
+    match req.headers().get(header::AUTHORIZATION) {
+        Some(value) => match value.to_str()?.strip_prefix("Bearer ") {
+            Some(token) => Ok(token.trim().to_string()),
+            None => anyhow::bail!("invalid authorization scheme, expected bearer token"),
+        },
+        None => anyhow::bail!("missing authorization header"),
+    }
+}
+
+fn bearer_token(req: &HttpRequest) -> anyhow::Result<Token> {
+    bearer(req)?.parse()
+}
+
+pub fn user_agent(req: &HttpRequest) -> String {
+    match req.headers().get(header::USER_AGENT) {
+        Some(value) => String::from_utf8_lossy(value.as_bytes())
+            .chars()
+            .take(255)
+            .collect(),
+        None => String::new(),
+    }
+}
+
+pub fn client_ip(req: &HttpRequest) -> String {
+    match req.peer_addr() {
+        Some(addr) => addr.ip().to_string(),
+        None => String::new(),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct LoginLockedResponse {
+    message: String,
+    retry_after: i64,
+}
+
+pub fn login_failed(e: anyhow::Error) -> HttpResponse {
+    match e.downcast_ref::<LoginLocked>() {
+        Some(locked) => HttpResponse::TooManyRequests()
+            .insert_header((header::RETRY_AFTER, locked.retry_after.to_string()))
+            .json(LoginLockedResponse {
+                message: locked.to_string(),
+                retry_after: locked.retry_after,
+            }),
+        None => HttpResponse::BadRequest().json(e.to_string()),
+    }
+}
+
+fn unauthorized(e: anyhow::Error) -> actix_web::Error {
+    InternalError::from_response(
+        e.to_string(),
+        HttpResponse::Unauthorized().json(e.to_string()),
+    )
+    .into()
+}
+
+async fn get_conn(pool: Option<web::Data<Pool>>) -> Result<Conn, actix_web::Error> {
+    match pool {
+        Some(pool) => match pool.get_conn().await {
+            Ok(conn) => Ok(conn),
+            Err(e) => Err(InternalError::from_response(
+                e.to_string(),
+                HttpResponse::BadGateway().json(e.to_string()),
+            )
+            .into()),
+        },
+        None => Err(actix_web::error::ErrorInternalServerError(
+            "database pool not configured",
+        )),
+    }
+}
+
+impl FromRequest for AuthedCustomer {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let token = bearer_token(req);
+        let pool = req.app_data::<web::Data<Pool>>().cloned();
+        Box::pin(async move {
+            let token = token.map_err(unauthorized)?;
+            let mut conn = get_conn(pool).await?;
+            AuthService::verify_user(&mut conn, &token)
+                .await
+                .map_err(unauthorized)
+        })
+    }
+}
+
+impl FromRequest for AuthedAdmin {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let credential = bearer(req);
+        let pool = req.app_data::<web::Data<Pool>>().cloned();
+        let method = req.method().to_string();
+        let path = req.path().to_string();
+        Box::pin(async move {
+            let credential = credential.map_err(unauthorized)?;
+            let mut conn = get_conn(pool).await?;
+            match credential.starts_with(API_KEY_PREFIX) {
+                true => {
+                    let admin = ApiKeyService::verify_api_key(&mut conn, &credential)
+                        .await
+                        .map_err(unauthorized)?;
+                    log::info!(
+                        "api key {} of {} used for {} {}",
+                        admin.api_key_id.unwrap_or_default(),
+                        admin.username,
+                        method,
+                        path
+                    );
+                    Ok(admin)
+                }
+                false => {
+                    let token = credential.parse::<Token>().map_err(unauthorized)?;
+                    AdminService::verify_admin(&mut conn, &token)
+                        .await
+                        .map_err(unauthorized)
+                }
+            }
+        })
+    }
+}
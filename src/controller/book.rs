@@ -1,6 +1,7 @@
-use crate::service::BookService;
-use crate::utils::Token;
-use actix_web::{post, web, HttpResponse, Responder};
+use crate::entity::{AuthedAdmin, AuthedCustomer, Book, BookFilter, BookSort};
+use crate::service::{BookService, SuggestService};
+use crate::utils::{format_isbn13, isbn13_to_isbn10, normalize_isbn};
+use actix_web::{get, post, web, HttpResponse, Responder};
 use mysql_async::Pool;
 use serde::{Deserialize, Serialize};
 
@@ -43,6 +44,8 @@ struct SeriesDetailResponse {
 struct BookDetailResponse {
     book_id: u32,
     isbn: String,
+    isbn10: Option<String>,
+    isbn_hyphenated: Option<String>,
     title: String,
     authors: Vec<AuthorDetailResponse>,
     publisher: PublisherDetailResponse,
@@ -55,60 +58,82 @@ struct BookDetailResponse {
     is_onstore: bool,
 }
 
+fn book_detail_response(book: Book) -> BookDetailResponse {
+    BookDetailResponse {
+        book_id: book.id,
+        isbn10: isbn13_to_isbn10(&book.isbn).ok(),
+        isbn_hyphenated: format_isbn13(&book.isbn),
+        isbn: book.isbn,
+        title: book.title,
+        authors: book
+            .authors
+            .into_iter()
+            .map(|author| AuthorDetailResponse {
+                author_id: author.id,
+                name: author.name,
+            })
+            .collect(),
+        publisher: PublisherDetailResponse {
+            publisher_id: book.publisher.id,
+            name: book.publisher.name,
+        },
+        suppliers: book
+            .suppliers
+            .into_iter()
+            .map(|supplier| SupplierDetailResponse {
+                supplier_id: supplier.id,
+                name: supplier.name,
+                telephone: supplier.telephone,
+                email: supplier.email,
+                address: supplier.address,
+                fax: supplier.fax,
+            })
+            .collect(),
+        in_series: book
+            .in_series
+            .into_iter()
+            .map(|series| SeriesDetailResponse {
+                series_id: series.series_id,
+                name: series.title,
+                column: series.column,
+            })
+            .collect(),
+        price: book.price.to_string(),
+        keywords: book
+            .keywords
+            .into_iter()
+            .map(|keyword| KeywordDetailResponse {
+                keyword_id: keyword.id,
+                keyword: keyword.keyword,
+            })
+            .collect(),
+        catalog: book.catalog,
+        cover: book.cover,
+        is_onstore: book.is_onstore,
+    }
+}
+
 #[post("/book/{id}/detail")]
 pub async fn book_detail(pool: web::Data<Pool>, id: web::Path<(u32,)>) -> impl Responder {
     let mut conn = pool.get_conn().await.unwrap();
     match BookService::get_book_detail(&mut conn, id.into_inner().0).await {
-        Ok(book) => HttpResponse::Ok().json(BookDetailResponse {
-            book_id: book.id,
-            isbn: book.isbn,
-            title: book.title,
-            authors: book
-                .authors
-                .into_iter()
-                .map(|author| AuthorDetailResponse {
-                    author_id: author.id,
-                    name: author.name,
-                })
-                .collect(),
-            publisher: PublisherDetailResponse {
-                publisher_id: book.publisher.id,
-                name: book.publisher.name,
-            },
-            suppliers: book
-                .suppliers
-                .into_iter()
-                .map(|supplier| SupplierDetailResponse {
-                    supplier_id: supplier.id,
-                    name: supplier.name,
-                    telephone: supplier.telephone,
-                    email: supplier.email,
-                    address: supplier.address,
-                    fax: supplier.fax,
-                })
-                .collect(),
-            in_series: book
-                .in_series
-                .into_iter()
-                .map(|series| SeriesDetailResponse {
-                    series_id: series.series_id,
-                    name: series.title,
-                    column: series.column,
-                })
-                .collect(),
-            price: book.price.to_string(),
-            keywords: book
-                .keywords
-                .into_iter()
-                .map(|keyword| KeywordDetailResponse {
-                    keyword_id: keyword.id,
-                    keyword: keyword.keyword,
-                })
-                .collect(),
-            catalog: book.catalog,
-            cover: book.cover,
-            is_onstore: book.is_onstore,
-        }),
+        Ok(book) => HttpResponse::Ok().json(book_detail_response(book)),
+        Err(e) => HttpResponse::BadGateway().body(e.to_string()),
+    }
+}
+
+#[get("/book/isbn/{isbn}")]
+pub async fn book_isbn_lookup(pool: web::Data<Pool>, isbn: web::Path<(String,)>) -> impl Responder {
+    let isbn = match normalize_isbn(&isbn.into_inner().0) {
+        Ok(isbn) => isbn,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    match pool.get_conn().await {
+        Ok(mut conn) => match BookService::get_book_by_isbn(&mut conn, &isbn).await {
+            Ok(Some(book)) => HttpResponse::Ok().json(book_detail_response(book)),
+            Ok(None) => HttpResponse::NotFound().body(format!("book with isbn {} not found", isbn)),
+            Err(e) => HttpResponse::BadGateway().body(e.to_string()),
+        },
         Err(e) => HttpResponse::BadGateway().body(e.to_string()),
     }
 }
@@ -153,68 +178,107 @@ struct BookListItemResponse {
     is_onstore: bool,
 }
 
+#[derive(Debug, Deserialize)]
+struct BookListRequest {
+    publisher_id: Option<u32>,
+    author_id: Option<u32>,
+    keyword_id: Option<u32>,
+    series_id: Option<u32>,
+    min_price: Option<String>,
+    max_price: Option<String>,
+    in_stock: Option<bool>,
+    sort: Option<String>,
+    page: Option<u64>,
+    page_size: Option<u64>,
+}
+
 #[derive(Debug, Serialize)]
 struct BookListResponse {
+    total: u64,
+    page: u64,
     books: Vec<BookListItemResponse>,
 }
 
-#[post("/book/list")]
-pub async fn book_list(pool: web::Data<Pool>) -> impl Responder {
+#[get("/book/list")]
+pub async fn book_list(
+    pool: web::Data<Pool>,
+    book_list_request: web::Query<BookListRequest>,
+) -> impl Responder {
+    let request = book_list_request.into_inner();
+    let filter = BookFilter {
+        publisher_id: request.publisher_id,
+        author_id: request.author_id,
+        keyword_id: request.keyword_id,
+        series_id: request.series_id,
+        min_price: request.min_price,
+        max_price: request.max_price,
+        in_stock: request.in_stock.unwrap_or(false),
+        onstore_only: true,
+    };
+    let sort = match request.sort.as_deref().map(str::parse::<BookSort>) {
+        Some(Ok(sort)) => sort,
+        Some(Err(e)) => return HttpResponse::BadRequest().body(e.to_string()),
+        None => BookSort::default(),
+    };
+    let page = request.page.unwrap_or(1).max(1);
+    let page_size = request.page_size.unwrap_or(20);
     match pool.get_conn().await {
-        Ok(mut conn) => match BookService::get_book_list(&mut conn).await {
-            Ok(books) => {
-                let books = books
-                    .into_iter()
-                    .map(|book| BookListItemResponse {
-                        book_id: book.id,
-                        isbn: book.isbn,
-                        title: book.title,
-                        authors: book
-                            .authors
-                            .iter()
-                            .map(|author| AuthorListItemResponse {
-                                author_id: author.id,
-                                name: author.name.clone(),
-                            })
-                            .collect(),
-                        publisher: PublisherListItemResponse {
-                            publisher_id: book.publisher.id,
-                            name: book.publisher.name,
-                        },
-                        suppliers: book
-                            .suppliers
-                            .into_iter()
-                            .map(|supplier| SupplierListItemResponse {
-                                supplier_id: supplier.id,
-                                name: supplier.name,
-                            })
-                            .collect(),
-                        in_series: book
-                            .in_series
-                            .into_iter()
-                            .map(|series| SeriesListItemResponse {
-                                series_id: series.series_id,
-                                name: series.title,
-                                column: series.column,
-                            })
-                            .collect(),
-                        price: book.price.to_string(),
-                        keywords: book
-                            .keywords
-                            .into_iter()
-                            .map(|keyword| KeywordListItemResponse {
-                                keyword_id: keyword.id,
-                                keyword: keyword.keyword,
-                            })
-                            .collect(),
-                        cover: book.cover,
-                        is_onstore: book.is_onstore,
-                    })
-                    .collect();
-                HttpResponse::Ok().json(BookListResponse { books })
+        Ok(mut conn) => {
+            match BookService::get_book_list(&mut conn, &filter, sort, page, page_size).await {
+                Ok((books, total)) => {
+                    let books = books
+                        .into_iter()
+                        .map(|book| BookListItemResponse {
+                            book_id: book.id,
+                            isbn: book.isbn,
+                            title: book.title,
+                            authors: book
+                                .authors
+                                .iter()
+                                .map(|author| AuthorListItemResponse {
+                                    author_id: author.id,
+                                    name: author.name.clone(),
+                                })
+                                .collect(),
+                            publisher: PublisherListItemResponse {
+                                publisher_id: book.publisher.id,
+                                name: book.publisher.name,
+                            },
+                            suppliers: book
+                                .suppliers
+                                .into_iter()
+                                .map(|supplier| SupplierListItemResponse {
+                                    supplier_id: supplier.id,
+                                    name: supplier.name,
+                                })
+                                .collect(),
+                            in_series: book
+                                .in_series
+                                .into_iter()
+                                .map(|series| SeriesListItemResponse {
+                                    series_id: series.series_id,
+                                    name: series.title,
+                                    column: series.column,
+                                })
+                                .collect(),
+                            price: book.price.to_string(),
+                            keywords: book
+                                .keywords
+                                .into_iter()
+                                .map(|keyword| KeywordListItemResponse {
+                                    keyword_id: keyword.id,
+                                    keyword: keyword.keyword,
+                                })
+                                .collect(),
+                            cover: book.cover,
+                            is_onstore: book.is_onstore,
+                        })
+                        .collect();
+                    HttpResponse::Ok().json(BookListResponse { total, page, books })
+                }
+                Err(e) => HttpResponse::BadRequest().body(e.to_string()),
             }
-            Err(e) => HttpResponse::BadGateway().body(e.to_string()),
-        },
+        }
         Err(e) => HttpResponse::BadGateway().body(e.to_string()),
     }
 }
@@ -252,9 +316,6 @@ pub async fn keyword_list(pool: web::Data<Pool>) -> impl Responder {
 #[derive(Debug, Deserialize)]
 struct KeywordAddRequest {
     keyword: String,
-    token: String,
-    tag: String,
-    nonce: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -265,17 +326,13 @@ struct KeywordAddResponse {
 #[post("/book/keyword/add")]
 pub async fn keyword_add(
     pool: web::Data<Pool>,
+    admin: AuthedAdmin,
     keyword_add_request: web::Json<KeywordAddRequest>,
 ) -> impl Responder {
     let request = keyword_add_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
     let keyword = &request.keyword;
     match pool.get_conn().await {
-        Ok(mut conn) => match BookService::add_keyword(&mut conn, token, keyword).await {
+        Ok(mut conn) => match BookService::add_keyword(&mut conn, &admin, keyword).await {
             Ok(keyword_id) => HttpResponse::Ok().json(KeywordAddResponse { keyword_id }),
             Err(e) => HttpResponse::BadRequest().body(e.to_string()),
         },
@@ -283,6 +340,211 @@ pub async fn keyword_add(
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct PriceInquiryCreateRequest {
+    book_title: String,
+    isbn: String,
+    expected_price: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PriceInquiryCreateResponse {
+    inquiry_id: u32,
+}
+
+#[post("/book/price_inquiry/create")]
+pub async fn price_inquiry_create(
+    pool: web::Data<Pool>,
+    customer: AuthedCustomer,
+    price_inquiry_create_request: web::Json<PriceInquiryCreateRequest>,
+) -> impl Responder {
+    let request = price_inquiry_create_request.into_inner();
+    let book_title = &request.book_title;
+    let isbn = &request.isbn;
+    let expected_price = &request.expected_price;
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match BookService::create_price_inquiry(
+                &mut conn,
+                &customer,
+                book_title,
+                isbn,
+                expected_price,
+            )
+            .await
+            {
+                Ok(inquiry_id) => {
+                    HttpResponse::Ok().json(PriceInquiryCreateResponse { inquiry_id })
+                }
+                Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().body(e.to_string()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BookSearchRequest {
+    q: String,
+    publisher_id: Option<u32>,
+    author_id: Option<u32>,
+    keyword_id: Option<u32>,
+    series_id: Option<u32>,
+    min_price: Option<String>,
+    max_price: Option<String>,
+    in_stock: Option<bool>,
+    page: Option<u64>,
+    page_size: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct BookSearchItemResponse {
+    book_id: u32,
+    isbn: String,
+    title: String,
+    authors: Vec<AuthorListItemResponse>,
+    publisher: PublisherListItemResponse,
+    suppliers: Vec<SupplierListItemResponse>,
+    in_series: Vec<SeriesListItemResponse>,
+    price: String,
+    keywords: Vec<KeywordListItemResponse>,
+    cover: String,
+    is_onstore: bool,
+    score: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct BookSearchResponse {
+    total: u64,
+    page: u64,
+    books: Vec<BookSearchItemResponse>,
+}
+
+#[post("/book/search")]
+pub async fn book_search(
+    pool: web::Data<Pool>,
+    book_search_request: web::Json<BookSearchRequest>,
+) -> impl Responder {
+    let request = book_search_request.into_inner();
+    let query = &request.q;
+    let filter = BookFilter {
+        publisher_id: request.publisher_id,
+        author_id: request.author_id,
+        keyword_id: request.keyword_id,
+        series_id: request.series_id,
+        min_price: request.min_price,
+        max_price: request.max_price,
+        in_stock: request.in_stock.unwrap_or(false),
+        onstore_only: true,
+    };
+    let page = request.page.unwrap_or(1).max(1);
+    let page_size = request.page_size.unwrap_or(20);
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match BookService::search_books(&mut conn, query, &filter, page, page_size).await {
+                Ok((books, total)) => {
+                    let books = books
+                        .into_iter()
+                        .map(|(book, score)| BookSearchItemResponse {
+                            book_id: book.id,
+                            isbn: book.isbn,
+                            title: book.title,
+                            authors: book
+                                .authors
+                                .into_iter()
+                                .map(|author| AuthorListItemResponse {
+                                    author_id: author.id,
+                                    name: author.name,
+                                })
+                                .collect(),
+                            publisher: PublisherListItemResponse {
+                                publisher_id: book.publisher.id,
+                                name: book.publisher.name,
+                            },
+                            suppliers: book
+                                .suppliers
+                                .into_iter()
+                                .map(|supplier| SupplierListItemResponse {
+                                    supplier_id: supplier.id,
+                                    name: supplier.name,
+                                })
+                                .collect(),
+                            in_series: book
+                                .in_series
+                                .into_iter()
+                                .map(|series| SeriesListItemResponse {
+                                    series_id: series.series_id,
+                                    name: series.title,
+                                    column: series.column,
+                                })
+                                .collect(),
+                            price: book.price.to_string(),
+                            keywords: book
+                                .keywords
+                                .into_iter()
+                                .map(|keyword| KeywordListItemResponse {
+                                    keyword_id: keyword.id,
+                                    keyword: keyword.keyword,
+                                })
+                                .collect(),
+                            cover: book.cover,
+                            is_onstore: book.is_onstore,
+                            score,
+                        })
+                        .collect();
+                    HttpResponse::Ok().json(BookSearchResponse { total, page, books })
+                }
+                Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().body(e.to_string()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BookSuggestRequest {
+    q: String,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct SuggestionItemResponse {
+    kind: String,
+    id: u32,
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+struct BookSuggestResponse {
+    suggestions: Vec<SuggestionItemResponse>,
+}
+
+#[get("/book/suggest")]
+pub async fn book_suggest(
+    pool: web::Data<Pool>,
+    book_suggest_request: web::Query<BookSuggestRequest>,
+) -> impl Responder {
+    let request = book_suggest_request.into_inner();
+    let query = &request.q;
+    let limit = request.limit.unwrap_or(8);
+    match pool.get_conn().await {
+        Ok(mut conn) => match SuggestService::suggest(&mut conn, query, limit).await {
+            Ok(suggestions) => HttpResponse::Ok().json(BookSuggestResponse {
+                suggestions: suggestions
+                    .into_iter()
+                    .map(|suggestion| SuggestionItemResponse {
+                        kind: suggestion.kind.to_string(),
+                        id: suggestion.id,
+                        text: suggestion.text,
+                    })
+                    .collect(),
+            }),
+            Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().body(e.to_string()),
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct BookTitleSearchRequest {
     title: String,
@@ -1,4 +1,5 @@
 mod admin;
+mod auth;
 mod book;
 mod index;
 mod order;
@@ -8,18 +9,32 @@ mod supplier;
 mod user;
 
 pub use admin::{
-    admin_book_add, admin_book_detail, admin_book_update, admin_customer_balance,
-    admin_customer_credit, admin_customer_list, admin_detail, admin_location_list, admin_login,
-    admin_order_list, admin_order_ship_auto, admin_register, admin_shortage_detail,
-    admin_shortage_list, admin_stock_change, admin_stock_transfer, admin_user_search,
+    admin_api_key_create, admin_api_key_list, admin_api_key_revoke, admin_audit_list,
+    admin_audit_verify, admin_book_add, admin_book_cover, admin_book_detail, admin_book_update,
+    admin_customer_balance, admin_customer_ban, admin_customer_cancel, admin_customer_credit,
+    admin_customer_list, admin_customer_status_history, admin_customer_unban, admin_detail,
+    admin_location_list, admin_login, admin_login_mfa, admin_login_mfa_setup, admin_login_unlock,
+    admin_logout, admin_mfa_disable, admin_mfa_enable, admin_mfa_policy, admin_mfa_recovery_codes,
+    admin_mfa_setup, admin_mfa_status, admin_order_list, admin_order_ship_auto,
+    admin_password_change, admin_permission_list, admin_register, admin_role_list, admin_role_save,
+    admin_roles, admin_roles_update, admin_session_list, admin_session_revoke,
+    admin_session_revoke_all, admin_shortage_detail, admin_shortage_list, admin_staff_deactivate,
+    admin_staff_list, admin_staff_mfa_reset, admin_staff_password_reset, admin_staff_reactivate,
+    admin_staff_role, admin_stock_add, admin_stock_change, admin_stock_transfer, admin_user_search,
 };
 pub use book::{
-    author_list, book_authors_search, book_detail, book_keywords_search, book_list,
-    book_title_search, keyword_list, publisher_list, series_list,
+    author_list, book_authors_search, book_detail, book_isbn_lookup, book_keywords_search,
+    book_list, book_search, book_suggest, book_title_search, keyword_add, keyword_list,
+    price_inquiry_create, publisher_list, series_list,
 };
 pub use index::homepage;
 pub use order::{order_create, order_detail, order_history, order_payment};
 pub use purchase_order::{purchase_order_create, purchase_order_detail, purchase_order_list};
 pub use shortage::shortage_create;
 pub use supplier::{supplier_list, supplier_profile};
-pub use user::{credit_rule, login, register, user_detail, user_logout, user_profile, user_update};
+pub use user::{
+    credit_rule, login, register, user_close, user_detail, user_email_verify,
+    user_email_verify_resend, user_export, user_logout, user_logout_all, user_password_change,
+    user_password_forgot, user_password_reset, user_profile, user_session_list,
+    user_session_revoke, user_token_refresh, user_update,
+};
@@ -1,14 +1,11 @@
-use crate::service::OrderService;
-use crate::utils::Token;
-use actix_web::{post, web, HttpResponse, Responder};
+use crate::entity::{AuthedAdmin, AuthedCustomer};
+use crate::service::{OrderRejected, OrderService};
+use actix_web::{get, post, web, HttpResponse, Responder};
 use mysql_async::Pool;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize)]
 struct OrderCreateRequest {
-    token: String,
-    tag: String,
-    nonce: String,
     items: Vec<(u32, u32)>,
 }
 
@@ -17,35 +14,53 @@ struct OrderCreateResponse {
     order_id: u32,
 }
 
+#[derive(Debug, Serialize)]
+struct OrderRejectedItemResponse {
+    book_id: u32,
+    error: String,
+}
+
+#[derive(Debug, Serialize)]
+struct OrderRejectedResponse {
+    message: String,
+    items: Vec<OrderRejectedItemResponse>,
+}
+
+fn order_create_failed(e: anyhow::Error) -> HttpResponse {
+    match e.downcast_ref::<OrderRejected>() {
+        Some(rejected) => HttpResponse::BadRequest().json(OrderRejectedResponse {
+            message: rejected.to_string(),
+            items: rejected
+                .items
+                .iter()
+                .map(|(book_id, error)| OrderRejectedItemResponse {
+                    book_id: *book_id,
+                    error: error.clone(),
+                })
+                .collect(),
+        }),
+        None => HttpResponse::BadRequest().json(e.to_string()),
+    }
+}
+
 #[post("/order/create")]
 pub async fn order_create(
     pool: web::Data<Pool>,
+    customer: AuthedCustomer,
     order_create_request: web::Json<OrderCreateRequest>,
 ) -> impl Responder {
     let request = order_create_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
     let items = &request.items;
 
     match pool.get_conn().await {
-        Ok(mut conn) => match OrderService::create_order(&mut conn, token, items).await {
+        Ok(mut conn) => match OrderService::create_order(&mut conn, &customer, items).await {
             Ok(order) => HttpResponse::Ok().json(OrderCreateResponse { order_id: order }),
-            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            Err(e) => order_create_failed(e),
         },
         Err(e) => HttpResponse::BadGateway().json(e.to_string()),
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct OrderHistoryRequest {
-    token: String,
-    tag: String,
-    nonce: String,
-}
-
 #[derive(Debug, Serialize)]
 struct OrderHistoryItemResponse {
     order_id: u32,
@@ -63,20 +78,10 @@ struct OrderHistoryResponse {
     orders: Vec<OrderHistoryItemResponse>,
 }
 
-#[post("/order/history")]
-pub async fn order_history(
-    pool: web::Data<Pool>,
-    order_history_request: web::Json<OrderHistoryRequest>,
-) -> impl Responder {
-    let request = order_history_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
-
+#[get("/order/history")]
+pub async fn order_history(pool: web::Data<Pool>, customer: AuthedCustomer) -> impl Responder {
     match pool.get_conn().await {
-        Ok(mut conn) => match OrderService::get_order_list(&mut conn, token).await {
+        Ok(mut conn) => match OrderService::get_order_list(&mut conn, &customer).await {
             Ok(orders) => HttpResponse::Ok().json(OrderHistoryResponse {
                 orders: orders
                     .iter()
@@ -98,13 +103,6 @@ pub async fn order_history(
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct OrderDetailRequest {
-    token: String,
-    tag: String,
-    nonce: String,
-}
-
 #[derive(Debug, Serialize)]
 struct PublisherDetailResponse {
     publisher_id: u32,
@@ -136,21 +134,15 @@ struct OrderDetailResponse {
     items: Vec<OrderItemResponse>,
 }
 
-#[post("/order/{id}/detail")]
+#[get("/order/{id}/detail")]
 pub async fn order_detail(
     pool: web::Data<Pool>,
-    order_detail_request: web::Json<OrderDetailRequest>,
+    customer: AuthedCustomer,
     id: web::Path<(u32,)>,
 ) -> impl Responder {
-    let request = order_detail_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
     match pool.get_conn().await {
         Ok(mut conn) => {
-            match OrderService::get_order_detail(&mut conn, token, id.into_inner().0).await {
+            match OrderService::get_order_detail(&mut conn, &customer, id.into_inner().0).await {
                 Ok((order, book)) => {
                     let mut order_items = Vec::new();
                     for item in book.into_iter().zip(order.items.iter()) {
@@ -190,9 +182,6 @@ pub async fn order_detail(
 
 #[derive(Debug, Deserialize)]
 struct OrderPaymentRequest {
-    token: String,
-    tag: String,
-    nonce: String,
     status: String,
 }
 
@@ -204,20 +193,16 @@ struct OrderPaymentResponse {
 #[post("/order/{id}/payment")]
 pub async fn order_payment(
     pool: web::Data<Pool>,
+    admin: AuthedAdmin,
     order_payment_request: web::Json<OrderPaymentRequest>,
     id: web::Path<(u32,)>,
 ) -> impl Responder {
     let request = order_payment_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
     match pool.get_conn().await {
         Ok(mut conn) => {
             match OrderService::update_order_payment_status(
                 &mut conn,
-                token,
+                &admin,
                 id.into_inner().0,
                 request.status.parse().unwrap(),
             )
@@ -1,16 +1,9 @@
+use crate::entity::AuthedAdmin;
 use crate::service::PurchaseOrderService;
-use crate::utils::Token;
-use actix_web::{post, web, HttpResponse, Responder};
+use actix_web::{get, post, web, HttpResponse, Responder};
 use mysql_async::Pool;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize)]
-struct PurchaseOrderListRequest {
-    token: String,
-    tag: String,
-    nonce: String,
-}
-
 #[derive(Debug, Serialize)]
 struct PurchaseOrderListItemResponse {
     purchase_order_id: u32,
@@ -25,46 +18,32 @@ struct PurchaseOrderListResponse {
     purchase_orders: Vec<PurchaseOrderListItemResponse>,
 }
 
-#[post("/purchase_order/list")]
-pub async fn purchase_order_list(
-    pool: web::Data<Pool>,
-    purchase_order_list_request: web::Json<PurchaseOrderListRequest>,
-) -> impl Responder {
-    let request = purchase_order_list_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
+#[get("/purchase_order/list")]
+pub async fn purchase_order_list(pool: web::Data<Pool>, admin: AuthedAdmin) -> impl Responder {
     match pool.get_conn().await {
-        Ok(mut conn) => match PurchaseOrderService::get_purchase_order_list(&mut conn, token).await
-        {
-            Ok(purchase_orders) => HttpResponse::Ok().json(PurchaseOrderListResponse {
-                purchase_orders: purchase_orders
-                    .iter()
-                    .map(|purchase_order| PurchaseOrderListItemResponse {
-                        purchase_order_id: purchase_order.id,
-                        order_date: purchase_order.order_date.to_string(),
-                        expected_delivery_date: purchase_order.expected_delivery_date.to_string(),
-                        status: purchase_order.status.to_string(),
-                        total_price: purchase_order.total_amount.to_string(),
-                    })
-                    .collect(),
-            }),
-            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
-        },
+        Ok(mut conn) => {
+            match PurchaseOrderService::get_purchase_order_list(&mut conn, &admin).await {
+                Ok(purchase_orders) => HttpResponse::Ok().json(PurchaseOrderListResponse {
+                    purchase_orders: purchase_orders
+                        .iter()
+                        .map(|purchase_order| PurchaseOrderListItemResponse {
+                            purchase_order_id: purchase_order.id,
+                            order_date: purchase_order.order_date.to_string(),
+                            expected_delivery_date: purchase_order
+                                .expected_delivery_date
+                                .to_string(),
+                            status: purchase_order.status.to_string(),
+                            total_price: purchase_order.total_amount.to_string(),
+                        })
+                        .collect(),
+                }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
         Err(e) => HttpResponse::BadGateway().json(e.to_string()),
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct PurchaseOrderDetailRequest {
-    token: String,
-    tag: String,
-    nonce: String,
-    purchase_order_id: u32,
-}
-
 #[derive(Debug, Serialize)]
 struct PurchaseOrderDetailItemResponse {
     supplier_catalog_id: u32,
@@ -89,22 +68,17 @@ struct PurchaseOrderDetailResponse {
     items: Vec<PurchaseOrderDetailItemResponse>,
 }
 
-#[post("/purchase_order/detail")]
+#[get("/purchase_order/{id}/detail")]
 pub async fn purchase_order_detail(
     pool: web::Data<Pool>,
-    purchase_order_detail_request: web::Json<PurchaseOrderDetailRequest>,
+    admin: AuthedAdmin,
+    id: web::Path<(u32,)>,
 ) -> impl Responder {
-    let request = purchase_order_detail_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
     match pool.get_conn().await {
         Ok(mut conn) => match PurchaseOrderService::get_purchase_order_detail(
             &mut conn,
-            token,
-            request.purchase_order_id,
+            &admin,
+            id.into_inner().0,
         )
         .await
         {
@@ -142,9 +116,6 @@ pub async fn purchase_order_detail(
 
 #[derive(Debug, Deserialize)]
 struct PurchaseOrderCreateRequest {
-    token: String,
-    tag: String,
-    nonce: String,
     shortage_id: u32,
 }
 
@@ -156,18 +127,18 @@ struct PurchaseOrderCreateResponse {
 #[post("/purchase_order/create")]
 pub async fn purchase_order_create(
     pool: web::Data<Pool>,
+    admin: AuthedAdmin,
     purchase_order_create_request: web::Json<PurchaseOrderCreateRequest>,
 ) -> impl Responder {
     let request = purchase_order_create_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
     match pool.get_conn().await {
         Ok(mut conn) => {
-            match PurchaseOrderService::create_purchase_order(&mut conn, token, request.shortage_id)
-                .await
+            match PurchaseOrderService::create_purchase_order(
+                &mut conn,
+                &admin,
+                request.shortage_id,
+            )
+            .await
             {
                 Ok(purchase_order_id) => {
                     HttpResponse::Ok().json(PurchaseOrderCreateResponse { purchase_order_id })
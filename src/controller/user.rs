@@ -1,6 +1,8 @@
+use crate::controller::auth::{client_ip, login_failed, user_agent};
+use crate::entity::AuthedCustomer;
 use crate::service::{AuthService, UserService};
-use crate::utils::Token;
-use actix_web::{post, web, HttpResponse, Responder};
+use crate::utils::{Audience, Mailer};
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
 use mysql_async::Pool;
 use serde::{Deserialize, Serialize};
 
@@ -13,26 +15,31 @@ struct LoginRequest {
 #[derive(Debug, Serialize)]
 struct LoginResponse {
     token: String,
-    tag: String,
-    nonce: String,
+    refresh_token: String,
+    expires_in: i64,
 }
 
 #[post("/user/login")]
 pub async fn login(
     pool: web::Data<Pool>,
+    req: HttpRequest,
     login_request: web::Json<LoginRequest>,
 ) -> impl Responder {
     let username = &login_request.username;
     let password = &login_request.password;
+    let user_agent = &user_agent(&req);
+    let ip = &client_ip(&req);
     match pool.get_conn().await {
-        Ok(mut conn) => match UserService::login(&mut conn, username, password).await {
-            Ok(token) => HttpResponse::Ok().json(LoginResponse {
-                token: token.token,
-                tag: token.tag,
-                nonce: token.nonce,
-            }),
-            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
-        },
+        Ok(mut conn) => {
+            match UserService::login(&mut conn, username, password, user_agent, ip).await {
+                Ok((token, refresh_token)) => HttpResponse::Ok().json(LoginResponse {
+                    token: token.to_string(),
+                    refresh_token,
+                    expires_in: Audience::Customer.validity(),
+                }),
+                Err(e) => login_failed(e),
+            }
+        }
         Err(e) => HttpResponse::BadGateway().json(e.to_string()),
     }
 }
@@ -42,41 +49,82 @@ struct RegisterRequest {
     username: String,
     password: String,
     name: String,
+    email: String,
 }
 
 #[derive(Debug, Serialize)]
 struct RegisterResponse {
     token: String,
-    tag: String,
-    nonce: String,
+    refresh_token: String,
+    expires_in: i64,
 }
 
 #[post("/user/register")]
 pub async fn register(
     pool: web::Data<Pool>,
+    mailer: web::Data<dyn Mailer>,
+    req: HttpRequest,
     register_request: web::Json<RegisterRequest>,
 ) -> impl Responder {
     let username = &register_request.username;
     let password = &register_request.password;
     let name = &register_request.name;
+    let email = &register_request.email;
+    let user_agent = &user_agent(&req);
     match pool.get_conn().await {
-        Ok(mut conn) => match UserService::register(&mut conn, username, password, name).await {
-            Ok(token) => HttpResponse::Ok().json(RegisterResponse {
-                token: token.token,
-                tag: token.tag,
-                nonce: token.nonce,
-            }),
-            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
-        },
+        Ok(mut conn) => {
+            match UserService::register(
+                &mut conn,
+                mailer.get_ref(),
+                username,
+                password,
+                name,
+                email,
+                user_agent,
+            )
+            .await
+            {
+                Ok((token, refresh_token)) => HttpResponse::Ok().json(RegisterResponse {
+                    token: token.to_string(),
+                    refresh_token,
+                    expires_in: Audience::Customer.validity(),
+                }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
         Err(e) => HttpResponse::BadGateway().json(e.to_string()),
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct CreditRuleRequest {
+#[derive(Deserialize)]
+struct TokenRefreshRequest {
+    refresh_token: String,
+}
+
+#[derive(Debug, Serialize)]
+struct TokenRefreshResponse {
     token: String,
-    tag: String,
-    nonce: String,
+    refresh_token: String,
+    expires_in: i64,
+}
+
+#[post("/user/token/refresh")]
+pub async fn user_token_refresh(
+    pool: web::Data<Pool>,
+    token_refresh_request: web::Json<TokenRefreshRequest>,
+) -> impl Responder {
+    let refresh_token = &token_refresh_request.refresh_token;
+    match pool.get_conn().await {
+        Ok(mut conn) => match AuthService::refresh_user_session(&mut conn, refresh_token).await {
+            Ok((token, refresh_token)) => HttpResponse::Ok().json(TokenRefreshResponse {
+                token: token.to_string(),
+                refresh_token,
+                expires_in: Audience::Customer.validity(),
+            }),
+            Err(e) => HttpResponse::Unauthorized().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -88,19 +136,10 @@ struct CreditRuleResponse {
     auto_upgrade_total_purchase: String,
 }
 
-#[post("/user/credit_rule")]
-pub async fn credit_rule(
-    pool: web::Data<Pool>,
-    credit_rule_request: web::Json<CreditRuleRequest>,
-) -> impl Responder {
-    let request = credit_rule_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
+#[get("/user/credit_rule")]
+pub async fn credit_rule(pool: web::Data<Pool>, customer: AuthedCustomer) -> impl Responder {
     match pool.get_conn().await {
-        Ok(mut conn) => match UserService::get_credit_rule(&mut conn, token).await {
+        Ok(mut conn) => match UserService::get_credit_rule(&mut conn, &customer).await {
             Ok(credit_rule) => HttpResponse::Ok().json(CreditRuleResponse {
                 credit_level: credit_rule.level,
                 discount_percentage: credit_rule.discount_percentage.to_string(),
@@ -114,43 +153,29 @@ pub async fn credit_rule(
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct UserDetailRequest {
-    token: String,
-    tag: String,
-    nonce: String,
-}
-
 #[derive(Debug, Serialize)]
 struct UserDetailResponse {
     username: String,
     name: String,
     address: String,
     email: String,
+    email_verified: bool,
     account_balance: String,
     credit_level: u32,
     total_purchase: String,
     overdraft_limit: String,
 }
 
-#[post("/user/detail")]
-pub async fn user_detail(
-    pool: web::Data<Pool>,
-    user_detail_request: web::Json<UserDetailRequest>,
-) -> impl Responder {
-    let request = user_detail_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
+#[get("/user/detail")]
+pub async fn user_detail(pool: web::Data<Pool>, customer: AuthedCustomer) -> impl Responder {
     match pool.get_conn().await {
-        Ok(mut conn) => match UserService::get_user_detail(&mut conn, token).await {
+        Ok(mut conn) => match UserService::get_user_detail(&mut conn, &customer).await {
             Ok(user) => HttpResponse::Ok().json(UserDetailResponse {
                 username: user.username,
                 name: user.name,
                 address: user.address,
                 email: user.email,
+                email_verified: user.email_verified_at.is_some(),
                 account_balance: user.account_balance.to_string(),
                 credit_level: user.credit_level,
                 total_purchase: user.total_purchase.to_string(),
@@ -170,7 +195,7 @@ struct UserProfileResponse {
     credit_level: u32,
 }
 
-#[post("/user/{username}/profile")]
+#[get("/user/{username}/profile")]
 pub async fn user_profile(pool: web::Data<Pool>, username: web::Path<(String,)>) -> impl Responder {
     let username = &username.into_inner().0;
     match pool.get_conn().await {
@@ -187,31 +212,15 @@ pub async fn user_profile(pool: web::Data<Pool>, username: web::Path<(String,)>)
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct UserLogoutRequest {
-    token: String,
-    tag: String,
-    nonce: String,
-}
-
 #[derive(Debug, Serialize)]
 struct UserLogoutResponse {
     message: String,
 }
 
 #[post("/user/logout")]
-pub async fn user_logout(
-    pool: web::Data<Pool>,
-    user_logout_request: web::Json<UserLogoutRequest>,
-) -> impl Responder {
-    let request = user_logout_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
+pub async fn user_logout(pool: web::Data<Pool>, customer: AuthedCustomer) -> impl Responder {
     match pool.get_conn().await {
-        Ok(mut conn) => match AuthService::logout_user(&mut conn, token).await {
+        Ok(mut conn) => match AuthService::logout_user(&mut conn, &customer).await {
             Ok(_) => HttpResponse::Ok().json(UserLogoutResponse {
                 message: "logout successfully".to_string(),
             }),
@@ -223,9 +232,6 @@ pub async fn user_logout(
 
 #[derive(Debug, Deserialize)]
 struct UserUpdateRequest {
-    token: String,
-    tag: String,
-    nonce: String,
     username: String,
     name: String,
     email: String,
@@ -234,35 +240,144 @@ struct UserUpdateRequest {
 
 #[derive(Debug, Serialize)]
 struct UserUpdateResponse {
-    token: String,
-    tag: String,
-    nonce: String,
+    message: String,
 }
 
 #[post("/user/update")]
 pub async fn user_update(
     pool: web::Data<Pool>,
+    mailer: web::Data<dyn Mailer>,
+    customer: AuthedCustomer,
     user_update_request: web::Json<UserUpdateRequest>,
 ) -> impl Responder {
     let request = user_update_request.into_inner();
-    let token = &Token {
-        token: request.token,
-        tag: request.tag,
-        nonce: request.nonce,
-    };
     let username = &request.username;
     let name = &request.name;
     let email = &request.email;
     let address = &request.address;
     match pool.get_conn().await {
         Ok(mut conn) => {
-            match UserService::update_user_profile(&mut conn, token, username, name, email, address)
+            match UserService::update_user_profile(
+                &mut conn,
+                mailer.get_ref(),
+                &customer,
+                username,
+                name,
+                email,
+                address,
+            )
+            .await
+            {
+                Ok(_) => HttpResponse::Ok().json(UserUpdateResponse {
+                    message: "user profile updated".to_string(),
+                }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct UserSessionItemResponse {
+    session_id: u32,
+    user_agent: String,
+    created_at: String,
+    last_used: String,
+    current: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct UserSessionListResponse {
+    sessions: Vec<UserSessionItemResponse>,
+}
+
+#[get("/user/sessions")]
+pub async fn user_session_list(pool: web::Data<Pool>, customer: AuthedCustomer) -> impl Responder {
+    match pool.get_conn().await {
+        Ok(mut conn) => match AuthService::get_user_sessions(&mut conn, &customer).await {
+            Ok(sessions) => HttpResponse::Ok().json(UserSessionListResponse {
+                sessions: sessions
+                    .into_iter()
+                    .map(|session| UserSessionItemResponse {
+                        session_id: session.id,
+                        user_agent: session.user_agent,
+                        created_at: session.created_at.to_string(),
+                        last_used: session.last_used.to_string(),
+                        current: session.id == customer.session_id,
+                    })
+                    .collect(),
+            }),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct UserSessionRevokeResponse {
+    message: String,
+}
+
+#[post("/user/session/{id}/revoke")]
+pub async fn user_session_revoke(
+    pool: web::Data<Pool>,
+    customer: AuthedCustomer,
+    id: web::Path<(u32,)>,
+) -> impl Responder {
+    let session_id = id.into_inner().0;
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match AuthService::revoke_user_session(&mut conn, &customer, session_id).await {
+                Ok(_) => HttpResponse::Ok().json(UserSessionRevokeResponse {
+                    message: "session revoked".to_string(),
+                }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[post("/user/logout/all")]
+pub async fn user_logout_all(pool: web::Data<Pool>, customer: AuthedCustomer) -> impl Responder {
+    match pool.get_conn().await {
+        Ok(mut conn) => match AuthService::logout_user_everywhere(&mut conn, &customer).await {
+            Ok(_) => HttpResponse::Ok().json(UserLogoutResponse {
+                message: "logout from all sessions successfully".to_string(),
+            }),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct PasswordChangeRequest {
+    old_password: String,
+    new_password: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PasswordChangeResponse {
+    message: String,
+}
+
+#[post("/user/password/change")]
+pub async fn user_password_change(
+    pool: web::Data<Pool>,
+    customer: AuthedCustomer,
+    password_change_request: web::Json<PasswordChangeRequest>,
+) -> impl Responder {
+    let old_password = &password_change_request.old_password;
+    let new_password = &password_change_request.new_password;
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match UserService::change_password(&mut conn, &customer, old_password, new_password)
                 .await
             {
-                Ok(token) => HttpResponse::Ok().json(UserUpdateResponse {
-                    token: token.token,
-                    tag: token.tag,
-                    nonce: token.nonce,
+                Ok(_) => HttpResponse::Ok().json(PasswordChangeResponse {
+                    message: "password changed, other sessions logged out".to_string(),
                 }),
                 Err(e) => HttpResponse::BadRequest().json(e.to_string()),
             }
@@ -270,3 +385,314 @@ pub async fn user_update(
         Err(e) => HttpResponse::BadGateway().json(e.to_string()),
     }
 }
+
+#[derive(Deserialize)]
+struct PasswordForgotRequest {
+    username: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PasswordForgotResponse {
+    message: String,
+}
+
+#[post("/user/password/forgot")]
+pub async fn user_password_forgot(
+    pool: web::Data<Pool>,
+    mailer: web::Data<dyn Mailer>,
+    password_forgot_request: web::Json<PasswordForgotRequest>,
+) -> impl Responder {
+    let username = &password_forgot_request.username;
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match UserService::request_password_reset(&mut conn, mailer.get_ref(), username).await {
+                Ok(_) => HttpResponse::Ok().json(PasswordForgotResponse {
+                    message: "if the account exists, a password reset email has been sent"
+                        .to_string(),
+                }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct PasswordResetRequest {
+    token: String,
+    password: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PasswordResetResponse {
+    message: String,
+}
+
+#[post("/user/password/reset")]
+pub async fn user_password_reset(
+    pool: web::Data<Pool>,
+    password_reset_request: web::Json<PasswordResetRequest>,
+) -> impl Responder {
+    let token = &password_reset_request.token;
+    let password = &password_reset_request.password;
+    match pool.get_conn().await {
+        Ok(mut conn) => match UserService::reset_password(&mut conn, token, password).await {
+            Ok(_) => HttpResponse::Ok().json(PasswordResetResponse {
+                message: "password reset, please login again".to_string(),
+            }),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct EmailVerifyRequest {
+    token: String,
+}
+
+#[derive(Debug, Serialize)]
+struct EmailVerifyResponse {
+    message: String,
+}
+
+#[post("/user/email/verify")]
+pub async fn user_email_verify(
+    pool: web::Data<Pool>,
+    email_verify_request: web::Json<EmailVerifyRequest>,
+) -> impl Responder {
+    let token = &email_verify_request.token;
+    match pool.get_conn().await {
+        Ok(mut conn) => match UserService::verify_email(&mut conn, token).await {
+            Ok(_) => HttpResponse::Ok().json(EmailVerifyResponse {
+                message: "email address verified".to_string(),
+            }),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[post("/user/email/verify/resend")]
+pub async fn user_email_verify_resend(
+    pool: web::Data<Pool>,
+    mailer: web::Data<dyn Mailer>,
+    customer: AuthedCustomer,
+) -> impl Responder {
+    match pool.get_conn().await {
+        Ok(mut conn) => {
+            match UserService::request_email_verification(&mut conn, mailer.get_ref(), &customer)
+                .await
+            {
+                Ok(_) => HttpResponse::Ok().json(EmailVerifyResponse {
+                    message: "verification email sent".to_string(),
+                }),
+                Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct UserExportProfileResponse {
+    customer_id: u32,
+    username: String,
+    name: String,
+    address: String,
+    email: String,
+    email_verified: bool,
+    account_balance: String,
+    credit_level: u32,
+    total_purchase: String,
+    overdraft_limit: String,
+    status: String,
+}
+
+#[derive(Debug, Serialize)]
+struct UserExportOrderItemResponse {
+    book_id: u32,
+    quantity: u32,
+    total_price: String,
+}
+
+#[derive(Debug, Serialize)]
+struct UserExportOrderResponse {
+    order_id: u32,
+    order_date: String,
+    shipping_address: String,
+    discount_percentage: String,
+    discount_amount: String,
+    original_price: String,
+    total_price: String,
+    payment_status: String,
+    shipping_status: String,
+    items: Vec<UserExportOrderItemResponse>,
+}
+
+#[derive(Debug, Serialize)]
+struct UserExportPriceInquiryResponse {
+    inquiry_id: u32,
+    book_title: String,
+    isbn: String,
+    expected_price: String,
+    inquiry_date: String,
+    status: String,
+}
+
+#[derive(Debug, Serialize)]
+struct UserExportSessionResponse {
+    session_id: u32,
+    user_agent: String,
+    created_at: String,
+    last_used: String,
+    expires_at: String,
+    is_online: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct UserExportStatusChangeResponse {
+    old_status: String,
+    new_status: String,
+    reason: String,
+    expires_at: Option<String>,
+    created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+struct UserExportBalanceAdjustmentResponse {
+    date: String,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct UserExportResponse {
+    profile: UserExportProfileResponse,
+    orders: Vec<UserExportOrderResponse>,
+    price_inquiries: Vec<UserExportPriceInquiryResponse>,
+    sessions: Vec<UserExportSessionResponse>,
+    status_history: Vec<UserExportStatusChangeResponse>,
+    staff_balance_adjustments: Vec<UserExportBalanceAdjustmentResponse>,
+}
+
+#[get("/user/export")]
+pub async fn user_export(pool: web::Data<Pool>, customer: AuthedCustomer) -> impl Responder {
+    match pool.get_conn().await {
+        Ok(mut conn) => match UserService::export_user_data(&mut conn, &customer).await {
+            Ok(export) => HttpResponse::Ok().json(UserExportResponse {
+                profile: UserExportProfileResponse {
+                    customer_id: export.customer.id,
+                    username: export.customer.username,
+                    name: export.customer.name,
+                    address: export.customer.address,
+                    email: export.customer.email,
+                    email_verified: export.customer.email_verified_at.is_some(),
+                    account_balance: export.customer.account_balance.to_string(),
+                    credit_level: export.customer.credit_level,
+                    total_purchase: export.customer.total_purchase.to_string(),
+                    overdraft_limit: export.customer.overdraft_limit.to_string(),
+                    status: export.customer.status.to_string(),
+                },
+                orders: export
+                    .orders
+                    .into_iter()
+                    .map(|order| UserExportOrderResponse {
+                        order_id: order.id,
+                        order_date: order.date.to_string(),
+                        shipping_address: order.shipping_address,
+                        discount_percentage: order.discount_percentage.with_scale(1).to_string(),
+                        discount_amount: order.discount_amount.with_scale(2).to_string(),
+                        original_price: order.original_amount.with_scale(2).to_string(),
+                        total_price: order.total_amount.with_scale(2).to_string(),
+                        payment_status: order.payment_status.to_string(),
+                        shipping_status: order.shipping_status.to_string(),
+                        items: order
+                            .items
+                            .into_iter()
+                            .map(|item| UserExportOrderItemResponse {
+                                book_id: item.book_id,
+                                quantity: item.quantity,
+                                total_price: item.total_price.with_scale(2).to_string(),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+                price_inquiries: export
+                    .price_inquiries
+                    .into_iter()
+                    .map(|inquiry| UserExportPriceInquiryResponse {
+                        inquiry_id: inquiry.id,
+                        book_title: inquiry.book_title,
+                        isbn: inquiry.isbn,
+                        expected_price: inquiry.expected_price.to_string(),
+                        inquiry_date: inquiry.date.to_string(),
+                        status: inquiry.status.to_string(),
+                    })
+                    .collect(),
+                sessions: export
+                    .sessions
+                    .into_iter()
+                    .map(|session| UserExportSessionResponse {
+                        session_id: session.id,
+                        user_agent: session.user_agent,
+                        created_at: session.created_at.to_string(),
+                        last_used: session.last_used.to_string(),
+                        expires_at: session.expires_at.to_string(),
+                        is_online: session.is_online,
+                    })
+                    .collect(),
+                status_history: export
+                    .status_history
+                    .into_iter()
+                    .map(|change| UserExportStatusChangeResponse {
+                        old_status: change.old_status.to_string(),
+                        new_status: change.new_status.to_string(),
+                        reason: change.reason,
+                        expires_at: change.expires_at.map(|expires_at| expires_at.to_string()),
+                        created_at: change.created_at.to_string(),
+                    })
+                    .collect(),
+                staff_balance_adjustments: export
+                    .staff_balance_adjustments
+                    .into_iter()
+                    .map(|adjustment| UserExportBalanceAdjustmentResponse {
+                        date: adjustment.date.to_string(),
+                        before: adjustment.before,
+                        after: adjustment.after,
+                    })
+                    .collect(),
+            }),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct UserCloseRequest {
+    password: String,
+}
+
+#[derive(Debug, Serialize)]
+struct UserCloseResponse {
+    message: String,
+}
+
+#[post("/user/close")]
+pub async fn user_close(
+    pool: web::Data<Pool>,
+    customer: AuthedCustomer,
+    user_close_request: web::Json<UserCloseRequest>,
+) -> impl Responder {
+    let password = &user_close_request.password;
+    match pool.get_conn().await {
+        Ok(mut conn) => match UserService::close_account(&mut conn, &customer, password).await {
+            Ok(_) => HttpResponse::Ok().json(UserCloseResponse {
+                message: "account closed".to_string(),
+            }),
+            Err(e) => HttpResponse::BadRequest().json(e.to_string()),
+        },
+        Err(e) => HttpResponse::BadGateway().json(e.to_string()),
+    }
+}
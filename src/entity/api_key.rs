@@ -0,0 +1,14 @@
+use crate::entity::Permission;
+use mysql_common::time::PrimitiveDateTime;
+
+pub struct ApiKey {
+    pub id: u32,
+    pub admin_id: u32,
+    pub name: String,
+    pub prefix: String,
+    pub permissions: Vec<Permission>,
+    pub created_at: PrimitiveDateTime,
+    pub expires_at: PrimitiveDateTime,
+    pub last_used_at: Option<PrimitiveDateTime>,
+    pub revoked_at: Option<PrimitiveDateTime>,
+}
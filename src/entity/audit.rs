@@ -0,0 +1,27 @@
+use mysql_common::time::PrimitiveDateTime;
+
+#[derive(Debug)]
+pub struct AuditLog {
+    pub id: u32,
+    pub admin_id: u32,
+    pub api_key_id: Option<u32>,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: u32,
+    pub before_value: Option<String>,
+    pub after_value: Option<String>,
+    pub created_at: PrimitiveDateTime,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    pub admin_id: Option<u32>,
+    pub api_key_id: Option<u32>,
+    pub action: Option<String>,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<u32>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
@@ -1,8 +1,89 @@
+use crate::entity::{AdminRole, Permission};
 use mysql_common::time::PrimitiveDateTime;
 
-pub struct AuthRecord {
+pub struct CustomerSession {
+    pub id: u32,
     pub customer_id: u32,
     pub token: String,
+    pub user_agent: String,
+    pub created_at: PrimitiveDateTime,
     pub last_used: PrimitiveDateTime,
+    pub expires_at: PrimitiveDateTime,
     pub is_online: bool,
 }
+
+pub struct RefreshToken {
+    pub id: u32,
+    pub session_id: u32,
+    pub created_at: PrimitiveDateTime,
+    pub used_at: Option<PrimitiveDateTime>,
+}
+
+pub struct PasswordResetToken {
+    pub id: u32,
+    pub customer_id: u32,
+    pub created_at: PrimitiveDateTime,
+    pub expires_at: PrimitiveDateTime,
+    pub used_at: Option<PrimitiveDateTime>,
+}
+
+pub struct EmailVerificationToken {
+    pub id: u32,
+    pub customer_id: u32,
+    pub email: String,
+    pub created_at: PrimitiveDateTime,
+    pub expires_at: PrimitiveDateTime,
+    pub used_at: Option<PrimitiveDateTime>,
+}
+
+pub struct AdminSession {
+    pub id: u32,
+    pub admin_id: u32,
+    pub token: String,
+    pub created_at: PrimitiveDateTime,
+    pub last_used: PrimitiveDateTime,
+    pub is_online: bool,
+}
+
+pub struct AdminMfa {
+    pub admin_id: u32,
+    pub secret: String,
+    pub is_enabled: bool,
+    pub last_step: Option<i64>,
+    pub created_at: PrimitiveDateTime,
+    pub enabled_at: Option<PrimitiveDateTime>,
+}
+
+pub struct AdminLoginChallenge {
+    pub id: u32,
+    pub admin_id: u32,
+    pub created_at: PrimitiveDateTime,
+    pub expires_at: PrimitiveDateTime,
+    pub used_at: Option<PrimitiveDateTime>,
+    pub attempts: u32,
+}
+
+pub struct AuthedCustomer {
+    pub id: u32,
+    pub session_id: u32,
+    pub username: String,
+}
+
+pub struct AuthedAdmin {
+    pub id: u32,
+    pub session_id: u32,
+    pub username: String,
+    pub role: AdminRole,
+    pub permissions: Vec<Permission>,
+    pub api_key_id: Option<u32>,
+}
+
+impl AuthedAdmin {
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+
+    pub fn is_api_key(&self) -> bool {
+        self.api_key_id.is_some()
+    }
+}
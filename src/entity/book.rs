@@ -17,3 +17,15 @@ pub struct Book {
     pub cover: String,
     pub is_onstore: bool,
 }
+
+#[derive(Debug, Default)]
+pub struct BookFilter {
+    pub publisher_id: Option<u32>,
+    pub author_id: Option<u32>,
+    pub keyword_id: Option<u32>,
+    pub series_id: Option<u32>,
+    pub min_price: Option<String>,
+    pub max_price: Option<String>,
+    pub in_stock: bool,
+    pub onstore_only: bool,
+}
@@ -1,5 +1,6 @@
-use crate::entity::CustomerStatus;
+use crate::entity::{CustomerStatus, UserStatus};
 use mysql_common::bigdecimal::BigDecimal;
+use mysql_common::time::PrimitiveDateTime;
 
 #[derive(Debug, Default)]
 pub struct Customer {
@@ -14,6 +15,7 @@ pub struct Customer {
     pub total_purchase: BigDecimal,
     pub overdraft_limit: BigDecimal,
     pub status: CustomerStatus,
+    pub email_verified_at: Option<PrimitiveDateTime>,
 }
 
 #[derive(Debug, Default)]
@@ -24,3 +26,15 @@ pub struct CreditRule {
     pub upgrade_balance: BigDecimal,
     pub upgrade_purchase: BigDecimal,
 }
+
+#[derive(Debug)]
+pub struct CustomerStatusChange {
+    pub id: u32,
+    pub customer_id: u32,
+    pub admin_id: Option<u32>,
+    pub old_status: UserStatus,
+    pub new_status: UserStatus,
+    pub reason: String,
+    pub expires_at: Option<PrimitiveDateTime>,
+    pub created_at: PrimitiveDateTime,
+}
@@ -273,7 +273,7 @@ impl Display for AdminRole {
     }
 }
 
-#[derive(Eq, PartialEq, Debug, Default)]
+#[derive(Eq, PartialEq, Debug, Default, Clone, Copy)]
 pub enum UserStatus {
     #[default]
     Active,
@@ -310,3 +310,223 @@ impl Display for UserStatus {
         write!(f, "{}", str)
     }
 }
+
+#[derive(Eq, PartialEq, Debug, Default, Clone, Copy)]
+pub enum LoginFailureKind {
+    #[default]
+    Username,
+    Ip,
+}
+
+impl LoginFailureKind {
+    pub fn new(kind: &str) -> LoginFailureKind {
+        match kind {
+            "username" => LoginFailureKind::Username,
+            "ip" => LoginFailureKind::Ip,
+            _ => LoginFailureKind::Username,
+        }
+    }
+}
+
+impl FromStr for LoginFailureKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(LoginFailureKind::new(s))
+    }
+}
+
+impl Display for LoginFailureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            LoginFailureKind::Username => "username",
+            LoginFailureKind::Ip => "ip",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum Permission {
+    CatalogRead,
+    CatalogWrite,
+    StockRead,
+    StockAdjust,
+    CustomerRead,
+    CustomerBalance,
+    CustomerCredit,
+    CustomerStatus,
+    OrderRead,
+    OrderShip,
+    ShortageRead,
+    ShortageWrite,
+    ProcurementRead,
+    ProcurementCreate,
+    ProcurementApprove,
+    SupplierRead,
+    SupplierWrite,
+    AdminManage,
+    AuditRead,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 19] = [
+        Permission::CatalogRead,
+        Permission::CatalogWrite,
+        Permission::StockRead,
+        Permission::StockAdjust,
+        Permission::CustomerRead,
+        Permission::CustomerBalance,
+        Permission::CustomerCredit,
+        Permission::CustomerStatus,
+        Permission::OrderRead,
+        Permission::OrderShip,
+        Permission::ShortageRead,
+        Permission::ShortageWrite,
+        Permission::ProcurementRead,
+        Permission::ProcurementCreate,
+        Permission::ProcurementApprove,
+        Permission::SupplierRead,
+        Permission::SupplierWrite,
+        Permission::AdminManage,
+        Permission::AuditRead,
+    ];
+}
+
+impl FromStr for Permission {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "catalog.read" => Ok(Permission::CatalogRead),
+            "catalog.write" => Ok(Permission::CatalogWrite),
+            "stock.read" => Ok(Permission::StockRead),
+            "stock.adjust" => Ok(Permission::StockAdjust),
+            "customer.read" => Ok(Permission::CustomerRead),
+            "customer.balance" => Ok(Permission::CustomerBalance),
+            "customer.credit" => Ok(Permission::CustomerCredit),
+            "customer.status" => Ok(Permission::CustomerStatus),
+            "order.read" => Ok(Permission::OrderRead),
+            "order.ship" => Ok(Permission::OrderShip),
+            "shortage.read" => Ok(Permission::ShortageRead),
+            "shortage.write" => Ok(Permission::ShortageWrite),
+            "procurement.read" => Ok(Permission::ProcurementRead),
+            "procurement.create" => Ok(Permission::ProcurementCreate),
+            "procurement.approve" => Ok(Permission::ProcurementApprove),
+            "supplier.read" => Ok(Permission::SupplierRead),
+            "supplier.write" => Ok(Permission::SupplierWrite),
+            "admin.manage" => Ok(Permission::AdminManage),
+            "audit.read" => Ok(Permission::AuditRead),
+            _ => anyhow::bail!("invalid permission: {}", s),
+        }
+    }
+}
+
+impl Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Permission::CatalogRead => "catalog.read",
+            Permission::CatalogWrite => "catalog.write",
+            Permission::StockRead => "stock.read",
+            Permission::StockAdjust => "stock.adjust",
+            Permission::CustomerRead => "customer.read",
+            Permission::CustomerBalance => "customer.balance",
+            Permission::CustomerCredit => "customer.credit",
+            Permission::CustomerStatus => "customer.status",
+            Permission::OrderRead => "order.read",
+            Permission::OrderShip => "order.ship",
+            Permission::ShortageRead => "shortage.read",
+            Permission::ShortageWrite => "shortage.write",
+            Permission::ProcurementRead => "procurement.read",
+            Permission::ProcurementCreate => "procurement.create",
+            Permission::ProcurementApprove => "procurement.approve",
+            Permission::SupplierRead => "supplier.read",
+            Permission::SupplierWrite => "supplier.write",
+            Permission::AdminManage => "admin.manage",
+            Permission::AuditRead => "audit.read",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Default, Clone, Copy)]
+pub enum BookSort {
+    #[default]
+    Title,
+    Price,
+    PriceDesc,
+    Newest,
+    BestSelling,
+}
+
+impl FromStr for BookSort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "title" => Ok(BookSort::Title),
+            "price" => Ok(BookSort::Price),
+            "price_desc" => Ok(BookSort::PriceDesc),
+            "newest" => Ok(BookSort::Newest),
+            "best_selling" => Ok(BookSort::BestSelling),
+            _ => anyhow::bail!("invalid sort: {}", s),
+        }
+    }
+}
+
+impl Display for BookSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            BookSort::Title => "title",
+            BookSort::Price => "price",
+            BookSort::PriceDesc => "price_desc",
+            BookSort::Newest => "newest",
+            BookSort::BestSelling => "best_selling",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Default, Clone, Copy)]
+pub enum SuggestionKind {
+    #[default]
+    Title,
+    Author,
+    Keyword,
+    Publisher,
+    Series,
+}
+
+impl SuggestionKind {
+    pub fn new(kind: &str) -> SuggestionKind {
+        match kind {
+            "title" => SuggestionKind::Title,
+            "author" => SuggestionKind::Author,
+            "keyword" => SuggestionKind::Keyword,
+            "publisher" => SuggestionKind::Publisher,
+            "series" => SuggestionKind::Series,
+            _ => SuggestionKind::Title,
+        }
+    }
+}
+
+impl FromStr for SuggestionKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(SuggestionKind::new(s))
+    }
+}
+
+impl Display for SuggestionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            SuggestionKind::Title => "title",
+            SuggestionKind::Author => "author",
+            SuggestionKind::Keyword => "keyword",
+            SuggestionKind::Publisher => "publisher",
+            SuggestionKind::Series => "series",
+        };
+        write!(f, "{}", str)
+    }
+}
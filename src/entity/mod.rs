@@ -1,4 +1,6 @@
 mod admin;
+mod api_key;
+mod audit;
 mod auth;
 mod author;
 mod book;
@@ -10,18 +12,26 @@ mod order;
 mod price_inquiry;
 mod publisher;
 mod purchase_order;
+mod role;
 mod series;
 mod shortage;
+mod suggestion;
 mod supplier;
 
 pub use admin::Admin;
-pub use auth::AuthRecord;
+pub use api_key::ApiKey;
+pub use audit::{AuditFilter, AuditLog};
+pub use auth::{
+    AdminLoginChallenge, AdminMfa, AdminSession, AuthedAdmin, AuthedCustomer, CustomerSession,
+    EmailVerificationToken, PasswordResetToken, RefreshToken,
+};
 pub use author::Author;
-pub use book::Book;
-pub use customer::{CreditRule, Customer};
+pub use book::{Book, BookFilter};
+pub use customer::{CreditRule, Customer, CustomerStatusChange};
 pub use enums::{
-    AdminRole, AdminStatus, CustomerStatus, OrderPaymentStatus, OrderShippingStatus,
-    PriceInquiryStatus, PurchaseOrderStatus, UserStatus,
+    AdminRole, AdminStatus, BookSort, CustomerStatus, LoginFailureKind, OrderPaymentStatus,
+    OrderShippingStatus, Permission, PriceInquiryStatus, PurchaseOrderStatus, SuggestionKind,
+    UserStatus,
 };
 pub use keyword::Keyword;
 pub use location::Location;
@@ -29,6 +39,8 @@ pub use order::{Order, OrderItem};
 pub use price_inquiry::PriceInquiry;
 pub use publisher::Publisher;
 pub use purchase_order::{PurchaseOrder, PurchaseOrderItem};
+pub use role::Role;
 pub use series::{BookInSeries, Series};
 pub use shortage::{Shortage, ShortageItem};
+pub use suggestion::Suggestion;
 pub use supplier::{Supplier, SupplierCatalog, SupplierRecord};
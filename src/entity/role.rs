@@ -0,0 +1,9 @@
+use crate::entity::Permission;
+
+#[derive(Debug, Default)]
+pub struct Role {
+    pub id: u32,
+    pub name: String,
+    pub description: String,
+    pub permissions: Vec<Permission>,
+}
@@ -0,0 +1,8 @@
+use crate::entity::SuggestionKind;
+
+#[derive(Debug, Default, Clone)]
+pub struct Suggestion {
+    pub kind: SuggestionKind,
+    pub id: u32,
+    pub text: String,
+}
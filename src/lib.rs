@@ -1,10 +1,5 @@
-pub const AES_PWD_KEY: &[u8; 32] = b"vL2i45V7o9e12n4F67l90u2irv6i8n0A";
-pub const AES_TOKEN_KEY: &[u8; 32] = b"0L2F15V7o4e1D74F67h90u2i6V6i8n0A";
 pub const COMPANY_NAME: &[u8; 9] = b"reinerina";
 
-pub const AES_ADMIN_PWD_KEY: &[u8; 32] = b"vD2i41V7o9312f4Jp7l90u2ir06i8b0A";
-
-
 pub mod controller;
 pub mod entity;
 pub mod repo;
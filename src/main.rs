@@ -1,20 +1,36 @@
+use actix_multipart::form::MultipartFormConfig;
 use actix_web::{get, web, App, HttpResponse, HttpServer};
 use bookstore::controller::{
-    admin_book_add, admin_book_detail, admin_book_update, admin_customer_balance,
-    admin_customer_credit, admin_customer_list, admin_detail, admin_location_list, admin_login,
-    admin_order_list, admin_order_ship_auto, admin_register, admin_shortage_detail,
-    admin_shortage_list, admin_stock_change, admin_stock_transfer, admin_user_search, author_list,
-    book_authors_search, book_detail, book_keywords_search, book_list, book_title_search,
-    credit_rule, keyword_list, login, order_create, order_detail, order_history, order_payment,
-    publisher_list, purchase_order_create, purchase_order_detail, purchase_order_list, register,
-    series_list, shortage_create, supplier_list, supplier_profile, user_detail, user_logout,
-    user_profile, user_update,
+    admin_api_key_create, admin_api_key_list, admin_api_key_revoke, admin_audit_list,
+    admin_audit_verify, admin_book_add, admin_book_cover, admin_book_detail, admin_book_update,
+    admin_customer_balance, admin_customer_ban, admin_customer_cancel, admin_customer_credit,
+    admin_customer_list, admin_customer_status_history, admin_customer_unban, admin_detail,
+    admin_location_list, admin_login, admin_login_mfa, admin_login_mfa_setup, admin_login_unlock,
+    admin_logout, admin_mfa_disable, admin_mfa_enable, admin_mfa_policy, admin_mfa_recovery_codes,
+    admin_mfa_setup, admin_mfa_status, admin_order_list, admin_order_ship_auto,
+    admin_password_change, admin_permission_list, admin_register, admin_role_list, admin_role_save,
+    admin_roles, admin_roles_update, admin_session_list, admin_session_revoke,
+    admin_session_revoke_all, admin_shortage_detail, admin_shortage_list, admin_staff_deactivate,
+    admin_staff_list, admin_staff_mfa_reset, admin_staff_password_reset, admin_staff_reactivate,
+    admin_staff_role, admin_stock_add, admin_stock_change, admin_stock_transfer, admin_user_search,
+    author_list, book_authors_search, book_detail, book_isbn_lookup, book_keywords_search,
+    book_list, book_search, book_suggest, book_title_search, credit_rule, keyword_add,
+    keyword_list, login, order_create, order_detail, order_history, order_payment,
+    price_inquiry_create, publisher_list, purchase_order_create, purchase_order_detail,
+    purchase_order_list, register, series_list, shortage_create, supplier_list, supplier_profile,
+    user_close, user_detail, user_email_verify, user_email_verify_resend, user_export, user_logout,
+    user_logout_all, user_password_change, user_password_forgot, user_password_reset, user_profile,
+    user_session_list, user_session_revoke, user_token_refresh, user_update,
+};
+use bookstore::utils::{
+    database_opts_from_env, CoverStore, FileOutbox, KeyRing, Mailer, COVER_SIZE_MAX,
+    COVER_URL_PREFIX,
 };
 use mysql_async::prelude::{Query, WithParams};
-use mysql_async::{OptsBuilder, Pool};
+use mysql_async::Pool;
 use serde::Deserialize;
-use std::env;
 use std::ops::Index;
+use std::sync::Arc;
 
 #[get("/")]
 async fn index() -> HttpResponse {
@@ -43,38 +59,91 @@ async fn sql_user(conn: web::Data<Pool>) -> HttpResponse {
 async fn main() -> std::io::Result<()> {
     pretty_env_logger::init();
 
-    let opts = OptsBuilder::default()
-        .db_name(env::var("SQL_DB").ok())
-        .user(env::var("SQL_USER").ok())
-        .pass(env::var("SQL_PWD").ok())
-        .ip_or_hostname(env::var("SQL_HOSTNAME").unwrap_or("localhost".to_string()))
-        .tcp_port(
-            env::var("SQL_PORT")
-                .unwrap_or("3306".to_string())
-                .parse()
-                .unwrap_or(3306),
-        );
+    if let Err(e) = KeyRing::from_env().and_then(KeyRing::install) {
+        log::error!("error loading key ring: {}", e);
+        return Err(std::io::Error::other(e.to_string()));
+    }
+
+    let mailer: Arc<dyn Mailer> = match FileOutbox::from_env() {
+        Ok(outbox) => Arc::new(outbox),
+        Err(e) => {
+            log::error!("error opening mail outbox: {}", e);
+            return Err(std::io::Error::other(e.to_string()));
+        }
+    };
+
+    let cover_store = match CoverStore::from_env() {
+        Ok(store) => web::Data::new(store),
+        Err(e) => {
+            log::error!("error opening cover store: {}", e);
+            return Err(std::io::Error::other(e.to_string()));
+        }
+    };
 
-    let pool = Pool::new(opts);
+    let pool = Pool::new(database_opts_from_env());
 
     let pool_clone = pool.clone();
     let server = HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(pool_clone.clone()))
+            .app_data(web::Data::from(mailer.clone()))
+            .app_data(cover_store.clone())
+            .app_data(
+                MultipartFormConfig::default()
+                    .total_limit(COVER_SIZE_MAX * 2)
+                    .memory_limit(COVER_SIZE_MAX * 2),
+            )
             .service(index)
             .service(actix_files::Files::new("/assets/images", "assets/images"))
+            .service(actix_files::Files::new(COVER_URL_PREFIX, cover_store.dir()))
             .service(admin_register)
             .service(admin_login)
+            .service(admin_login_unlock)
+            .service(admin_login_mfa)
+            .service(admin_login_mfa_setup)
+            .service(admin_mfa_status)
+            .service(admin_mfa_setup)
+            .service(admin_mfa_enable)
+            .service(admin_mfa_disable)
+            .service(admin_mfa_recovery_codes)
+            .service(admin_mfa_policy)
+            .service(admin_staff_mfa_reset)
+            .service(admin_permission_list)
+            .service(admin_role_list)
+            .service(admin_role_save)
+            .service(admin_roles)
+            .service(admin_roles_update)
+            .service(admin_api_key_create)
+            .service(admin_api_key_list)
+            .service(admin_api_key_revoke)
+            .service(admin_audit_list)
+            .service(admin_audit_verify)
+            .service(admin_logout)
+            .service(admin_session_list)
+            .service(admin_session_revoke)
+            .service(admin_session_revoke_all)
             .service(admin_detail)
+            .service(admin_password_change)
+            .service(admin_staff_list)
+            .service(admin_staff_deactivate)
+            .service(admin_staff_reactivate)
+            .service(admin_staff_role)
+            .service(admin_staff_password_reset)
             .service(admin_book_detail)
             .service(admin_location_list)
+            .service(admin_stock_add)
             .service(admin_stock_change)
             .service(admin_stock_transfer)
             .service(admin_book_update)
+            .service(admin_book_cover)
             .service(admin_book_add)
             .service(admin_customer_list)
             .service(admin_customer_credit)
             .service(admin_customer_balance)
+            .service(admin_customer_ban)
+            .service(admin_customer_unban)
+            .service(admin_customer_cancel)
+            .service(admin_customer_status_history)
             .service(admin_order_list)
             .service(admin_order_ship_auto)
             .service(admin_shortage_list)
@@ -85,16 +154,32 @@ async fn main() -> std::io::Result<()> {
             .service(user_detail)
             .service(user_profile)
             .service(user_update)
+            .service(user_email_verify)
+            .service(user_email_verify_resend)
+            .service(user_export)
+            .service(user_close)
             .service(user_logout)
+            .service(user_logout_all)
+            .service(user_token_refresh)
+            .service(user_session_list)
+            .service(user_session_revoke)
+            .service(user_password_change)
+            .service(user_password_forgot)
+            .service(user_password_reset)
             .service(supplier_profile)
             .service(credit_rule)
             .service(book_detail)
+            .service(book_isbn_lookup)
             .service(book_list)
             .service(author_list)
             .service(publisher_list)
             .service(keyword_list)
+            .service(keyword_add)
+            .service(price_inquiry_create)
             .service(supplier_list)
             .service(series_list)
+            .service(book_search)
+            .service(book_suggest)
             .service(book_title_search)
             .service(book_keywords_search)
             .service(book_authors_search)
@@ -1,4 +1,4 @@
-use crate::entity::{Admin, AdminRole};
+use crate::entity::{Admin, AdminRole, AdminStatus};
 use mysql_async::prelude::{Query, WithParams};
 use mysql_async::{params, Conn};
 
@@ -24,18 +24,61 @@ impl AdminRepo {
         Ok(admin_id)
     }
 
-    pub async fn login_admin(
+    pub async fn get_admin_password(
         conn: &mut Conn,
         username: &str,
-        password: &str,
-    ) -> anyhow::Result<bool> {
-        let query = r"SELECT COUNT(admin_id) FROM admins WHERE admin_username=:username AND admin_pwd=:password;";
+    ) -> anyhow::Result<Option<(u32, String)>> {
+        let query = r"SELECT admin_id,admin_pwd FROM admins WHERE admin_username=:username;";
         let params = params! {
             "username" => username,
+        };
+        let result = query
+            .with(params)
+            .first::<(u32, String), &mut Conn>(conn)
+            .await?;
+        Ok(result)
+    }
+
+    pub async fn update_admin_password(
+        conn: &mut Conn,
+        admin_id: u32,
+        password: &str,
+    ) -> anyhow::Result<()> {
+        let query = r"UPDATE admins SET admin_pwd=:password WHERE admin_id=:admin_id;";
+        let params = params! {
             "password" => password,
+            "admin_id" => admin_id,
         };
-        let result = query.with(params).first::<u32, &mut Conn>(conn).await?;
-        Ok(result == Some(1))
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn update_admin_status(
+        conn: &mut Conn,
+        admin_id: u32,
+        status: &AdminStatus,
+    ) -> anyhow::Result<()> {
+        let query = r"UPDATE admins SET status=:status WHERE admin_id=:admin_id;";
+        let params = params! {
+            "status" => status.to_string(),
+            "admin_id" => admin_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn update_admin_role(
+        conn: &mut Conn,
+        admin_id: u32,
+        role: &AdminRole,
+    ) -> anyhow::Result<()> {
+        let query = r"UPDATE admins SET role=:role WHERE admin_id=:admin_id;";
+        let params = params! {
+            "role" => role.to_string(),
+            "admin_id" => admin_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
     }
 
     pub async fn get_admin_detail(
@@ -64,8 +107,41 @@ impl AdminRepo {
         Ok(result.pop())
     }
 
+    pub async fn get_admin_detail_by_id(
+        conn: &mut Conn,
+        admin_id: u32,
+    ) -> anyhow::Result<Option<Admin>> {
+        let query =
+            r"SELECT admin_id,admin_username,status,role FROM admins WHERE admin_id=:admin_id;";
+        let params = params! {
+            "admin_id" => admin_id,
+        };
+        let mut result = query
+            .with(params)
+            .map(conn, |(admin_id, admin_username, status, role)| {
+                let status: String = status;
+                let role: String = role;
+                Admin {
+                    id: admin_id,
+                    username: admin_username,
+                    password: String::new(),
+                    status: status.parse().unwrap(),
+                    role: role.parse().unwrap(),
+                }
+            })
+            .await?;
+
+        Ok(result.pop())
+    }
+
+    pub async fn get_admin_count(conn: &mut Conn) -> anyhow::Result<u64> {
+        let query = r"SELECT COUNT(*) FROM admins;";
+        let count = query.with(()).first::<u64, &mut Conn>(conn).await?;
+        Ok(count.unwrap_or(0))
+    }
+
     pub async fn get_user_list(conn: &mut Conn) -> anyhow::Result<Vec<Admin>> {
-        let query = r"SELECT admin_id,admin_username,status,role FROM admins;";
+        let query = r"SELECT admin_id,admin_username,status,role FROM admins ORDER BY admin_id;";
         let result = query
             .map(conn, |(admin_id, admin_username, status, role)| {
                 let status: String = status;
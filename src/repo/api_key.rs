@@ -0,0 +1,156 @@
+use crate::entity::{ApiKey, Permission};
+use mysql_async::prelude::{Query, WithParams};
+use mysql_async::{params, Conn, Params};
+
+pub struct ApiKeyRepo;
+
+impl ApiKeyRepo {
+    pub async fn create_api_key(
+        conn: &mut Conn,
+        admin_id: u32,
+        name: &str,
+        prefix: &str,
+        key_hash: &str,
+        expires_at: &str,
+    ) -> anyhow::Result<Option<u32>> {
+        let query = r"INSERT INTO api_keys (admin_id,key_name,key_prefix,key_hash,created_at,expires_at)
+        SELECT :admin_id,:name,:prefix,:key_hash,NOW(),:expires_at FROM DUAL WHERE :expires_at > NOW();";
+        let params = params! {
+            "admin_id" => admin_id,
+            "name" => name,
+            "prefix" => prefix,
+            "key_hash" => key_hash,
+            "expires_at" => expires_at,
+        };
+        query.with(params).run(&mut *conn).await?;
+        if conn.affected_rows() == 0 {
+            return Ok(None);
+        }
+        let query = r"SELECT LAST_INSERT_ID() as api_key_id;";
+        let api_key_id = query.with(()).first::<u32, &mut Conn>(conn).await?;
+        Ok(api_key_id)
+    }
+
+    pub async fn set_api_key_permissions(
+        conn: &mut Conn,
+        api_key_id: u32,
+        permissions: &[Permission],
+    ) -> anyhow::Result<()> {
+        for permission in permissions {
+            let query = r"INSERT INTO api_key_permissions (api_key_id,permission_id)
+            SELECT :api_key_id,permission_id FROM permissions WHERE permission_name=:permission_name;";
+            let params = params! {
+                "api_key_id" => api_key_id,
+                "permission_name" => permission.to_string(),
+            };
+            query.with(params).run(&mut *conn).await?;
+            if conn.affected_rows() == 0 {
+                anyhow::bail!("permission {} not found", permission);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn get_api_key_permissions(
+        conn: &mut Conn,
+        api_key_id: u32,
+    ) -> anyhow::Result<Vec<Permission>> {
+        let query = r"SELECT permissions.permission_name FROM api_key_permissions
+        JOIN permissions ON api_key_permissions.permission_id = permissions.permission_id
+        WHERE api_key_permissions.api_key_id = :api_key_id ORDER BY permissions.permission_id;";
+        let params = params! {
+            "api_key_id" => api_key_id,
+        };
+        let result = query
+            .with(params)
+            .map(conn, |permission_name: String| permission_name)
+            .await?;
+
+        Ok(result
+            .into_iter()
+            .filter_map(|permission_name| permission_name.parse().ok())
+            .collect())
+    }
+
+    pub async fn get_api_key_by_hash(
+        conn: &mut Conn,
+        key_hash: &str,
+    ) -> anyhow::Result<Option<ApiKey>> {
+        let query = r"SELECT api_key_id,admin_id,key_name,key_prefix,created_at,expires_at,last_used_at,revoked_at
+        FROM api_keys WHERE key_hash=:key_hash;";
+        let params = params! {
+            "key_hash" => key_hash,
+        };
+        let mut result = ApiKeyRepo::query_api_keys(conn, query, params).await?;
+        match result.pop() {
+            Some(mut api_key) => {
+                api_key.permissions = ApiKeyRepo::get_api_key_permissions(conn, api_key.id).await?;
+                Ok(Some(api_key))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_api_key_list(conn: &mut Conn) -> anyhow::Result<Vec<ApiKey>> {
+        let query = r"SELECT api_key_id,admin_id,key_name,key_prefix,created_at,expires_at,last_used_at,revoked_at
+        FROM api_keys ORDER BY api_key_id;";
+        let mut result = ApiKeyRepo::query_api_keys(conn, query, Params::Empty).await?;
+        for api_key in result.iter_mut() {
+            api_key.permissions = ApiKeyRepo::get_api_key_permissions(conn, api_key.id).await?;
+        }
+        Ok(result)
+    }
+
+    pub async fn touch_api_key(conn: &mut Conn, api_key_id: u32) -> anyhow::Result<()> {
+        let query = r"UPDATE api_keys SET last_used_at=NOW() WHERE api_key_id=:api_key_id;";
+        let params = params! {
+            "api_key_id" => api_key_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn revoke_api_key(conn: &mut Conn, api_key_id: u32) -> anyhow::Result<bool> {
+        let query = r"UPDATE api_keys SET revoked_at=NOW() WHERE api_key_id=:api_key_id AND revoked_at IS NULL;";
+        let params = params! {
+            "api_key_id" => api_key_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(conn.affected_rows() == 1)
+    }
+
+    async fn query_api_keys(
+        conn: &mut Conn,
+        query: &str,
+        params: Params,
+    ) -> anyhow::Result<Vec<ApiKey>> {
+        let result = query
+            .with(params)
+            .map(
+                conn,
+                |(
+                    api_key_id,
+                    admin_id,
+                    key_name,
+                    key_prefix,
+                    created_at,
+                    expires_at,
+                    last_used_at,
+                    revoked_at,
+                )| ApiKey {
+                    id: api_key_id,
+                    admin_id,
+                    name: key_name,
+                    prefix: key_prefix,
+                    permissions: Vec::new(),
+                    created_at,
+                    expires_at,
+                    last_used_at,
+                    revoked_at,
+                },
+            )
+            .await?;
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,145 @@
+use crate::entity::{AuditFilter, AuditLog};
+use mysql_async::prelude::{Query, WithParams};
+use mysql_async::{params, Conn, Params};
+
+pub struct AuditRepo;
+
+fn filter_params(filter: &AuditFilter) -> Vec<(String, mysql_async::Value)> {
+    vec![
+        ("admin_id".to_string(), filter.admin_id.into()),
+        ("api_key_id".to_string(), filter.api_key_id.into()),
+        ("action".to_string(), filter.action.clone().into()),
+        ("entity_type".to_string(), filter.entity_type.clone().into()),
+        ("entity_id".to_string(), filter.entity_id.into()),
+        ("from".to_string(), filter.from.clone().into()),
+        ("to".to_string(), filter.to.clone().into()),
+    ]
+}
+
+impl AuditRepo {
+    pub async fn lock_chain_head(conn: &mut Conn) -> anyhow::Result<Option<String>> {
+        let query = r"SELECT hash FROM audit_chain_head WHERE chain_id = 1 FOR UPDATE;";
+        let result = query.with(()).first::<String, &mut Conn>(conn).await?;
+        Ok(result)
+    }
+
+    pub async fn set_chain_head(conn: &mut Conn, hash: &str) -> anyhow::Result<()> {
+        let query = r"UPDATE audit_chain_head SET hash = :hash WHERE chain_id = 1;";
+        let params = params! {
+            "hash" => hash,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn add_audit_log(conn: &mut Conn, log: &AuditLog) -> anyhow::Result<Option<u32>> {
+        let query = r"INSERT INTO audit_logs (admin_id,api_key_id,action,entity_type,entity_id,before_value,after_value,created_at,prev_hash,hash)
+        VALUES (:admin_id,:api_key_id,:action,:entity_type,:entity_id,:before_value,:after_value,:created_at,:prev_hash,:hash);";
+        let params = params! {
+            "admin_id" => log.admin_id,
+            "api_key_id" => log.api_key_id,
+            "action" => &log.action,
+            "entity_type" => &log.entity_type,
+            "entity_id" => log.entity_id,
+            "before_value" => &log.before_value,
+            "after_value" => &log.after_value,
+            "created_at" => log.created_at,
+            "prev_hash" => &log.prev_hash,
+            "hash" => &log.hash,
+        };
+        query.with(params).run(&mut *conn).await?;
+        let query = r"SELECT LAST_INSERT_ID() as audit_log_id;";
+        let audit_log_id = query.with(()).first::<u32, &mut Conn>(conn).await?;
+        Ok(audit_log_id)
+    }
+
+    pub async fn get_audit_log_count(conn: &mut Conn, filter: &AuditFilter) -> anyhow::Result<u64> {
+        let query = r"SELECT COUNT(*) FROM audit_logs
+        WHERE (:admin_id IS NULL OR admin_id = :admin_id)
+        AND (:api_key_id IS NULL OR api_key_id = :api_key_id)
+        AND (:action IS NULL OR action = :action)
+        AND (:entity_type IS NULL OR entity_type = :entity_type)
+        AND (:entity_id IS NULL OR entity_id = :entity_id)
+        AND (:from IS NULL OR created_at >= :from)
+        AND (:to IS NULL OR created_at < :to);";
+        let params = Params::from(filter_params(filter));
+        let result = query.with(params).first::<u64, &mut Conn>(conn).await?;
+        Ok(result.unwrap_or(0))
+    }
+
+    pub async fn get_audit_logs(
+        conn: &mut Conn,
+        filter: &AuditFilter,
+        offset: u64,
+        limit: u64,
+    ) -> anyhow::Result<Vec<AuditLog>> {
+        let query = r"SELECT audit_log_id,admin_id,api_key_id,action,entity_type,entity_id,before_value,after_value,created_at,prev_hash,hash
+        FROM audit_logs
+        WHERE (:admin_id IS NULL OR admin_id = :admin_id)
+        AND (:api_key_id IS NULL OR api_key_id = :api_key_id)
+        AND (:action IS NULL OR action = :action)
+        AND (:entity_type IS NULL OR entity_type = :entity_type)
+        AND (:entity_id IS NULL OR entity_id = :entity_id)
+        AND (:from IS NULL OR created_at >= :from)
+        AND (:to IS NULL OR created_at < :to)
+        ORDER BY audit_log_id DESC LIMIT :limit OFFSET :offset;";
+        let mut params = filter_params(filter);
+        params.push(("limit".to_string(), limit.into()));
+        params.push(("offset".to_string(), offset.into()));
+        AuditRepo::query_audit_logs(conn, query, Params::from(params)).await
+    }
+
+    pub async fn get_audit_chain(
+        conn: &mut Conn,
+        after_id: u32,
+        limit: u64,
+    ) -> anyhow::Result<Vec<AuditLog>> {
+        let query = r"SELECT audit_log_id,admin_id,api_key_id,action,entity_type,entity_id,before_value,after_value,created_at,prev_hash,hash
+        FROM audit_logs WHERE audit_log_id > :after_id ORDER BY audit_log_id ASC LIMIT :limit;";
+        let params = params! {
+            "after_id" => after_id,
+            "limit" => limit,
+        };
+        AuditRepo::query_audit_logs(conn, query, params).await
+    }
+
+    async fn query_audit_logs(
+        conn: &mut Conn,
+        query: &str,
+        params: Params,
+    ) -> anyhow::Result<Vec<AuditLog>> {
+        let result = query
+            .with(params)
+            .map(
+                conn,
+                |(
+                    audit_log_id,
+                    admin_id,
+                    api_key_id,
+                    action,
+                    entity_type,
+                    entity_id,
+                    before_value,
+                    after_value,
+                    created_at,
+                    prev_hash,
+                    hash,
+                )| AuditLog {
+                    id: audit_log_id,
+                    admin_id,
+                    api_key_id,
+                    action,
+                    entity_type,
+                    entity_id,
+                    before_value,
+                    after_value,
+                    created_at,
+                    prev_hash,
+                    hash,
+                },
+            )
+            .await?;
+
+        Ok(result)
+    }
+}
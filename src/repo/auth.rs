@@ -1,72 +1,683 @@
-use crate::entity::AuthRecord;
+use crate::entity::{
+    AdminSession, CustomerSession, EmailVerificationToken, LoginFailureKind, PasswordResetToken,
+    RefreshToken,
+};
+use crate::utils::Audience;
 use mysql_async::prelude::{Query, WithParams};
 use mysql_async::{params, Conn};
 
 pub struct AuthRepo;
 
 impl AuthRepo {
-    pub async fn create_auth_record(
+    pub async fn create_customer_session(
         conn: &mut Conn,
         customer_id: u32,
         token: &str,
-    ) -> anyhow::Result<()> {
-        let query = r"INSERT INTO authed_customers (customer_id,token,last_used,is_online)
-        VALUE (:customer_id,:token,NOW(),TRUE) ON DUPLICATE KEY UPDATE token=:token,last_used=NOW(),is_online=TRUE;";
+        user_agent: &str,
+        validity: i64,
+    ) -> anyhow::Result<Option<u32>> {
+        let query = r"INSERT INTO customer_sessions (customer_id,token,user_agent,created_at,last_used,expires_at,is_online)
+        VALUES (:customer_id,:token,:user_agent,NOW(),NOW(),NOW() + INTERVAL :validity SECOND,TRUE);";
         let params = params! {
             "customer_id" => customer_id,
             "token" => token,
+            "user_agent" => user_agent,
+            "validity" => validity,
+        };
+        query.with(params).run(&mut *conn).await?;
+        let query = r"SELECT LAST_INSERT_ID() as session_id;";
+        let session_id = query.with(()).first::<u32, &mut Conn>(conn).await?;
+        Ok(session_id)
+    }
+
+    pub async fn get_customer_session(
+        conn: &mut Conn,
+        token: &str,
+    ) -> anyhow::Result<Option<CustomerSession>> {
+        let query = r"SELECT session_id,customer_id,token,user_agent,created_at,last_used,expires_at,is_online FROM customer_sessions
+        WHERE token = :token;";
+        let params = params! {
+            "token" => token,
+        };
+        let mut result = query
+            .with(params)
+            .map(
+                conn,
+                |(
+                    session_id,
+                    customer_id,
+                    token,
+                    user_agent,
+                    created_at,
+                    last_used,
+                    expires_at,
+                    is_online,
+                )| CustomerSession {
+                    id: session_id,
+                    customer_id,
+                    token,
+                    user_agent,
+                    created_at,
+                    last_used,
+                    expires_at,
+                    is_online,
+                },
+            )
+            .await?;
+
+        Ok(result.pop())
+    }
+
+    pub async fn get_customer_sessions(
+        conn: &mut Conn,
+        customer_id: u32,
+    ) -> anyhow::Result<Vec<CustomerSession>> {
+        let query = r"SELECT session_id,customer_id,token,user_agent,created_at,last_used,expires_at,is_online FROM customer_sessions
+        WHERE customer_id = :customer_id AND is_online = TRUE AND expires_at > NOW() ORDER BY last_used DESC;";
+        let params = params! {
+            "customer_id" => customer_id,
+        };
+        let result = query
+            .with(params)
+            .map(
+                conn,
+                |(
+                    session_id,
+                    customer_id,
+                    token,
+                    user_agent,
+                    created_at,
+                    last_used,
+                    expires_at,
+                    is_online,
+                )| CustomerSession {
+                    id: session_id,
+                    customer_id,
+                    token,
+                    user_agent,
+                    created_at,
+                    last_used,
+                    expires_at,
+                    is_online,
+                },
+            )
+            .await?;
+
+        Ok(result)
+    }
+
+    pub async fn get_customer_session_history(
+        conn: &mut Conn,
+        customer_id: u32,
+    ) -> anyhow::Result<Vec<CustomerSession>> {
+        let query = r"SELECT session_id,customer_id,token,user_agent,created_at,last_used,expires_at,is_online FROM customer_sessions
+        WHERE customer_id = :customer_id ORDER BY created_at ASC;";
+        let params = params! {
+            "customer_id" => customer_id,
+        };
+        let result = query
+            .with(params)
+            .map(
+                conn,
+                |(
+                    session_id,
+                    customer_id,
+                    token,
+                    user_agent,
+                    created_at,
+                    last_used,
+                    expires_at,
+                    is_online,
+                )| CustomerSession {
+                    id: session_id,
+                    customer_id,
+                    token,
+                    user_agent,
+                    created_at,
+                    last_used,
+                    expires_at,
+                    is_online,
+                },
+            )
+            .await?;
+
+        Ok(result)
+    }
+
+    pub async fn touch_customer_session(conn: &mut Conn, session_id: u32) -> anyhow::Result<()> {
+        let query = r"UPDATE customer_sessions SET last_used=NOW() WHERE session_id=:session_id;";
+        let params = params! {
+            "session_id" => session_id,
         };
         query.with(params).run(&mut *conn).await?;
         Ok(())
     }
 
-    pub async fn update_auth_record(
+    pub async fn revoke_customer_session(
         conn: &mut Conn,
         customer_id: u32,
-        token: &str,
-        is_online: bool,
+        session_id: u32,
     ) -> anyhow::Result<()> {
-        let query = r"UPDATE authed_customers SET token=:token,last_used=NOW(),is_online=:is_online
-        WHERE customer_id=:customer_id;";
+        let query = r"UPDATE customer_sessions SET is_online=FALSE
+        WHERE session_id=:session_id AND customer_id=:customer_id;";
         let params = params! {
+            "session_id" => session_id,
             "customer_id" => customer_id,
-            "token" => token,
-            "is_online" => is_online,
         };
         query.with(params).run(&mut *conn).await?;
         Ok(())
     }
 
-    pub async fn get_auth_record(
+    pub async fn revoke_customer_sessions(conn: &mut Conn, customer_id: u32) -> anyhow::Result<()> {
+        let query = r"UPDATE customer_sessions SET is_online=FALSE WHERE customer_id=:customer_id;";
+        let params = params! {
+            "customer_id" => customer_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn revoke_other_customer_sessions(
         conn: &mut Conn,
         customer_id: u32,
-    ) -> anyhow::Result<Option<AuthRecord>> {
-        let query = r"SELECT customer_id,token,last_used,is_online FROM authed_customers
-        WHERE customer_id = :customer_id;";
+        session_id: u32,
+    ) -> anyhow::Result<()> {
+        let query = r"UPDATE customer_sessions SET is_online=FALSE
+        WHERE customer_id=:customer_id AND session_id<>:session_id;";
         let params = params! {
-            "customer_id" => customer_id
+            "customer_id" => customer_id,
+            "session_id" => session_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn get_customer_session_by_id(
+        conn: &mut Conn,
+        session_id: u32,
+    ) -> anyhow::Result<Option<CustomerSession>> {
+        let query = r"SELECT session_id,customer_id,token,user_agent,created_at,last_used,expires_at,is_online FROM customer_sessions
+        WHERE session_id = :session_id;";
+        let params = params! {
+            "session_id" => session_id,
         };
         let mut result = query
             .with(params)
-            .map(conn, |(customer_id, token, last_used, is_online)| {
-                AuthRecord {
+            .map(
+                conn,
+                |(
+                    session_id,
                     customer_id,
                     token,
+                    user_agent,
+                    created_at,
+                    last_used,
+                    expires_at,
+                    is_online,
+                )| CustomerSession {
+                    id: session_id,
+                    customer_id,
+                    token,
+                    user_agent,
+                    created_at,
+                    last_used,
+                    expires_at,
+                    is_online,
+                },
+            )
+            .await?;
+
+        Ok(result.pop())
+    }
+
+    pub async fn rotate_customer_session(
+        conn: &mut Conn,
+        session_id: u32,
+        token: &str,
+        refresh_token_hash: &str,
+        validity: i64,
+    ) -> anyhow::Result<()> {
+        let query = r"UPDATE customer_sessions SET token=:token,last_used=NOW(),expires_at=NOW() + INTERVAL :validity SECOND
+        WHERE session_id=:session_id;";
+        let params = params! {
+            "token" => token,
+            "validity" => validity,
+            "session_id" => session_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        AuthRepo::create_refresh_token(conn, session_id, refresh_token_hash).await
+    }
+
+    pub async fn create_refresh_token(
+        conn: &mut Conn,
+        session_id: u32,
+        token_hash: &str,
+    ) -> anyhow::Result<()> {
+        let query = r"INSERT INTO customer_refresh_tokens (session_id,token_hash,created_at)
+        VALUES (:session_id,:token_hash,NOW());";
+        let params = params! {
+            "session_id" => session_id,
+            "token_hash" => token_hash,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn get_refresh_token(
+        conn: &mut Conn,
+        token_hash: &str,
+    ) -> anyhow::Result<Option<RefreshToken>> {
+        let query = r"SELECT refresh_token_id,session_id,created_at,used_at FROM customer_refresh_tokens
+        WHERE token_hash = :token_hash;";
+        let params = params! {
+            "token_hash" => token_hash,
+        };
+        let mut result = query
+            .with(params)
+            .map(
+                conn,
+                |(refresh_token_id, session_id, created_at, used_at)| RefreshToken {
+                    id: refresh_token_id,
+                    session_id,
+                    created_at,
+                    used_at,
+                },
+            )
+            .await?;
+
+        Ok(result.pop())
+    }
+
+    pub async fn use_refresh_token(conn: &mut Conn, refresh_token_id: u32) -> anyhow::Result<bool> {
+        let query = r"UPDATE customer_refresh_tokens SET used_at=NOW()
+        WHERE refresh_token_id=:refresh_token_id AND used_at IS NULL;";
+        let params = params! {
+            "refresh_token_id" => refresh_token_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(conn.affected_rows() == 1)
+    }
+
+    pub async fn create_password_reset_token(
+        conn: &mut Conn,
+        customer_id: u32,
+        token_hash: &str,
+        validity: i64,
+    ) -> anyhow::Result<()> {
+        AuthRepo::invalidate_password_reset_tokens(conn, customer_id).await?;
+        let query = r"INSERT INTO password_reset_tokens (customer_id,token_hash,created_at,expires_at)
+        VALUES (:customer_id,:token_hash,NOW(),NOW() + INTERVAL :validity SECOND);";
+        let params = params! {
+            "customer_id" => customer_id,
+            "token_hash" => token_hash,
+            "validity" => validity,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn has_recent_password_reset_token(
+        conn: &mut Conn,
+        customer_id: u32,
+        cooldown: i64,
+    ) -> anyhow::Result<bool> {
+        let query = r"SELECT COUNT(*) FROM password_reset_tokens
+        WHERE customer_id=:customer_id AND created_at > NOW() - INTERVAL :cooldown SECOND;";
+        let params = params! {
+            "customer_id" => customer_id,
+            "cooldown" => cooldown,
+        };
+        let result = query.with(params).first::<u64, &mut Conn>(conn).await?;
+        Ok(result.unwrap_or_default() > 0)
+    }
+
+    pub async fn invalidate_password_reset_tokens(
+        conn: &mut Conn,
+        customer_id: u32,
+    ) -> anyhow::Result<()> {
+        let query = r"UPDATE password_reset_tokens SET used_at=NOW()
+        WHERE customer_id=:customer_id AND used_at IS NULL;";
+        let params = params! {
+            "customer_id" => customer_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn get_password_reset_token(
+        conn: &mut Conn,
+        token_hash: &str,
+    ) -> anyhow::Result<Option<PasswordResetToken>> {
+        let query = r"SELECT reset_token_id,customer_id,created_at,expires_at,used_at FROM password_reset_tokens
+        WHERE token_hash = :token_hash;";
+        let params = params! {
+            "token_hash" => token_hash,
+        };
+        let mut result = query
+            .with(params)
+            .map(
+                conn,
+                |(reset_token_id, customer_id, created_at, expires_at, used_at)| {
+                    PasswordResetToken {
+                        id: reset_token_id,
+                        customer_id,
+                        created_at,
+                        expires_at,
+                        used_at,
+                    }
+                },
+            )
+            .await?;
+
+        Ok(result.pop())
+    }
+
+    pub async fn use_password_reset_token(
+        conn: &mut Conn,
+        reset_token_id: u32,
+    ) -> anyhow::Result<bool> {
+        let query = r"UPDATE password_reset_tokens SET used_at=NOW()
+        WHERE reset_token_id=:reset_token_id AND used_at IS NULL AND expires_at > NOW();";
+        let params = params! {
+            "reset_token_id" => reset_token_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(conn.affected_rows() == 1)
+    }
+
+    pub async fn create_email_verification_token(
+        conn: &mut Conn,
+        customer_id: u32,
+        email: &str,
+        token_hash: &str,
+        validity: i64,
+    ) -> anyhow::Result<()> {
+        AuthRepo::invalidate_email_verification_tokens(conn, customer_id).await?;
+        let query = r"INSERT INTO email_verification_tokens (customer_id,email,token_hash,created_at,expires_at)
+        VALUES (:customer_id,:email,:token_hash,NOW(),NOW() + INTERVAL :validity SECOND);";
+        let params = params! {
+            "customer_id" => customer_id,
+            "email" => email,
+            "token_hash" => token_hash,
+            "validity" => validity,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn has_recent_email_verification_token(
+        conn: &mut Conn,
+        customer_id: u32,
+        cooldown: i64,
+    ) -> anyhow::Result<bool> {
+        let query = r"SELECT COUNT(*) FROM email_verification_tokens
+        WHERE customer_id=:customer_id AND created_at > NOW() - INTERVAL :cooldown SECOND;";
+        let params = params! {
+            "customer_id" => customer_id,
+            "cooldown" => cooldown,
+        };
+        let result = query.with(params).first::<u64, &mut Conn>(conn).await?;
+        Ok(result.unwrap_or_default() > 0)
+    }
+
+    pub async fn invalidate_email_verification_tokens(
+        conn: &mut Conn,
+        customer_id: u32,
+    ) -> anyhow::Result<()> {
+        let query = r"UPDATE email_verification_tokens SET used_at=NOW()
+        WHERE customer_id=:customer_id AND used_at IS NULL;";
+        let params = params! {
+            "customer_id" => customer_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn delete_email_verification_tokens(
+        conn: &mut Conn,
+        customer_id: u32,
+    ) -> anyhow::Result<()> {
+        let query = r"DELETE FROM email_verification_tokens WHERE customer_id=:customer_id;";
+        let params = params! {
+            "customer_id" => customer_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn get_email_verification_token(
+        conn: &mut Conn,
+        token_hash: &str,
+    ) -> anyhow::Result<Option<EmailVerificationToken>> {
+        let query = r"SELECT verification_token_id,customer_id,email,created_at,expires_at,used_at FROM email_verification_tokens
+        WHERE token_hash = :token_hash;";
+        let params = params! {
+            "token_hash" => token_hash,
+        };
+        let mut result = query
+            .with(params)
+            .map(
+                conn,
+                |(verification_token_id, customer_id, email, created_at, expires_at, used_at)| {
+                    EmailVerificationToken {
+                        id: verification_token_id,
+                        customer_id,
+                        email,
+                        created_at,
+                        expires_at,
+                        used_at,
+                    }
+                },
+            )
+            .await?;
+
+        Ok(result.pop())
+    }
+
+    pub async fn use_email_verification_token(
+        conn: &mut Conn,
+        verification_token_id: u32,
+    ) -> anyhow::Result<bool> {
+        let query = r"UPDATE email_verification_tokens SET used_at=NOW()
+        WHERE verification_token_id=:verification_token_id AND used_at IS NULL AND expires_at > NOW();";
+        let params = params! {
+            "verification_token_id" => verification_token_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(conn.affected_rows() == 1)
+    }
+
+    pub async fn create_admin_session(
+        conn: &mut Conn,
+        admin_id: u32,
+        token: &str,
+    ) -> anyhow::Result<Option<u32>> {
+        let query = r"INSERT INTO admin_sessions (admin_id,token,created_at,last_used,is_online)
+        VALUES (:admin_id,:token,NOW(),NOW(),TRUE);";
+        let params = params! {
+            "admin_id" => admin_id,
+            "token" => token,
+        };
+        query.with(params).run(&mut *conn).await?;
+        let query = r"SELECT LAST_INSERT_ID() as session_id;";
+        let session_id = query.with(()).first::<u32, &mut Conn>(conn).await?;
+        Ok(session_id)
+    }
+
+    pub async fn get_admin_session(
+        conn: &mut Conn,
+        token: &str,
+    ) -> anyhow::Result<Option<AdminSession>> {
+        let query = r"SELECT session_id,admin_id,token,created_at,last_used,is_online FROM admin_sessions
+        WHERE token = :token;";
+        let params = params! {
+            "token" => token,
+        };
+        let mut result = query
+            .with(params)
+            .map(
+                conn,
+                |(session_id, admin_id, token, created_at, last_used, is_online)| AdminSession {
+                    id: session_id,
+                    admin_id,
+                    token,
+                    created_at,
                     last_used,
                     is_online,
-                }
-            })
+                },
+            )
             .await?;
 
         Ok(result.pop())
     }
 
-    pub async fn is_online(conn: &mut Conn, customer_id: u32) -> anyhow::Result<Option<bool>> {
-        let query = r"SELECT is_online FROM authed_customers WHERE customer_id = :customer_id;";
+    pub async fn get_admin_sessions(
+        conn: &mut Conn,
+        admin_id: u32,
+    ) -> anyhow::Result<Vec<AdminSession>> {
+        let query = r"SELECT session_id,admin_id,token,created_at,last_used,is_online FROM admin_sessions
+        WHERE admin_id = :admin_id AND is_online = TRUE ORDER BY last_used DESC;";
+        let params = params! {
+            "admin_id" => admin_id,
+        };
+        let result = query
+            .with(params)
+            .map(
+                conn,
+                |(session_id, admin_id, token, created_at, last_used, is_online)| AdminSession {
+                    id: session_id,
+                    admin_id,
+                    token,
+                    created_at,
+                    last_used,
+                    is_online,
+                },
+            )
+            .await?;
+
+        Ok(result)
+    }
+
+    pub async fn touch_admin_session(conn: &mut Conn, session_id: u32) -> anyhow::Result<()> {
+        let query = r"UPDATE admin_sessions SET last_used=NOW() WHERE session_id=:session_id;";
+        let params = params! {
+            "session_id" => session_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn revoke_admin_session(conn: &mut Conn, session_id: u32) -> anyhow::Result<()> {
+        let query = r"UPDATE admin_sessions SET is_online=FALSE WHERE session_id=:session_id;";
+        let params = params! {
+            "session_id" => session_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn revoke_admin_sessions(conn: &mut Conn, admin_id: u32) -> anyhow::Result<()> {
+        let query = r"UPDATE admin_sessions SET is_online=FALSE WHERE admin_id=:admin_id;";
         let params = params! {
-            "customer_id" => customer_id
+            "admin_id" => admin_id,
         };
-        let result = query.with(params).first::<bool, &mut Conn>(conn).await?;
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn revoke_other_admin_sessions(
+        conn: &mut Conn,
+        admin_id: u32,
+        session_id: u32,
+    ) -> anyhow::Result<()> {
+        let query = r"UPDATE admin_sessions SET is_online=FALSE
+        WHERE admin_id=:admin_id AND session_id<>:session_id;";
+        let params = params! {
+            "admin_id" => admin_id,
+            "session_id" => session_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn get_login_lock(
+        conn: &mut Conn,
+        scope: Audience,
+        kind: LoginFailureKind,
+        subject: &str,
+    ) -> anyhow::Result<Option<i64>> {
+        let query = r"SELECT TIMESTAMPDIFF(SECOND,NOW(),locked_until) FROM login_failures
+        WHERE scope=:scope AND kind=:kind AND subject=:subject AND locked_until > NOW();";
+        let params = params! {
+            "scope" => scope.to_string(),
+            "kind" => kind.to_string(),
+            "subject" => subject,
+        };
+        let result = query.with(params).first::<i64, &mut Conn>(conn).await?;
         Ok(result)
     }
+
+    pub async fn record_login_failure(
+        conn: &mut Conn,
+        scope: Audience,
+        kind: LoginFailureKind,
+        subject: &str,
+        window: i64,
+    ) -> anyhow::Result<u32> {
+        let query = r"INSERT INTO login_failures (scope,kind,subject,failures,last_failure)
+        VALUES (:scope,:kind,:subject,1,NOW())
+        ON DUPLICATE KEY UPDATE failures=IF(last_failure < NOW() - INTERVAL :window SECOND,1,failures + 1),last_failure=NOW();";
+        let params = params! {
+            "scope" => scope.to_string(),
+            "kind" => kind.to_string(),
+            "subject" => subject,
+            "window" => window,
+        };
+        query.with(params).run(&mut *conn).await?;
+        let query = r"SELECT failures FROM login_failures
+        WHERE scope=:scope AND kind=:kind AND subject=:subject;";
+        let params = params! {
+            "scope" => scope.to_string(),
+            "kind" => kind.to_string(),
+            "subject" => subject,
+        };
+        let failures = query.with(params).first::<u32, &mut Conn>(conn).await?;
+        Ok(failures.unwrap_or(1))
+    }
+
+    pub async fn lock_login(
+        conn: &mut Conn,
+        scope: Audience,
+        kind: LoginFailureKind,
+        subject: &str,
+        seconds: i64,
+    ) -> anyhow::Result<()> {
+        let query = r"UPDATE login_failures SET locked_until=NOW() + INTERVAL :seconds SECOND
+        WHERE scope=:scope AND kind=:kind AND subject=:subject;";
+        let params = params! {
+            "seconds" => seconds,
+            "scope" => scope.to_string(),
+            "kind" => kind.to_string(),
+            "subject" => subject,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn clear_login_failures(
+        conn: &mut Conn,
+        scope: Audience,
+        kind: LoginFailureKind,
+        subject: &str,
+    ) -> anyhow::Result<bool> {
+        let query =
+            r"DELETE FROM login_failures WHERE scope=:scope AND kind=:kind AND subject=:subject;";
+        let params = params! {
+            "scope" => scope.to_string(),
+            "kind" => kind.to_string(),
+            "subject" => subject,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(conn.affected_rows() > 0)
+    }
 }
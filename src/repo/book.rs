@@ -1,174 +1,81 @@
 use crate::entity::{
-    Author, Book, BookInSeries, Keyword, PriceInquiry, PriceInquiryStatus, Publisher, Series,
-    Supplier,
+    Author, Book, BookFilter, BookInSeries, BookSort, Keyword, PriceInquiry, PriceInquiryStatus,
+    Publisher, Series, Suggestion, Supplier,
 };
+use crate::utils::{boolean_term, SearchQuery};
 use mysql_async::prelude::{Query, WithParams};
-use mysql_async::{params, Conn};
+use mysql_async::{params, Conn, Params};
 use mysql_common::bigdecimal::BigDecimal;
+use std::collections::HashMap;
+
+const BOOK_FILTER: &str = r"(:publisher_id IS NULL OR books.publisher_id = :publisher_id)
+        AND (:author_id IS NULL OR EXISTS (SELECT 1 FROM book_authors WHERE book_authors.book_id = books.book_id AND book_authors.author_id = :author_id))
+        AND (:keyword_id IS NULL OR EXISTS (SELECT 1 FROM book_keywords WHERE book_keywords.book_id = books.book_id AND book_keywords.keyword_id = :keyword_id))
+        AND (:series_id IS NULL OR EXISTS (SELECT 1 FROM series_books WHERE series_books.book_id = books.book_id AND series_books.series_id = :series_id))
+        AND (:min_price IS NULL OR books.price >= :min_price)
+        AND (:max_price IS NULL OR books.price <= :max_price)
+        AND (:in_stock = FALSE OR EXISTS (SELECT 1 FROM book_locations WHERE book_locations.book_id = books.book_id AND book_locations.quantity > 0))
+        AND (:onstore_only = FALSE OR books.is_onstore = TRUE)";
+
+fn book_filter_params(filter: &BookFilter) -> Vec<(String, mysql_async::Value)> {
+    vec![
+        ("publisher_id".to_string(), filter.publisher_id.into()),
+        ("author_id".to_string(), filter.author_id.into()),
+        ("keyword_id".to_string(), filter.keyword_id.into()),
+        ("series_id".to_string(), filter.series_id.into()),
+        ("min_price".to_string(), filter.min_price.clone().into()),
+        ("max_price".to_string(), filter.max_price.clone().into()),
+        ("in_stock".to_string(), filter.in_stock.into()),
+        ("onstore_only".to_string(), filter.onstore_only.into()),
+    ]
+}
+
+fn search_match(param: &str) -> String {
+    format!(
+        r"(MATCH ( books.title ) AGAINST ( :{param} IN BOOLEAN MODE )
+        OR MATCH ( books.catalog ) AGAINST ( :{param} IN BOOLEAN MODE )
+        OR EXISTS (SELECT 1 FROM book_authors JOIN `authors` ON `authors`.author_id = book_authors.author_id
+            WHERE book_authors.book_id = books.book_id AND MATCH ( `authors`.`name` ) AGAINST ( :{param} IN BOOLEAN MODE ))
+        OR EXISTS (SELECT 1 FROM book_keywords JOIN keywords ON keywords.keyword_id = book_keywords.keyword_id
+            WHERE book_keywords.book_id = books.book_id AND MATCH ( keywords.keyword ) AGAINST ( :{param} IN BOOLEAN MODE )))"
+    )
+}
+
+fn search_condition(
+    search: &SearchQuery,
+    filter: &BookFilter,
+) -> (String, Vec<(String, mysql_async::Value)>) {
+    let mut condition = vec![BOOK_FILTER.to_string()];
+    let mut params = book_filter_params(filter);
+    params.push(("terms".to_string(), search.positive_terms().into()));
+    for (i, term) in search.required.iter().enumerate() {
+        let param = format!("required_{}", i);
+        condition.push(search_match(&param));
+        params.push((param, boolean_term(term).into()));
+    }
+    if search.required.is_empty() && !search.optional.is_empty() {
+        condition.push(search_match("terms"));
+    }
+    for (i, term) in search.excluded.iter().enumerate() {
+        let param = format!("excluded_{}", i);
+        condition.push(format!("NOT {}", search_match(&param)));
+        params.push((param, boolean_term(term).into()));
+    }
+    (condition.join("\n        AND "), params)
+}
+
+fn id_list(ids: &[u32]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
 
 pub struct BookRepo;
 
 impl BookRepo {
     pub async fn get_book_detail(conn: &mut Conn, book_id: u32) -> anyhow::Result<Option<Book>> {
-        let query = r"SELECT
-	DISTINCT books.book_id,
-	books.isbn,
-	books.title,
-	GROUP_CONCAT( DISTINCT CONCAT( `authors`.author_id, ',', `authors`.`name` ) ORDER BY book_authors.`order` ASC SEPARATOR ';' ),
-GROUP_CONCAT( DISTINCT CONCAT( keywords.keyword_id, ',', keywords.keyword ) SEPARATOR ';' ),
-GROUP_CONCAT( DISTINCT CONCAT( publishers.publisher_id, ',', publishers.`name` ) SEPARATOR ';' ),
-	GROUP_CONCAT(
-		DISTINCT CONCAT(
-			suppliers.supplier_id,
-			',',
-			suppliers.`name`,
-			',',
-			suppliers.telephone,
-			',',
-			suppliers.email,
-			',',
-			suppliers.address,
-			',',
-			suppliers.fax
-		) SEPARATOR ';'
-),
-GROUP_CONCAT( DISTINCT CONCAT( series.series_id, ',', series.series_title, ',', series_books.column_num ) SEPARATOR ';' ) AS series,
-books.price,
-books.catalog,
-books.cover,
-books.is_onstore
-FROM
-	books
-	LEFT JOIN publishers ON publishers.publisher_id = books.publisher_id
-	LEFT JOIN book_authors ON book_authors.book_id = books.book_id
-	LEFT JOIN `authors` ON `authors`.author_id = book_authors.author_id
-	LEFT JOIN book_suppliers ON book_suppliers.book_id = books.book_id
-	LEFT JOIN suppliers ON book_suppliers.supplier_id = suppliers.supplier_id
-	LEFT JOIN book_keywords ON book_keywords.book_id = books.book_id
-	LEFT JOIN keywords ON book_keywords.keyword_id = keywords.keyword_id
-	LEFT JOIN series_books ON series_books.book_id = books.book_id
-	LEFT JOIN series ON series_books.series_id = series.series_id
-WHERE
-	books.book_id = :book_id
-GROUP BY books.book_id;";
-        let params = params! {
-            "book_id" => book_id,
-        };
-        let mut result = query
-            .with(params)
-            .map(
-                conn,
-                |(
-                    book_id,
-                    isbn,
-                    title,
-                    authors,
-                    keywords,
-                    publisher,
-                    suppliers,
-                    series,
-                    price,
-                    catalog,
-                    cover,
-                    is_onstore,
-                )| Book {
-                    id: book_id,
-                    isbn,
-                    title,
-                    authors: {
-                        let authors: Option<String> = authors;
-                        match authors {
-                            Some(authors) => authors
-                                .split(';')
-                                .map(|s| {
-                                    let mut iter = s.split(',');
-                                    Author {
-                                        id: iter.next().unwrap().parse().unwrap(),
-                                        name: iter.next().unwrap().to_string(),
-                                    }
-                                })
-                                .collect(),
-                            None => Vec::new(),
-                        }
-                    },
-                    keywords: {
-                        let keywords: Option<String> = keywords;
-                        match keywords {
-                            Some(keywords) => keywords
-                                .split(';')
-                                .map(|s| {
-                                    let mut iter = s.split(',');
-                                    Keyword {
-                                        id: iter.next().unwrap().parse().unwrap(),
-                                        keyword: iter.next().unwrap().to_string(),
-                                    }
-                                })
-                                .collect(),
-                            None => Vec::new(),
-                        }
-                    },
-                    publisher: {
-                        let publisher: Option<String> = publisher;
-                        match publisher {
-                            Some(publisher) => {
-                                let mut iter = publisher.split(',');
-                                Publisher {
-                                    id: iter.next().unwrap().parse().unwrap(),
-                                    name: iter.next().unwrap().to_string(),
-                                }
-                            }
-                            None => Publisher {
-                                id: 0,
-                                name: "".to_string(),
-                            },
-                        }
-                    },
-                    suppliers: {
-                        let suppliers: Option<String> = suppliers;
-                        match suppliers {
-                            Some(suppliers) => suppliers
-                                .split(';')
-                                .map(|s| {
-                                    let mut iter = s.split(',');
-                                    Supplier {
-                                        id: iter.next().unwrap().parse().unwrap(),
-                                        name: iter.next().unwrap().to_string(),
-                                        telephone: iter.next().unwrap().to_string(),
-                                        email: iter.next().unwrap().to_string(),
-                                        address: iter.next().unwrap().to_string(),
-                                        fax: iter.next().unwrap().to_string(),
-                                    }
-                                })
-                                .collect(),
-                            None => Vec::new(),
-                        }
-                    },
-                    in_series: {
-                        let series: Option<String> = series;
-
-                        match series {
-                            Some(series) => series
-                                .split(';')
-                                .map(|s| {
-                                    let mut iter = s.split(',');
-                                    BookInSeries {
-                                        series_id: iter.next().unwrap().parse().unwrap(),
-                                        title: iter.next().unwrap().to_string(),
-                                        column: iter.next().unwrap().parse().unwrap(),
-                                    }
-                                })
-                                .collect(),
-                            None => Vec::new(),
-                        }
-                    },
-                    price,
-                    catalog,
-                    cover,
-                    is_onstore,
-                },
-            )
-            .await?;
-
+        let mut result = BookRepo::get_books_by_ids(conn, &[book_id]).await?;
         Ok(result.pop())
     }
 
@@ -188,684 +95,388 @@ GROUP BY books.book_id;";
         Ok(result.pop())
     }
 
-    pub async fn get_book_list(conn: &mut Conn) -> anyhow::Result<Vec<Book>> {
-        let query = r"SELECT
-	books.book_id,
-	books.isbn,
-	books.title,
-	GROUP_CONCAT( DISTINCT CONCAT( `authors`.author_id, ',', `authors`.`name` ) ORDER BY book_authors.`order` ASC SEPARATOR ';' ),
-GROUP_CONCAT( DISTINCT CONCAT( keywords.keyword_id, ',', keywords.keyword ) SEPARATOR ';' ),
-GROUP_CONCAT( DISTINCT CONCAT( publishers.publisher_id, ',', publishers.`name` ) SEPARATOR ';' ),
-	GROUP_CONCAT(
-		DISTINCT CONCAT(
-			suppliers.supplier_id,
-			',',
-			suppliers.`name`,
-			',',
-			suppliers.telephone,
-			',',
-			suppliers.email,
-			',',
-			suppliers.address,
-			',',
-			suppliers.fax
-		) SEPARATOR ';'
-),
-GROUP_CONCAT( DISTINCT CONCAT( series.series_id, ',', series.series_title, ',', series_books.column_num ) SEPARATOR ';' ) AS series,
-books.price,
-books.catalog,
-books.cover,
-books.is_onstore
-FROM
-	books
-	LEFT JOIN publishers ON publishers.publisher_id = books.publisher_id
-	LEFT JOIN book_authors ON book_authors.book_id = books.book_id
-	LEFT JOIN `authors` ON `authors`.author_id = book_authors.author_id
-	LEFT JOIN book_suppliers ON book_suppliers.book_id = books.book_id
-	LEFT JOIN suppliers ON book_suppliers.supplier_id = suppliers.supplier_id
-	LEFT JOIN book_keywords ON book_keywords.book_id = books.book_id
-	LEFT JOIN keywords ON book_keywords.keyword_id = keywords.keyword_id
-	LEFT JOIN series_books ON series_books.book_id = books.book_id
-	LEFT JOIN series ON series_books.series_id = series.series_id
-GROUP BY books.book_id;";
-        let result = query
-            .with(())
-            .map(
-                conn,
-                |(
-                    book_id,
-                    isbn,
-                    title,
-                    authors,
-                    keywords,
-                    publisher,
-                    suppliers,
-                    series,
-                    price,
-                    catalog,
-                    cover,
-                    is_onstore,
-                )| Book {
-                    id: book_id,
-                    isbn,
-                    title,
-                    authors: {
-                        let authors: Option<String> = authors;
-                        match authors {
-                            Some(authors) => authors
-                                .split(';')
-                                .map(|s| {
-                                    let mut iter = s.split(',');
-                                    Author {
-                                        id: iter.next().unwrap().parse().unwrap(),
-                                        name: iter.next().unwrap().to_string(),
-                                    }
-                                })
-                                .collect(),
-                            None => Vec::new(),
-                        }
-                    },
-                    keywords: {
-                        let keywords: Option<String> = keywords;
-                        match keywords {
-                            Some(keywords) => keywords
-                                .split(';')
-                                .map(|s| {
-                                    let mut iter = s.split(',');
-                                    Keyword {
-                                        id: iter.next().unwrap().parse().unwrap(),
-                                        keyword: iter.next().unwrap().to_string(),
-                                    }
-                                })
-                                .collect(),
-                            None => Vec::new(),
-                        }
-                    },
-                    publisher: {
-                        let publisher: Option<String> = publisher;
-                        match publisher {
-                            Some(publisher) => {
-                                let mut iter = publisher.split(',');
-                                Publisher {
-                                    id: iter.next().unwrap().parse().unwrap(),
-                                    name: iter.next().unwrap().to_string(),
-                                }
-                            }
-                            None => Publisher {
-                                id: 0,
-                                name: "".to_string(),
-                            },
-                        }
-                    },
-                    suppliers: {
-                        let suppliers: Option<String> = suppliers;
-                        match suppliers {
-                            Some(suppliers) => suppliers
-                                .split(';')
-                                .map(|s| {
-                                    let mut iter = s.split(',');
-                                    Supplier {
-                                        id: iter.next().unwrap().parse().unwrap(),
-                                        name: iter.next().unwrap().to_string(),
-                                        telephone: iter.next().unwrap().to_string(),
-                                        email: iter.next().unwrap().to_string(),
-                                        address: iter.next().unwrap().to_string(),
-                                        fax: iter.next().unwrap().to_string(),
-                                    }
-                                })
-                                .collect(),
-                            None => Vec::new(),
-                        }
-                    },
-                    in_series: {
-                        let series: Option<String> = series;
+    pub async fn lock_book_onstore(conn: &mut Conn, book_id: u32) -> anyhow::Result<Option<bool>> {
+        let query = r"SELECT is_onstore FROM books WHERE book_id=:book_id FOR UPDATE;";
+        let params = params! {
+            "book_id" => book_id,
+        };
+        let result = query.with(params).first::<bool, &mut Conn>(conn).await?;
+        Ok(result)
+    }
 
-                        match series {
-                            Some(series) => series
-                                .split(';')
-                                .map(|s| {
-                                    let mut iter = s.split(',');
-                                    BookInSeries {
-                                        series_id: iter.next().unwrap().parse().unwrap(),
-                                        title: iter.next().unwrap().to_string(),
-                                        column: iter.next().unwrap().parse().unwrap(),
-                                    }
-                                })
-                                .collect(),
-                            None => Vec::new(),
-                        }
-                    },
-                    price,
-                    catalog,
-                    cover,
-                    is_onstore,
-                },
-            )
+    pub async fn get_book_id_by_isbn(conn: &mut Conn, isbn: &str) -> anyhow::Result<Option<u32>> {
+        let query = r"SELECT book_id FROM books WHERE isbn=:isbn;";
+        let params = params! {
+            "isbn" => isbn,
+        };
+        let result = query.with(params).first::<u32, &mut Conn>(conn).await?;
+        Ok(result)
+    }
+
+    pub async fn update_book_cover(
+        conn: &mut Conn,
+        book_id: u32,
+        cover: &str,
+    ) -> anyhow::Result<()> {
+        let query = r"UPDATE books SET cover=:cover WHERE book_id=:book_id;";
+        let params = params! {
+            "book_id" => book_id,
+            "cover" => cover,
+        };
+        query.with(params).run(conn).await?;
+        Ok(())
+    }
+
+    pub async fn get_cover_usage(conn: &mut Conn, cover: &str) -> anyhow::Result<u64> {
+        let query = r"SELECT COUNT(*) FROM books WHERE cover=:cover;";
+        let params = params! {
+            "cover" => cover,
+        };
+        let result = query.with(params).first::<u64, &mut Conn>(conn).await?;
+        Ok(result.unwrap_or_default())
+    }
+
+    pub async fn get_book_count(conn: &mut Conn, filter: &BookFilter) -> anyhow::Result<u64> {
+        let query = format!(r"SELECT COUNT(*) FROM books WHERE {};", BOOK_FILTER);
+        let params = Params::from(book_filter_params(filter));
+        let result = query.with(params).first::<u64, &mut Conn>(conn).await?;
+        Ok(result.unwrap_or(0))
+    }
+
+    pub async fn get_book_page_ids(
+        conn: &mut Conn,
+        filter: &BookFilter,
+        sort: BookSort,
+        offset: u64,
+        limit: u64,
+    ) -> anyhow::Result<Vec<u32>> {
+        let order = match sort {
+            BookSort::Title => "books.title ASC, books.book_id ASC",
+            BookSort::Price => "books.price ASC, books.book_id ASC",
+            BookSort::PriceDesc => "books.price DESC, books.book_id ASC",
+            BookSort::Newest => "books.book_id DESC",
+            BookSort::BestSelling => {
+                "(SELECT COALESCE(SUM(order_items.quantity),0) FROM order_items
+        JOIN orders ON orders.order_id = order_items.order_id
+        WHERE order_items.book_id = books.book_id AND orders.payment_status = 'paid') DESC, books.book_id ASC"
+            }
+        };
+        let query = format!(
+            r"SELECT books.book_id FROM books WHERE {} ORDER BY {} LIMIT :limit OFFSET :offset;",
+            BOOK_FILTER, order
+        );
+        let mut params = book_filter_params(filter);
+        params.push(("limit".to_string(), limit.into()));
+        params.push(("offset".to_string(), offset.into()));
+        let result = query
+            .with(Params::from(params))
+            .map(conn, |book_id: u32| book_id)
             .await?;
         Ok(result)
     }
 
-    pub async fn search_by_title_natural(
+    pub async fn get_search_count(
         conn: &mut Conn,
-        title: &str,
-    ) -> anyhow::Result<Vec<Book>> {
-        let query = r"SELECT
+        search: &SearchQuery,
+        filter: &BookFilter,
+    ) -> anyhow::Result<u64> {
+        let (condition, params) = search_condition(search, filter);
+        let query = format!(r"SELECT COUNT(*) FROM books WHERE {};", condition);
+        let result = query
+            .with(Params::from(params))
+            .first::<u64, &mut Conn>(conn)
+            .await?;
+        Ok(result.unwrap_or(0))
+    }
+
+    pub async fn search_book_ids(
+        conn: &mut Conn,
+        search: &SearchQuery,
+        filter: &BookFilter,
+        offset: u64,
+        limit: u64,
+    ) -> anyhow::Result<Vec<(u32, f64)>> {
+        let (condition, mut params) = search_condition(search, filter);
+        let query = format!(
+            r"SELECT
 	books.book_id,
-	books.isbn,
-	books.title,
-	GROUP_CONCAT( DISTINCT CONCAT( `authors`.author_id, ',', `authors`.`name` ) ORDER BY book_authors.`order` ASC SEPARATOR ';' ),
-GROUP_CONCAT( DISTINCT CONCAT( keywords.keyword_id, ',', keywords.keyword ) SEPARATOR ';' ),
-GROUP_CONCAT( DISTINCT CONCAT( publishers.publisher_id, ',', publishers.`name` ) SEPARATOR ';' ),
-	GROUP_CONCAT(
-		DISTINCT CONCAT(
-			suppliers.supplier_id,
-			',',
-			suppliers.`name`,
-			',',
-			suppliers.telephone,
-			',',
-			suppliers.email,
-			',',
-			suppliers.address,
-			',',
-			suppliers.fax
-		) SEPARATOR ';'
-),
-GROUP_CONCAT( DISTINCT CONCAT( series.series_id, ',', series.series_title, ',', series_books.column_num ) SEPARATOR ';' ) AS series,
-books.price,
-books.catalog,
-books.cover,
-books.is_onstore
+	3 * MATCH ( books.title ) AGAINST ( :terms IN BOOLEAN MODE )
+	+ MATCH ( books.catalog ) AGAINST ( :terms IN BOOLEAN MODE )
+	+ 2 * ( SELECT COALESCE( SUM( MATCH ( `authors`.`name` ) AGAINST ( :terms IN BOOLEAN MODE ) ), 0 )
+		FROM book_authors JOIN `authors` ON `authors`.author_id = book_authors.author_id
+		WHERE book_authors.book_id = books.book_id )
+	+ 2 * ( SELECT COALESCE( SUM( MATCH ( keywords.keyword ) AGAINST ( :terms IN BOOLEAN MODE ) ), 0 )
+		FROM book_keywords JOIN keywords ON keywords.keyword_id = book_keywords.keyword_id
+		WHERE book_keywords.book_id = books.book_id ) AS score
 FROM
 	books
-	LEFT JOIN publishers ON publishers.publisher_id = books.publisher_id
-	LEFT JOIN book_authors ON book_authors.book_id = books.book_id
-	LEFT JOIN `authors` ON `authors`.author_id = book_authors.author_id
-	LEFT JOIN book_suppliers ON book_suppliers.book_id = books.book_id
-	LEFT JOIN suppliers ON book_suppliers.supplier_id = suppliers.supplier_id
-	LEFT JOIN book_keywords ON book_keywords.book_id = books.book_id
-	LEFT JOIN keywords ON book_keywords.keyword_id = keywords.keyword_id
-	LEFT JOIN series_books ON series_books.book_id = books.book_id
-	LEFT JOIN series ON series_books.series_id = series.series_id
 WHERE
-	MATCH ( books.title ) AGAINST ( :title IN NATURAL LANGUAGE MODE )
-GROUP BY
-	books.book_id
+	{}
 ORDER BY
-	MATCH ( books.title ) AGAINST ( :title IN NATURAL LANGUAGE MODE ) DESC;";
-        let params = params! {
-            "title" => title,
-        };
+	score DESC,
+	books.book_id ASC
+LIMIT :limit OFFSET :offset;",
+            condition
+        );
+        params.push(("limit".to_string(), limit.into()));
+        params.push(("offset".to_string(), offset.into()));
         let result = query
-            .with(params)
+            .with(Params::from(params))
+            .map(conn, |(book_id, score): (u32, f64)| (book_id, score))
+            .await?;
+        Ok(result)
+    }
+
+    pub async fn get_books_by_ids(conn: &mut Conn, book_ids: &[u32]) -> anyhow::Result<Vec<Book>> {
+        if book_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let ids = id_list(book_ids);
+
+        let query = format!(
+            r"SELECT books.book_id,books.isbn,books.title,publishers.publisher_id,publishers.`name`,
+        books.price,books.catalog,books.cover,books.is_onstore
+        FROM books LEFT JOIN publishers ON publishers.publisher_id = books.publisher_id
+        WHERE books.book_id IN ({});",
+            ids
+        );
+        let rows = query
+            .with(())
             .map(
-                conn,
+                &mut *conn,
                 |(
                     book_id,
                     isbn,
                     title,
-                    authors,
-                    keywords,
-                    publisher,
-                    suppliers,
-                    series,
+                    publisher_id,
+                    publisher_name,
                     price,
                     catalog,
                     cover,
                     is_onstore,
                 )| {
+                    let publisher_id: Option<u32> = publisher_id;
+                    let publisher_name: Option<String> = publisher_name;
                     Book {
                         id: book_id,
                         isbn,
                         title,
-                        authors: {
-                            let authors: Option<String> = authors;
-                            match authors {
-                                Some(authors) => authors
-                                    .split(';')
-                                    .map(|s| {
-                                        let mut iter = s.split(',');
-                                        Author {
-                                            id: iter.next().unwrap().parse().unwrap(),
-                                            name: iter.next().unwrap().to_string(),
-                                        }
-                                    })
-                                    .collect(),
-                                None => Vec::new(),
-                            }
-                        },
-                        keywords: {
-                            let keywords: Option<String> = keywords;
-                            match keywords {
-                                Some(keywords) => keywords
-                                    .split(';')
-                                    .map(|s| {
-                                        let mut iter = s.split(',');
-                                        Keyword {
-                                            id: iter.next().unwrap().parse().unwrap(),
-                                            keyword: iter.next().unwrap().to_string(),
-                                        }
-                                    })
-                                    .collect(),
-                                None => Vec::new(),
-                            }
-                        },
-                        publisher: {
-                            let publisher: Option<String> = publisher;
-                            match publisher {
-                                Some(publisher) => {
-                                    let mut iter = publisher.split(',');
-                                    Publisher {
-                                        id: iter.next().unwrap().parse().unwrap(),
-                                        name: iter.next().unwrap().to_string(),
-                                    }
-                                }
-                                None => Publisher {
-                                    id: 0,
-                                    name: "".to_string(),
-                                },
-                            }
-                        },
-                        suppliers: {
-                            let suppliers: Option<String> = suppliers;
-                            match suppliers {
-                                Some(suppliers) => suppliers
-                                    .split(';')
-                                    .map(|s| {
-                                        let mut iter = s.split(',');
-                                        Supplier {
-                                            id: iter.next().unwrap().parse().unwrap(),
-                                            name: iter.next().unwrap().to_string(),
-                                            telephone: iter.next().unwrap().to_string(),
-                                            email: iter.next().unwrap().to_string(),
-                                            address: iter.next().unwrap().to_string(),
-                                            fax: iter.next().unwrap().to_string(),
-                                        }
-                                    })
-                                    .collect(),
-                                None => Vec::new(),
-                            }
-                        },
-                        in_series: {
-                            let series: Option<String> = series;
-
-                            match series {
-                                Some(series) => series
-                                    .split(';')
-                                    .map(|s| {
-                                        let mut iter = s.split(',');
-                                        BookInSeries {
-                                            series_id: iter.next().unwrap().parse().unwrap(),
-                                            title: iter.next().unwrap().to_string(),
-                                            column: iter.next().unwrap().parse().unwrap(),
-                                        }
-                                    })
-                                    .collect(),
-                                None => Vec::new(),
-                            }
+                        publisher: Publisher {
+                            id: publisher_id.unwrap_or_default(),
+                            name: publisher_name.unwrap_or_default(),
                         },
                         price,
                         catalog,
                         cover,
                         is_onstore,
+                        ..Default::default()
                     }
                 },
             )
             .await?;
+        let mut books: HashMap<u32, Book> = rows.into_iter().map(|book| (book.id, book)).collect();
+
+        let query = format!(
+            r"SELECT book_authors.book_id,`authors`.author_id,`authors`.`name`
+        FROM book_authors JOIN `authors` ON `authors`.author_id = book_authors.author_id
+        WHERE book_authors.book_id IN ({})
+        ORDER BY book_authors.book_id ASC, book_authors.`order` ASC;",
+            ids
+        );
+        let authors = query
+            .with(())
+            .map(
+                &mut *conn,
+                |(book_id, author_id, name): (u32, u32, String)| {
+                    (
+                        book_id,
+                        Author {
+                            id: author_id,
+                            name,
+                        },
+                    )
+                },
+            )
+            .await?;
+        for (book_id, author) in authors {
+            if let Some(book) = books.get_mut(&book_id) {
+                book.authors.push(author);
+            }
+        }
 
-        Ok(result)
+        let query = format!(
+            r"SELECT book_keywords.book_id,keywords.keyword_id,keywords.keyword
+        FROM book_keywords JOIN keywords ON keywords.keyword_id = book_keywords.keyword_id
+        WHERE book_keywords.book_id IN ({})
+        ORDER BY book_keywords.book_id ASC, keywords.keyword_id ASC;",
+            ids
+        );
+        let keywords = query
+            .with(())
+            .map(
+                &mut *conn,
+                |(book_id, keyword_id, keyword): (u32, u32, String)| {
+                    (
+                        book_id,
+                        Keyword {
+                            id: keyword_id,
+                            keyword,
+                        },
+                    )
+                },
+            )
+            .await?;
+        for (book_id, keyword) in keywords {
+            if let Some(book) = books.get_mut(&book_id) {
+                book.keywords.push(keyword);
+            }
+        }
+
+        let query = format!(
+            r"SELECT book_suppliers.book_id,suppliers.supplier_id,suppliers.`name`,suppliers.telephone,
+        suppliers.email,suppliers.address,suppliers.fax
+        FROM book_suppliers JOIN suppliers ON suppliers.supplier_id = book_suppliers.supplier_id
+        WHERE book_suppliers.book_id IN ({})
+        ORDER BY book_suppliers.book_id ASC, suppliers.supplier_id ASC;",
+            ids
+        );
+        let suppliers = query
+            .with(())
+            .map(
+                &mut *conn,
+                |(book_id, supplier_id, name, telephone, email, address, fax): (
+                    u32,
+                    u32,
+                    String,
+                    String,
+                    String,
+                    String,
+                    String,
+                )| {
+                    (
+                        book_id,
+                        Supplier {
+                            id: supplier_id,
+                            name,
+                            telephone,
+                            email,
+                            address,
+                            fax,
+                        },
+                    )
+                },
+            )
+            .await?;
+        for (book_id, supplier) in suppliers {
+            if let Some(book) = books.get_mut(&book_id) {
+                book.suppliers.push(supplier);
+            }
+        }
+
+        let query = format!(
+            r"SELECT series_books.book_id,series.series_id,series.series_title,series_books.column_num
+        FROM series_books JOIN series ON series.series_id = series_books.series_id
+        WHERE series_books.book_id IN ({})
+        ORDER BY series_books.book_id ASC, series.series_id ASC;",
+            ids
+        );
+        let series = query
+            .with(())
+            .map(
+                &mut *conn,
+                |(book_id, series_id, title, column): (u32, u32, String, u32)| {
+                    (
+                        book_id,
+                        BookInSeries {
+                            series_id,
+                            title,
+                            column,
+                        },
+                    )
+                },
+            )
+            .await?;
+        for (book_id, in_series) in series {
+            if let Some(book) = books.get_mut(&book_id) {
+                book.in_series.push(in_series);
+            }
+        }
+
+        Ok(book_ids
+            .iter()
+            .filter_map(|book_id| books.remove(book_id))
+            .collect())
+    }
+
+    pub async fn search_by_title_natural(
+        conn: &mut Conn,
+        title: &str,
+    ) -> anyhow::Result<Vec<Book>> {
+        let query = r"SELECT books.book_id FROM books
+        WHERE books.is_onstore = TRUE
+        AND MATCH ( books.title ) AGAINST ( :title IN NATURAL LANGUAGE MODE )
+        ORDER BY MATCH ( books.title ) AGAINST ( :title IN NATURAL LANGUAGE MODE ) DESC;";
+        let params = params! {
+            "title" => title,
+        };
+        let book_ids = query
+            .with(params)
+            .map(&mut *conn, |book_id: u32| book_id)
+            .await?;
+        BookRepo::get_books_by_ids(conn, &book_ids).await
     }
 
     pub async fn search_by_author_natural(
         conn: &mut Conn,
         authors: &str,
     ) -> anyhow::Result<Vec<Book>> {
-        let query = r"SELECT
-	books.book_id,
-	books.isbn,
-	books.title,
-	GROUP_CONCAT( DISTINCT CONCAT( `authors`.author_id, ',', `authors`.`name` ) ORDER BY book_authors.`order` ASC SEPARATOR ';' ),
-GROUP_CONCAT( DISTINCT CONCAT( keywords.keyword_id, ',', keywords.keyword ) SEPARATOR ';' ),
-GROUP_CONCAT( DISTINCT CONCAT( publishers.publisher_id, ',', publishers.`name` ) SEPARATOR ';' ),
-GROUP_CONCAT(
-	DISTINCT CONCAT(
-		suppliers.supplier_id,
-		',',
-		suppliers.`name`,
-		',',
-		suppliers.telephone,
-		',',
-		suppliers.email,
-		',',
-		suppliers.address,
-		',',
-		suppliers.fax
-	) SEPARATOR ';'
-),
-GROUP_CONCAT( DISTINCT CONCAT( series.series_id, ',', series.series_title, ',', series_books.column_num ) SEPARATOR ';' ) AS series,
-books.price,
-books.catalog,
-books.cover,
-books.is_onstore
-FROM
-	books
-	LEFT JOIN publishers ON publishers.publisher_id = books.publisher_id
-	LEFT JOIN book_authors ON book_authors.book_id = books.book_id
-	LEFT JOIN `authors` ON `authors`.author_id = book_authors.author_id
-	LEFT JOIN book_suppliers ON book_suppliers.book_id = books.book_id
-	LEFT JOIN suppliers ON book_suppliers.supplier_id = suppliers.supplier_id
-	LEFT JOIN book_keywords ON book_keywords.book_id = books.book_id
-	LEFT JOIN keywords ON book_keywords.keyword_id = keywords.keyword_id
-	LEFT JOIN series_books ON series_books.book_id = books.book_id
-	LEFT JOIN series ON series_books.series_id = series.series_id
-WHERE
-	MATCH ( `authors`.`name` ) AGAINST ( :authors IN NATURAL LANGUAGE MODE )
-GROUP BY
-	books.book_id
-ORDER BY
-	SUM( MATCH ( `authors`.`name` ) AGAINST ( :authors IN NATURAL LANGUAGE MODE ) ) DESC;";
+        let query = r"SELECT books.book_id FROM books
+        JOIN book_authors ON book_authors.book_id = books.book_id
+        JOIN `authors` ON `authors`.author_id = book_authors.author_id
+        WHERE books.is_onstore = TRUE
+        AND MATCH ( `authors`.`name` ) AGAINST ( :authors IN NATURAL LANGUAGE MODE )
+        GROUP BY books.book_id
+        ORDER BY SUM( MATCH ( `authors`.`name` ) AGAINST ( :authors IN NATURAL LANGUAGE MODE ) ) DESC;";
         let params = params! {
             "authors" => authors,
         };
-        let result = query
+        let book_ids = query
             .with(params)
-            .map(
-                conn,
-                |(
-                    book_id,
-                    isbn,
-                    title,
-                    authors,
-                    keywords,
-                    publisher,
-                    suppliers,
-                    series,
-                    price,
-                    catalog,
-                    cover,
-                    is_onstore,
-                )| {
-                    Book {
-                        id: book_id,
-                        isbn,
-                        title,
-                        authors: {
-                            let authors: Option<String> = authors;
-                            match authors {
-                                Some(authors) => authors
-                                    .split(';')
-                                    .map(|s| {
-                                        let mut iter = s.split(',');
-                                        Author {
-                                            id: iter.next().unwrap().parse().unwrap(),
-                                            name: iter.next().unwrap().to_string(),
-                                        }
-                                    })
-                                    .collect(),
-                                None => Vec::new(),
-                            }
-                        },
-                        keywords: {
-                            let keywords: Option<String> = keywords;
-                            match keywords {
-                                Some(keywords) => keywords
-                                    .split(';')
-                                    .map(|s| {
-                                        let mut iter = s.split(',');
-                                        Keyword {
-                                            id: iter.next().unwrap().parse().unwrap(),
-                                            keyword: iter.next().unwrap().to_string(),
-                                        }
-                                    })
-                                    .collect(),
-                                None => Vec::new(),
-                            }
-                        },
-                        publisher: {
-                            let publisher: Option<String> = publisher;
-                            match publisher {
-                                Some(publisher) => {
-                                    let mut iter = publisher.split(',');
-                                    Publisher {
-                                        id: iter.next().unwrap().parse().unwrap(),
-                                        name: iter.next().unwrap().to_string(),
-                                    }
-                                }
-                                None => Publisher {
-                                    id: 0,
-                                    name: "".to_string(),
-                                },
-                            }
-                        },
-                        suppliers: {
-                            let suppliers: Option<String> = suppliers;
-                            match suppliers {
-                                Some(suppliers) => suppliers
-                                    .split(';')
-                                    .map(|s| {
-                                        let mut iter = s.split(',');
-                                        Supplier {
-                                            id: iter.next().unwrap().parse().unwrap(),
-                                            name: iter.next().unwrap().to_string(),
-                                            telephone: iter.next().unwrap().to_string(),
-                                            email: iter.next().unwrap().to_string(),
-                                            address: iter.next().unwrap().to_string(),
-                                            fax: iter.next().unwrap().to_string(),
-                                        }
-                                    })
-                                    .collect(),
-                                None => Vec::new(),
-                            }
-                        },
-                        in_series: {
-                            let series: Option<String> = series;
-
-                            match series {
-                                Some(series) => series
-                                    .split(';')
-                                    .map(|s| {
-                                        let mut iter = s.split(',');
-                                        BookInSeries {
-                                            series_id: iter.next().unwrap().parse().unwrap(),
-                                            title: iter.next().unwrap().to_string(),
-                                            column: iter.next().unwrap().parse().unwrap(),
-                                        }
-                                    })
-                                    .collect(),
-                                None => Vec::new(),
-                            }
-                        },
-                        price,
-                        catalog,
-                        cover,
-                        is_onstore,
-                    }
-                },
-            )
+            .map(&mut *conn, |book_id: u32| book_id)
             .await?;
-
-        Ok(result)
+        BookRepo::get_books_by_ids(conn, &book_ids).await
     }
 
     pub async fn search_by_keyword_natural(
         conn: &mut Conn,
         keywords: &str,
     ) -> anyhow::Result<Vec<Book>> {
-        let query = r"SELECT
-	books.book_id,
-	books.isbn,
-	books.title,
-	GROUP_CONCAT( DISTINCT CONCAT( `authors`.author_id, ',', `authors`.`name` ) ORDER BY book_authors.`order` ASC SEPARATOR ';' ),
-GROUP_CONCAT( DISTINCT CONCAT( keywords.keyword_id, ',', keywords.keyword ) SEPARATOR ';' ),
-GROUP_CONCAT( DISTINCT CONCAT( publishers.publisher_id, ',', publishers.`name` ) SEPARATOR ';' ),
-GROUP_CONCAT(
-	DISTINCT CONCAT(
-		suppliers.supplier_id,
-		',',
-		suppliers.`name`,
-		',',
-		suppliers.telephone,
-		',',
-		suppliers.email,
-		',',
-		suppliers.address,
-		',',
-		suppliers.fax
-	) SEPARATOR ';'
-),
-GROUP_CONCAT( DISTINCT CONCAT( series.series_id, ',', series.series_title, ',', series_books.column_num ) SEPARATOR ';' ) AS series,
-books.price,
-books.catalog,
-books.cover,
-books.is_onstore
-FROM
-	books
-	LEFT JOIN publishers ON publishers.publisher_id = books.publisher_id
-	LEFT JOIN book_authors ON book_authors.book_id = books.book_id
-	LEFT JOIN `authors` ON `authors`.author_id = book_authors.author_id
-	LEFT JOIN book_suppliers ON book_suppliers.book_id = books.book_id
-	LEFT JOIN suppliers ON book_suppliers.supplier_id = suppliers.supplier_id
-	LEFT JOIN book_keywords ON book_keywords.book_id = books.book_id
-	LEFT JOIN keywords ON book_keywords.keyword_id = keywords.keyword_id
-	LEFT JOIN series_books ON series_books.book_id = books.book_id
-	LEFT JOIN series ON series_books.series_id = series.series_id
-WHERE
-	MATCH ( keywords.keyword ) AGAINST ( :keywords IN NATURAL LANGUAGE MODE )
-GROUP BY
-	books.book_id
-ORDER BY
-	SUM( MATCH ( keywords.keyword ) AGAINST ( :keywords IN NATURAL LANGUAGE MODE ) ) DESC;";
+        let query = r"SELECT books.book_id FROM books
+        JOIN book_keywords ON book_keywords.book_id = books.book_id
+        JOIN keywords ON keywords.keyword_id = book_keywords.keyword_id
+        WHERE books.is_onstore = TRUE
+        AND MATCH ( keywords.keyword ) AGAINST ( :keywords IN NATURAL LANGUAGE MODE )
+        GROUP BY books.book_id
+        ORDER BY SUM( MATCH ( keywords.keyword ) AGAINST ( :keywords IN NATURAL LANGUAGE MODE ) ) DESC;";
         let params = params! {
             "keywords" => keywords,
         };
-        let result = query
+        let book_ids = query
             .with(params)
-            .map(
-                conn,
-                |(
-                    book_id,
-                    isbn,
-                    title,
-                    authors,
-                    keywords,
-                    publisher,
-                    suppliers,
-                    series,
-                    price,
-                    catalog,
-                    cover,
-                    is_onstore,
-                )| {
-                    Book {
-                        id: book_id,
-                        isbn,
-                        title,
-                        authors: {
-                            let authors: Option<String> = authors;
-                            match authors {
-                                Some(authors) => authors
-                                    .split(';')
-                                    .map(|s| {
-                                        let mut iter = s.split(',');
-                                        Author {
-                                            id: iter.next().unwrap().parse().unwrap(),
-                                            name: iter.next().unwrap().to_string(),
-                                        }
-                                    })
-                                    .collect(),
-                                None => Vec::new(),
-                            }
-                        },
-                        keywords: {
-                            let keywords: Option<String> = keywords;
-                            match keywords {
-                                Some(keywords) => keywords
-                                    .split(';')
-                                    .map(|s| {
-                                        let mut iter = s.split(',');
-                                        Keyword {
-                                            id: iter.next().unwrap().parse().unwrap(),
-                                            keyword: iter.next().unwrap().to_string(),
-                                        }
-                                    })
-                                    .collect(),
-                                None => Vec::new(),
-                            }
-                        },
-                        publisher: {
-                            let publisher: Option<String> = publisher;
-                            match publisher {
-                                Some(publisher) => {
-                                    let mut iter = publisher.split(',');
-                                    Publisher {
-                                        id: iter.next().unwrap().parse().unwrap(),
-                                        name: iter.next().unwrap().to_string(),
-                                    }
-                                }
-                                None => Publisher {
-                                    id: 0,
-                                    name: "".to_string(),
-                                },
-                            }
-                        },
-                        suppliers: {
-                            let suppliers: Option<String> = suppliers;
-                            match suppliers {
-                                Some(suppliers) => suppliers
-                                    .split(';')
-                                    .map(|s| {
-                                        let mut iter = s.split(',');
-                                        Supplier {
-                                            id: iter.next().unwrap().parse().unwrap(),
-                                            name: iter.next().unwrap().to_string(),
-                                            telephone: iter.next().unwrap().to_string(),
-                                            email: iter.next().unwrap().to_string(),
-                                            address: iter.next().unwrap().to_string(),
-                                            fax: iter.next().unwrap().to_string(),
-                                        }
-                                    })
-                                    .collect(),
-                                None => Vec::new(),
-                            }
-                        },
-                        in_series: {
-                            let series: Option<String> = series;
-
-                            match series {
-                                Some(series) => series
-                                    .split(';')
-                                    .map(|s| {
-                                        let mut iter = s.split(',');
-                                        BookInSeries {
-                                            series_id: iter.next().unwrap().parse().unwrap(),
-                                            title: iter.next().unwrap().to_string(),
-                                            column: iter.next().unwrap().parse().unwrap(),
-                                        }
-                                    })
-                                    .collect(),
-                                None => Vec::new(),
-                            }
-                        },
-                        price,
-                        catalog,
-                        cover,
-                        is_onstore,
-                    }
-                },
-            )
+            .map(&mut *conn, |book_id: u32| book_id)
             .await?;
-
-        Ok(result)
+        BookRepo::get_books_by_ids(conn, &book_ids).await
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn add_book(
         conn: &mut Conn,
         isbn: &str,
         title: &str,
-        authors: &Vec<u32>,
-        keywords: &Vec<u32>,
-        series: &Vec<(u32, u32)>,
-        supplier: &Vec<u32>,
+        authors: &[u32],
+        keywords: &[u32],
+        series: &[(u32, u32)],
+        supplier: &[u32],
         publisher: u32,
         price: BigDecimal,
         catalog: &str,
@@ -1003,6 +614,26 @@ ORDER BY
         Ok(result)
     }
 
+    pub async fn get_suggestion_list(conn: &mut Conn) -> anyhow::Result<Vec<Suggestion>> {
+        let query = r"SELECT 'title',book_id,title FROM books WHERE is_onstore = TRUE
+        UNION ALL SELECT 'author',author_id,`name` FROM `authors`
+        UNION ALL SELECT 'keyword',keyword_id,keyword FROM keywords
+        UNION ALL SELECT 'publisher',publisher_id,`name` FROM publishers
+        UNION ALL SELECT 'series',series_id,series_title FROM series;";
+        let result = query
+            .with(())
+            .map(conn, |(kind, id, text)| Suggestion {
+                kind: {
+                    let kind: String = kind;
+                    kind.parse().unwrap()
+                },
+                id,
+                text,
+            })
+            .await?;
+        Ok(result)
+    }
+
     pub async fn get_series_list(conn: &mut Conn) -> anyhow::Result<Vec<Series>> {
         let query = r"SELECT series_id,series_title FROM series;";
         let result = query
@@ -1044,15 +675,16 @@ ORDER BY
         Ok(publisher_id)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn update_book(
         conn: &mut Conn,
         book_id: u32,
         isbn: &str,
         title: &str,
-        authors: &Vec<u32>,
-        keywords: &Vec<u32>,
-        series: &Vec<(u32, u32)>,
-        supplier: &Vec<u32>,
+        authors: &[u32],
+        keywords: &[u32],
+        series: &[(u32, u32)],
+        supplier: &[u32],
         publisher: u32,
         price: BigDecimal,
         catalog: &str,
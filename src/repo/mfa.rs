@@ -0,0 +1,212 @@
+use crate::entity::{AdminLoginChallenge, AdminMfa};
+use mysql_async::prelude::{Query, WithParams};
+use mysql_async::{params, Conn};
+
+pub struct MfaRepo;
+
+impl MfaRepo {
+    pub async fn get_admin_mfa(conn: &mut Conn, admin_id: u32) -> anyhow::Result<Option<AdminMfa>> {
+        let query = r"SELECT admin_id,secret,is_enabled,last_step,created_at,enabled_at FROM admin_mfa
+        WHERE admin_id=:admin_id;";
+        let params = params! {
+            "admin_id" => admin_id,
+        };
+        let mut result = query
+            .with(params)
+            .map(
+                conn,
+                |(admin_id, secret, is_enabled, last_step, created_at, enabled_at)| AdminMfa {
+                    admin_id,
+                    secret,
+                    is_enabled,
+                    last_step,
+                    created_at,
+                    enabled_at,
+                },
+            )
+            .await?;
+
+        Ok(result.pop())
+    }
+
+    pub async fn save_admin_mfa_secret(
+        conn: &mut Conn,
+        admin_id: u32,
+        secret: &str,
+    ) -> anyhow::Result<()> {
+        let query = r"INSERT INTO admin_mfa (admin_id,secret,is_enabled,last_step,created_at,enabled_at)
+        VALUES (:admin_id,:secret,FALSE,NULL,NOW(),NULL)
+        ON DUPLICATE KEY UPDATE secret=:secret,last_step=NULL,created_at=NOW();";
+        let params = params! {
+            "admin_id" => admin_id,
+            "secret" => secret,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn enable_admin_mfa(
+        conn: &mut Conn,
+        admin_id: u32,
+        step: i64,
+    ) -> anyhow::Result<bool> {
+        let query = r"UPDATE admin_mfa SET is_enabled=TRUE,enabled_at=NOW(),last_step=:step
+        WHERE admin_id=:admin_id AND is_enabled=FALSE;";
+        let params = params! {
+            "admin_id" => admin_id,
+            "step" => step,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(conn.affected_rows() == 1)
+    }
+
+    pub async fn use_totp_step(conn: &mut Conn, admin_id: u32, step: i64) -> anyhow::Result<bool> {
+        let query = r"UPDATE admin_mfa SET last_step=:step
+        WHERE admin_id=:admin_id AND (last_step IS NULL OR last_step < :step);";
+        let params = params! {
+            "admin_id" => admin_id,
+            "step" => step,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(conn.affected_rows() == 1)
+    }
+
+    pub async fn delete_admin_mfa(conn: &mut Conn, admin_id: u32) -> anyhow::Result<()> {
+        let query = r"DELETE FROM admin_recovery_codes WHERE admin_id=:admin_id;";
+        let params = params! {
+            "admin_id" => admin_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        let query = r"DELETE FROM admin_mfa WHERE admin_id=:admin_id;";
+        let params = params! {
+            "admin_id" => admin_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn set_recovery_codes(
+        conn: &mut Conn,
+        admin_id: u32,
+        code_hashes: &[String],
+    ) -> anyhow::Result<()> {
+        let query = r"DELETE FROM admin_recovery_codes WHERE admin_id=:admin_id;";
+        let params = params! {
+            "admin_id" => admin_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+
+        for code_hash in code_hashes {
+            let query = r"INSERT INTO admin_recovery_codes (admin_id,code_hash) VALUES (:admin_id,:code_hash);";
+            let params = params! {
+                "admin_id" => admin_id,
+                "code_hash" => code_hash,
+            };
+            query.with(params).run(&mut *conn).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn use_recovery_code(
+        conn: &mut Conn,
+        admin_id: u32,
+        code_hash: &str,
+    ) -> anyhow::Result<bool> {
+        let query = r"UPDATE admin_recovery_codes SET used_at=NOW()
+        WHERE admin_id=:admin_id AND code_hash=:code_hash AND used_at IS NULL LIMIT 1;";
+        let params = params! {
+            "admin_id" => admin_id,
+            "code_hash" => code_hash,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(conn.affected_rows() == 1)
+    }
+
+    pub async fn get_recovery_code_count(conn: &mut Conn, admin_id: u32) -> anyhow::Result<u64> {
+        let query = r"SELECT COUNT(*) FROM admin_recovery_codes WHERE admin_id=:admin_id AND used_at IS NULL;";
+        let params = params! {
+            "admin_id" => admin_id,
+        };
+        let count = query.with(params).first::<u64, &mut Conn>(conn).await?;
+        Ok(count.unwrap_or(0))
+    }
+
+    pub async fn create_login_challenge(
+        conn: &mut Conn,
+        admin_id: u32,
+        challenge_hash: &str,
+        validity: i64,
+    ) -> anyhow::Result<()> {
+        let query = r"INSERT INTO admin_login_challenges (admin_id,challenge_hash,created_at,expires_at)
+        VALUES (:admin_id,:challenge_hash,NOW(),NOW() + INTERVAL :validity SECOND);";
+        let params = params! {
+            "admin_id" => admin_id,
+            "challenge_hash" => challenge_hash,
+            "validity" => validity,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn get_login_challenge(
+        conn: &mut Conn,
+        challenge_hash: &str,
+        max_attempts: u32,
+    ) -> anyhow::Result<Option<AdminLoginChallenge>> {
+        let query = r"SELECT challenge_id,admin_id,created_at,expires_at,used_at,attempts FROM admin_login_challenges
+        WHERE challenge_hash=:challenge_hash AND used_at IS NULL AND expires_at > NOW() AND attempts < :max_attempts;";
+        let params = params! {
+            "challenge_hash" => challenge_hash,
+            "max_attempts" => max_attempts,
+        };
+        let mut result = query
+            .with(params)
+            .map(
+                conn,
+                |(challenge_id, admin_id, created_at, expires_at, used_at, attempts)| {
+                    AdminLoginChallenge {
+                        id: challenge_id,
+                        admin_id,
+                        created_at,
+                        expires_at,
+                        used_at,
+                        attempts,
+                    }
+                },
+            )
+            .await?;
+
+        Ok(result.pop())
+    }
+
+    pub async fn fail_login_challenge(conn: &mut Conn, challenge_id: u32) -> anyhow::Result<()> {
+        let query = r"UPDATE admin_login_challenges SET attempts=attempts+1 WHERE challenge_id=:challenge_id;";
+        let params = params! {
+            "challenge_id" => challenge_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn use_login_challenge(
+        conn: &mut Conn,
+        challenge_id: u32,
+        max_attempts: u32,
+    ) -> anyhow::Result<bool> {
+        let query = r"UPDATE admin_login_challenges SET used_at=NOW()
+        WHERE challenge_id=:challenge_id AND used_at IS NULL AND expires_at > NOW() AND attempts < :max_attempts;";
+        let params = params! {
+            "challenge_id" => challenge_id,
+            "max_attempts" => max_attempts,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(conn.affected_rows() == 1)
+    }
+
+    pub async fn revoke_admin_sessions_without_mfa(conn: &mut Conn) -> anyhow::Result<()> {
+        let query = r"UPDATE admin_sessions SET is_online=FALSE
+        WHERE admin_id NOT IN (SELECT admin_id FROM admin_mfa WHERE is_enabled=TRUE);";
+        query.run(&mut *conn).await?;
+        Ok(())
+    }
+}
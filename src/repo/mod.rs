@@ -1,9 +1,14 @@
 mod admin;
+mod api_key;
+mod audit;
 mod auth;
 mod book;
+mod mfa;
 mod order;
 mod publisher;
 mod purchase_order;
+mod role;
+mod setting;
 mod shortage;
 mod stock;
 mod supplier;
@@ -11,11 +16,16 @@ mod user;
 mod utils;
 
 pub use admin::AdminRepo;
+pub use api_key::ApiKeyRepo;
+pub use audit::AuditRepo;
 pub use auth::AuthRepo;
 pub use book::BookRepo;
+pub use mfa::MfaRepo;
 pub use order::OrderRepo;
 pub use publisher::PublisherRepo;
 pub use purchase_order::PurchaseOrderRepo;
+pub use role::RoleRepo;
+pub use setting::SettingRepo;
 pub use shortage::ShortageRepo;
 pub use stock::StockRepo;
 pub use supplier::SupplierRepo;
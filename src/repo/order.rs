@@ -357,13 +357,12 @@ WHERE order_items.order_id = :order_id;";
             "order_id" => order_id,
         };
         query.with(params).run(&mut *conn).await?;
-        match status {
-            OrderPaymentStatus::Paid => {
-                if payment_status == OrderPaymentStatus::Paid.to_string() {
-                    anyhow::bail!("payment already completed");
-                }
+        if status == OrderPaymentStatus::Paid {
+            if payment_status == OrderPaymentStatus::Paid.to_string() {
+                anyhow::bail!("payment already completed");
+            }
 
-                let query = r"SET @total_purchase = (
+            let query = r"SET @total_purchase = (
 	SELECT
 		SUM( books.price * order_items.quantity * ( 100 - credit_rules.discount_percentage ) * 0.01 )
 	FROM
@@ -375,26 +374,54 @@ WHERE order_items.order_id = :order_id;";
 	WHERE
 		order_items.order_id = :order_id
 );";
-                let params = params! {
-                    "order_id" => order_id,
-                };
-                query.with(params).run(&mut *conn).await?;
+            let params = params! {
+                "order_id" => order_id,
+            };
+            query.with(params).run(&mut *conn).await?;
 
-                let query = r"UPDATE customers
+            let query = r"UPDATE customers
 SET total_purchase = total_purchase + @total_purchase,
     account_balance = account_balance - @total_purchase
 WHERE customers.customer_id = ( SELECT customer_id FROM orders WHERE order_id = :order_id );";
-                let params = params! {
-                    "order_id" => order_id,
-                };
-                query.with(params).run(&mut *conn).await?;
-            }
-            _ => {}
+            let params = params! {
+                "order_id" => order_id,
+            };
+            query.with(params).run(&mut *conn).await?;
         }
 
         Ok(())
     }
 
+    pub async fn get_order_customer(conn: &mut Conn, order_id: u32) -> anyhow::Result<Option<u32>> {
+        let query = r"SELECT customer_id FROM orders WHERE order_id = :order_id;";
+        let params = params! {
+            "order_id" => order_id,
+        };
+        let result = query.with(params).first::<u32, &mut Conn>(conn).await?;
+        Ok(result)
+    }
+
+    pub async fn get_order_status(
+        conn: &mut Conn,
+        order_id: u32,
+    ) -> anyhow::Result<Option<(OrderPaymentStatus, OrderShippingStatus)>> {
+        let query =
+            r"SELECT payment_status,shipping_status FROM orders WHERE order_id = :order_id;";
+        let params = params! {
+            "order_id" => order_id,
+        };
+        let result = query
+            .with(params)
+            .first::<(String, String), &mut Conn>(conn)
+            .await?;
+        Ok(result.map(|(payment_status, shipping_status)| {
+            (
+                OrderPaymentStatus::new(&payment_status),
+                OrderShippingStatus::new(&shipping_status),
+            )
+        }))
+    }
+
     pub async fn update_order_shipping_status(
         conn: &mut Conn,
         order_id: u32,
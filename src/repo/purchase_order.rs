@@ -9,7 +9,7 @@ pub struct PurchaseOrderRepo;
 impl PurchaseOrderRepo {
     pub async fn complete_purchase_order(
         conn: &mut Conn,
-        stock: &Vec<(u32, u32, u32)>,
+        stock: &[(u32, u32, u32)],
         order_id: u32,
     ) -> anyhow::Result<()> {
         for (book_id, location_id, quantity) in stock.iter() {
@@ -0,0 +1,149 @@
+use crate::entity::{Permission, Role};
+use mysql_async::prelude::{Query, WithParams};
+use mysql_async::{params, Conn};
+
+pub struct RoleRepo;
+
+impl RoleRepo {
+    pub async fn get_admin_permissions(
+        conn: &mut Conn,
+        admin_id: u32,
+    ) -> anyhow::Result<Vec<Permission>> {
+        let query = r"SELECT DISTINCT permissions.permission_name FROM admin_roles
+        JOIN role_permissions ON admin_roles.role_id = role_permissions.role_id
+        JOIN permissions ON role_permissions.permission_id = permissions.permission_id
+        WHERE admin_roles.admin_id = :admin_id;";
+        let params = params! {
+            "admin_id" => admin_id,
+        };
+        let result = query
+            .with(params)
+            .map(conn, |permission_name: String| permission_name)
+            .await?;
+
+        Ok(result
+            .into_iter()
+            .filter_map(|permission_name| permission_name.parse().ok())
+            .collect())
+    }
+
+    pub async fn get_admin_roles(conn: &mut Conn, admin_id: u32) -> anyhow::Result<Vec<String>> {
+        let query = r"SELECT roles.role_name FROM admin_roles
+        JOIN roles ON admin_roles.role_id = roles.role_id
+        WHERE admin_roles.admin_id = :admin_id ORDER BY roles.role_name;";
+        let params = params! {
+            "admin_id" => admin_id,
+        };
+        let result = query
+            .with(params)
+            .map(conn, |role_name: String| role_name)
+            .await?;
+
+        Ok(result)
+    }
+
+    pub async fn get_role_permissions(
+        conn: &mut Conn,
+        role_id: u32,
+    ) -> anyhow::Result<Vec<Permission>> {
+        let query = r"SELECT permissions.permission_name FROM role_permissions
+        JOIN permissions ON role_permissions.permission_id = permissions.permission_id
+        WHERE role_permissions.role_id = :role_id ORDER BY permissions.permission_id;";
+        let params = params! {
+            "role_id" => role_id,
+        };
+        let result = query
+            .with(params)
+            .map(conn, |permission_name: String| permission_name)
+            .await?;
+
+        Ok(result
+            .into_iter()
+            .filter_map(|permission_name| permission_name.parse().ok())
+            .collect())
+    }
+
+    pub async fn get_role_list(conn: &mut Conn) -> anyhow::Result<Vec<Role>> {
+        let query = r"SELECT role_id,role_name,description FROM roles ORDER BY role_id;";
+        let mut result = query
+            .with(())
+            .map(&mut *conn, |(role_id, role_name, description)| Role {
+                id: role_id,
+                name: role_name,
+                description,
+                ..Default::default()
+            })
+            .await?;
+
+        for role in result.iter_mut() {
+            role.permissions = RoleRepo::get_role_permissions(conn, role.id).await?;
+        }
+
+        Ok(result)
+    }
+
+    pub async fn save_role(
+        conn: &mut Conn,
+        name: &str,
+        description: &str,
+        permissions: &[Permission],
+    ) -> anyhow::Result<Option<u32>> {
+        let query = r"INSERT INTO roles (role_name,description) VALUES (:name,:description)
+        ON DUPLICATE KEY UPDATE description=:description,role_id=LAST_INSERT_ID(role_id);";
+        let params = params! {
+            "name" => name,
+            "description" => description,
+        };
+        query.with(params).run(&mut *conn).await?;
+        let query = r"SELECT LAST_INSERT_ID() as role_id;";
+        let role_id = match query.with(()).first::<u32, &mut Conn>(&mut *conn).await? {
+            Some(role_id) => role_id,
+            None => return Ok(None),
+        };
+
+        let query = r"DELETE FROM role_permissions WHERE role_id=:role_id;";
+        let params = params! {
+            "role_id" => role_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+
+        for permission in permissions {
+            let query = r"INSERT INTO role_permissions (role_id,permission_id)
+            SELECT :role_id,permission_id FROM permissions WHERE permission_name=:permission_name;";
+            let params = params! {
+                "role_id" => role_id,
+                "permission_name" => permission.to_string(),
+            };
+            query.with(params).run(&mut *conn).await?;
+        }
+
+        Ok(Some(role_id))
+    }
+
+    pub async fn set_admin_roles(
+        conn: &mut Conn,
+        admin_id: u32,
+        roles: &[String],
+    ) -> anyhow::Result<()> {
+        let query = r"DELETE FROM admin_roles WHERE admin_id=:admin_id;";
+        let params = params! {
+            "admin_id" => admin_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+
+        for role in roles {
+            let query = r"INSERT INTO admin_roles (admin_id,role_id)
+            SELECT :admin_id,role_id FROM roles WHERE role_name=:role_name;";
+            let params = params! {
+                "admin_id" => admin_id,
+                "role_name" => role,
+            };
+            query.with(params).run(&mut *conn).await?;
+            if conn.affected_rows() == 0 {
+                anyhow::bail!("role {} not found", role);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+use mysql_async::prelude::{Query, WithParams};
+use mysql_async::{params, Conn};
+
+pub struct SettingRepo;
+
+impl SettingRepo {
+    pub async fn get_setting(conn: &mut Conn, name: &str) -> anyhow::Result<Option<String>> {
+        let query = r"SELECT setting_value FROM settings WHERE setting_name=:name;";
+        let params = params! {
+            "name" => name,
+        };
+        let result = query.with(params).first::<String, &mut Conn>(conn).await?;
+        Ok(result)
+    }
+
+    pub async fn set_setting(conn: &mut Conn, name: &str, value: &str) -> anyhow::Result<()> {
+        let query = r"INSERT INTO settings (setting_name,setting_value) VALUES (:name,:value)
+        ON DUPLICATE KEY UPDATE setting_value=:value;";
+        let params = params! {
+            "name" => name,
+            "value" => value,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+}
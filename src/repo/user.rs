@@ -1,26 +1,25 @@
-use crate::entity::{CreditRule, Customer, UserStatus};
+use crate::entity::{CreditRule, Customer, CustomerStatusChange, UserStatus};
 use mysql_async::prelude::{Query, WithParams};
 use mysql_async::{params, Conn};
 use mysql_common::bigdecimal::BigDecimal;
+use mysql_common::time::PrimitiveDateTime;
 
 pub struct UserRepo;
 
 impl UserRepo {
-    pub async fn login(
+    pub async fn get_user_password(
         conn: &mut Conn,
         username: &str,
-        password: &str,
-    ) -> anyhow::Result<Option<u32>> {
-        let query = r"SELECT customer_id FROM customers WHERE username=:username AND pwd=:pwd;";
+    ) -> anyhow::Result<Option<(u32, String)>> {
+        let query = r"SELECT customer_id,pwd FROM customers WHERE username=:username;";
         let params = params! {
             "username" => username,
-            "pwd" => password,
         };
-        let result = query.with(params).first::<u32, &mut Conn>(conn).await?;
-        match result {
-            Some(customer_id) => Ok(Some(customer_id)),
-            None => anyhow::bail!("login failed, username or password incorrect"),
-        }
+        let result = query
+            .with(params)
+            .first::<(u32, String), &mut Conn>(conn)
+            .await?;
+        Ok(result)
     }
 
     pub async fn register(
@@ -28,12 +27,15 @@ impl UserRepo {
         username: &str,
         password: &str,
         name: &str,
+        email: &str,
     ) -> anyhow::Result<Option<u32>> {
-        let query = r"INSERT INTO customers (username,pwd,name) VALUES(:username,:pwd,:name);";
+        let query =
+            r"INSERT INTO customers (username,pwd,name,email) VALUES(:username,:pwd,:name,:email);";
         let params = params! {
             "username" => username,
             "pwd" => password,
             "name" => name,
+            "email" => email,
         };
         query.with(params).run(&mut *conn).await?;
         let query = r"SELECT LAST_INSERT_ID() as customer_id;";
@@ -47,7 +49,7 @@ impl UserRepo {
     ) -> anyhow::Result<Option<Customer>> {
         let query = r"SELECT customer_id,username,pwd,name,address,email,
         account_balance,credit_level,
-        total_purchase,overdraft_limit,status
+        total_purchase,overdraft_limit,status,email_verified_at
         FROM customers WHERE username=:username;";
         let params = params! {
             "username" => username,
@@ -69,6 +71,7 @@ impl UserRepo {
                     total_purchase,
                     overdraft_limit,
                     status,
+                    email_verified_at,
                 )| {
                     Customer {
                         id: customer_id,
@@ -85,6 +88,60 @@ impl UserRepo {
                             let status: String = status;
                             status.parse().unwrap()
                         },
+                        email_verified_at,
+                    }
+                },
+            )
+            .await?;
+        Ok(result.pop())
+    }
+
+    pub async fn get_user_detail_by_id(
+        conn: &mut Conn,
+        customer_id: u32,
+    ) -> anyhow::Result<Option<Customer>> {
+        let query = r"SELECT customer_id,username,pwd,name,address,email,
+        account_balance,credit_level,
+        total_purchase,overdraft_limit,status,email_verified_at
+        FROM customers WHERE customer_id=:customer_id;";
+        let params = params! {
+            "customer_id" => customer_id,
+        };
+
+        let mut result = query
+            .with(params)
+            .map(
+                conn,
+                |(
+                    customer_id,
+                    username,
+                    pwd,
+                    name,
+                    address,
+                    email,
+                    account_balance,
+                    credit_level,
+                    total_purchase,
+                    overdraft_limit,
+                    status,
+                    email_verified_at,
+                )| {
+                    Customer {
+                        id: customer_id,
+                        username,
+                        password: pwd,
+                        name,
+                        address,
+                        email,
+                        account_balance,
+                        credit_level,
+                        total_purchase,
+                        overdraft_limit,
+                        status: {
+                            let status: String = status;
+                            status.parse().unwrap()
+                        },
+                        email_verified_at,
                     }
                 },
             )
@@ -101,6 +158,15 @@ impl UserRepo {
         Ok(result)
     }
 
+    pub async fn get_username(conn: &mut Conn, customer_id: u32) -> anyhow::Result<Option<String>> {
+        let query = r"SELECT username FROM customers WHERE customer_id=:customer_id;";
+        let params = params! {
+            "customer_id" => customer_id,
+        };
+        let result = query.with(params).first::<String, &mut Conn>(conn).await?;
+        Ok(result)
+    }
+
     pub async fn get_user_credit_level(
         conn: &mut Conn,
         username: &str,
@@ -149,7 +215,7 @@ impl UserRepo {
     pub async fn get_user_list(conn: &mut Conn) -> anyhow::Result<Vec<Customer>> {
         let query = r"SELECT customer_id,username,pwd,name,address,email,
         account_balance,credit_level,
-        total_purchase,overdraft_limit,status
+        total_purchase,overdraft_limit,status,email_verified_at
         FROM customers;";
         let result = query
             .map(
@@ -166,6 +232,7 @@ impl UserRepo {
                     total_purchase,
                     overdraft_limit,
                     status,
+                    email_verified_at,
                 )| {
                     Customer {
                         id: customer_id,
@@ -182,6 +249,7 @@ impl UserRepo {
                             let status: String = status;
                             status.parse().unwrap()
                         },
+                        email_verified_at,
                     }
                 },
             )
@@ -239,13 +307,47 @@ impl UserRepo {
         Ok(())
     }
 
+    pub async fn set_email_verified(
+        conn: &mut Conn,
+        customer_id: u32,
+        email: &str,
+    ) -> anyhow::Result<bool> {
+        let query = r"UPDATE customers SET email_verified_at=NOW()
+        WHERE customer_id=:customer_id AND email=:email;";
+        let params = params! {
+            "customer_id" => customer_id,
+            "email" => email,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(conn.affected_rows() == 1)
+    }
+
+    pub async fn clear_email_verified(conn: &mut Conn, customer_id: u32) -> anyhow::Result<()> {
+        let query = r"UPDATE customers SET email_verified_at=NULL WHERE customer_id=:customer_id;";
+        let params = params! {
+            "customer_id" => customer_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn anonymize_user(conn: &mut Conn, customer_id: u32) -> anyhow::Result<()> {
+        let query = r"UPDATE customers SET name='',address='',email='',email_verified_at=NULL
+        WHERE customer_id=:customer_id;";
+        let params = params! {
+            "customer_id" => customer_id,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
     pub async fn search_username_natural(
         conn: &mut Conn,
         username: &str,
     ) -> anyhow::Result<Vec<Customer>> {
         let query = r"SELECT customer_id,username,pwd,name,address,email,
         account_balance,credit_level,
-        total_purchase,overdraft_limit,status
+        total_purchase,overdraft_limit,status,email_verified_at
         FROM customers WHERE MATCH(username) AGAINST(:username IN NATURAL LANGUAGE MODE);";
         let params = params! {
             "username" => username,
@@ -266,6 +368,7 @@ impl UserRepo {
                     total_purchase,
                     overdraft_limit,
                     status,
+                    email_verified_at,
                 )| {
                     Customer {
                         id: customer_id,
@@ -282,6 +385,7 @@ impl UserRepo {
                             let status: String = status;
                             status.parse().unwrap()
                         },
+                        email_verified_at,
                     }
                 },
             )
@@ -308,16 +412,100 @@ impl UserRepo {
         conn: &mut Conn,
         customer_id: u32,
         status: UserStatus,
+        expires_at: Option<&str>,
     ) -> anyhow::Result<()> {
-        let query = r"UPDATE customers SET status=:status WHERE customer_id=:customer_id;";
+        let query = r"UPDATE customers SET status=:status,status_expires_at=:expires_at WHERE customer_id=:customer_id;";
         let params = params! {
             "status" => status.to_string(),
+            "expires_at" => expires_at,
             "customer_id" => customer_id,
         };
         query.with(params).run(&mut *conn).await?;
         Ok(())
     }
 
+    pub async fn get_user_status(
+        conn: &mut Conn,
+        customer_id: u32,
+    ) -> anyhow::Result<Option<(UserStatus, Option<PrimitiveDateTime>)>> {
+        let query =
+            r"SELECT status,status_expires_at FROM customers WHERE customer_id=:customer_id;";
+        let params = params! {
+            "customer_id" => customer_id,
+        };
+        let result = query
+            .with(params)
+            .first::<(String, Option<PrimitiveDateTime>), &mut Conn>(conn)
+            .await?;
+        Ok(result.map(|(status, expires_at)| (UserStatus::new(&status), expires_at)))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn add_user_status_history(
+        conn: &mut Conn,
+        customer_id: u32,
+        admin_id: Option<u32>,
+        old_status: &UserStatus,
+        new_status: &UserStatus,
+        reason: &str,
+        expires_at: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let query = r"INSERT INTO customer_status_history (customer_id,admin_id,old_status,new_status,reason,expires_at,created_at)
+        VALUES (:customer_id,:admin_id,:old_status,:new_status,:reason,:expires_at,NOW());";
+        let params = params! {
+            "customer_id" => customer_id,
+            "admin_id" => admin_id,
+            "old_status" => old_status.to_string(),
+            "new_status" => new_status.to_string(),
+            "reason" => reason,
+            "expires_at" => expires_at,
+        };
+        query.with(params).run(&mut *conn).await?;
+        Ok(())
+    }
+
+    pub async fn get_user_status_history(
+        conn: &mut Conn,
+        customer_id: u32,
+    ) -> anyhow::Result<Vec<CustomerStatusChange>> {
+        let query = r"SELECT history_id,customer_id,admin_id,old_status,new_status,reason,expires_at,created_at
+        FROM customer_status_history WHERE customer_id=:customer_id ORDER BY history_id DESC;";
+        let params = params! {
+            "customer_id" => customer_id,
+        };
+        let result = query
+            .with(params)
+            .map(
+                conn,
+                |(
+                    history_id,
+                    customer_id,
+                    admin_id,
+                    old_status,
+                    new_status,
+                    reason,
+                    expires_at,
+                    created_at,
+                )| {
+                    let old_status: String = old_status;
+                    let new_status: String = new_status;
+                    CustomerStatusChange {
+                        id: history_id,
+                        customer_id,
+                        admin_id,
+                        old_status: UserStatus::new(&old_status),
+                        new_status: UserStatus::new(&new_status),
+                        reason,
+                        expires_at,
+                        created_at,
+                    }
+                },
+            )
+            .await?;
+
+        Ok(result)
+    }
+
     pub async fn update_user_password(
         conn: &mut Conn,
         customer_id: u32,
@@ -338,7 +526,7 @@ impl UserRepo {
     ) -> anyhow::Result<Vec<Customer>> {
         let query = r"SELECT customer_id,username,pwd,name,address,email,
         account_balance,credit_level,
-        total_purchase,overdraft_limit,status
+        total_purchase,overdraft_limit,status,email_verified_at
         FROM customers WHERE MATCH(name) AGAINST(:name IN NATURAL LANGUAGE MODE)
         ORDER BY MATCH(name) AGAINST(:name IN NATURAL LANGUAGE MODE) DESC;";
         let params = params! {
@@ -360,6 +548,7 @@ impl UserRepo {
                     total_purchase,
                     overdraft_limit,
                     status,
+                    email_verified_at,
                 )| {
                     Customer {
                         id: customer_id,
@@ -376,6 +565,7 @@ impl UserRepo {
                             let status: String = status;
                             status.parse().unwrap()
                         },
+                        email_verified_at,
                     }
                 },
             )
@@ -389,7 +579,7 @@ impl UserRepo {
     ) -> anyhow::Result<Vec<Customer>> {
         let query = r"SELECT customer_id,username,pwd,name,address,email,
         account_balance,credit_level,
-        total_purchase,overdraft_limit,status
+        total_purchase,overdraft_limit,status,email_verified_at
         FROM customers WHERE MATCH(username) AGAINST(:username IN NATURAL LANGUAGE MODE)
         ORDER BY MATCH(username) AGAINST(:username IN NATURAL LANGUAGE MODE) DESC;";
         let params = params! {
@@ -411,6 +601,7 @@ impl UserRepo {
                     total_purchase,
                     overdraft_limit,
                     status,
+                    email_verified_at,
                 )| {
                     Customer {
                         id: customer_id,
@@ -427,6 +618,7 @@ impl UserRepo {
                             let status: String = status;
                             status.parse().unwrap()
                         },
+                        email_verified_at,
                     }
                 },
             )
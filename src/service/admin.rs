@@ -1,8 +1,83 @@
-use crate::entity::{Admin, AdminRole, Book, Customer, Location, Order, Shortage};
-use crate::repo::{AdminRepo, BookRepo, OrderRepo, ShortageRepo, StockRepo, UserRepo, UtilsRepo};
-use crate::utils::{encrypt_admin_password, generate_token, validate_token, Token};
+use crate::entity::{
+    Admin, AdminRole, AdminStatus, AuthedAdmin, Book, Customer, Location, Order, Permission,
+    Shortage,
+};
+use crate::repo::{
+    AdminRepo, AuthRepo, BookRepo, OrderRepo, RoleRepo, ShortageRepo, StockRepo, UserRepo,
+    UtilsRepo,
+};
+use crate::service::{
+    AdminLogin, AuditService, AuthService, BookService, MfaService, SuggestService,
+};
+use crate::utils::{
+    generate_secret, hash_password, validate_token, verify_admin_password, verify_dummy_password,
+    Audience, CoverStore, PasswordVerification, Token,
+};
+use actix_web::web::Bytes;
 use mysql_async::Conn;
 use mysql_common::bigdecimal::BigDecimal;
+use serde_json::{json, Value};
+
+#[allow(clippy::too_many_arguments)]
+fn book_value(
+    isbn: &str,
+    title: &str,
+    authors: &[u32],
+    keywords: &[u32],
+    series: &[(u32, u32)],
+    suppliers: &[u32],
+    publisher: u32,
+    price: &BigDecimal,
+    catalog: &str,
+    cover: &str,
+    is_onstore: bool,
+) -> Value {
+    json!({
+        "isbn": isbn,
+        "title": title,
+        "authors": authors,
+        "keywords": keywords,
+        "series": series,
+        "suppliers": suppliers,
+        "publisher": publisher,
+        "price": price.to_string(),
+        "catalog": catalog,
+        "cover": cover,
+        "is_onstore": is_onstore,
+    })
+}
+
+fn book_snapshot(book: &Book) -> Value {
+    book_value(
+        &book.isbn,
+        &book.title,
+        &book
+            .authors
+            .iter()
+            .map(|author| author.id)
+            .collect::<Vec<_>>(),
+        &book
+            .keywords
+            .iter()
+            .map(|keyword| keyword.id)
+            .collect::<Vec<_>>(),
+        &book
+            .in_series
+            .iter()
+            .map(|series| (series.series_id, series.column))
+            .collect::<Vec<_>>(),
+        &book
+            .suppliers
+            .iter()
+            .map(|supplier| supplier.id)
+            .collect::<Vec<_>>(),
+        book.publisher.id,
+        &book.price,
+        &book.catalog,
+        &book.cover,
+        book.is_onstore,
+    )
+}
 
 pub struct AdminService;
 
@@ -12,302 +87,863 @@ impl AdminService {
         username: &str,
         password: &str,
         role: AdminRole,
-        token: &Token,
+        admin: &AuthedAdmin,
     ) -> anyhow::Result<u32> {
-        let password = encrypt_admin_password(password).await?;
+        let password = hash_password(password).await?;
+        let roles = [role.to_string()];
 
-        match AdminService::verify_admin(conn, token, AdminRole::Admin).await? {
-            (_, _, true) => match AdminRepo::register_admin(conn, username, &password, role).await?
-            {
-                Some(admin_id) => Ok(admin_id),
-                None => anyhow::bail!("register failed"),
+        match admin.has_permission(Permission::AdminManage) {
+            true => {
+                UtilsRepo::transaction(conn).await?;
+                match AdminRepo::register_admin(conn, username, &password, role).await? {
+                    Some(admin_id) => {
+                        match RoleRepo::set_admin_roles(conn, admin_id, &roles).await {
+                            Ok(_) => {
+                                UtilsRepo::commit(conn).await?;
+                                Ok(admin_id)
+                            }
+                            Err(e) => {
+                                UtilsRepo::rollback(conn).await?;
+                                anyhow::bail!(e)
+                            }
+                        }
+                    }
+                    None => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!("register failed")
+                    }
+                }
+            }
+            false => {
+                anyhow::bail!("permission denied: admin.manage required to register admin user")
+            }
+        }
+    }
+
+    pub async fn bootstrap(conn: &mut Conn, username: &str, password: &str) -> anyhow::Result<u32> {
+        let password = hash_password(password).await?;
+        let roles = [AdminRole::Admin.to_string()];
+
+        UtilsRepo::transaction(conn).await?;
+        match AdminRepo::register_admin(conn, username, &password, AdminRole::Admin).await {
+            Ok(Some(admin_id)) => match RoleRepo::set_admin_roles(conn, admin_id, &roles).await {
+                Ok(_) => {
+                    UtilsRepo::commit(conn).await?;
+                    Ok(admin_id)
+                }
+                Err(e) => {
+                    UtilsRepo::rollback(conn).await?;
+                    anyhow::bail!(e)
+                }
             },
-            (_, _, false) => anyhow::bail!("permission denied: only admin can register admin user"),
+            Ok(None) => {
+                UtilsRepo::rollback(conn).await?;
+                anyhow::bail!("register failed")
+            }
+            Err(e) => {
+                UtilsRepo::rollback(conn).await?;
+                anyhow::bail!(e)
+            }
         }
     }
 
-    pub async fn login(conn: &mut Conn, username: &str, password: &str) -> anyhow::Result<Token> {
-        let password = encrypt_admin_password(password).await?;
+    pub async fn login(
+        conn: &mut Conn,
+        username: &str,
+        password: &str,
+        ip: &str,
+    ) -> anyhow::Result<AdminLogin> {
+        AuthService::check_login(conn, Audience::Admin, username, ip).await?;
 
-        match AdminRepo::login_admin(conn, username, &password).await? {
+        let admin_id = match AdminRepo::get_admin_password(conn, username).await? {
+            Some((admin_id, hash)) => match verify_admin_password(password, &hash).await? {
+                PasswordVerification::Valid => Some(admin_id),
+                PasswordVerification::Outdated => {
+                    let hash = hash_password(password).await?;
+                    AdminRepo::update_admin_password(conn, admin_id, &hash).await?;
+                    Some(admin_id)
+                }
+                PasswordVerification::Invalid => None,
+            },
+            None => {
+                verify_dummy_password(password).await?;
+                None
+            }
+        };
+        let admin_id = match admin_id {
+            Some(admin_id) => match AdminRepo::get_admin_detail_by_id(conn, admin_id).await? {
+                Some(admin) if admin.status == AdminStatus::Cancelled => {
+                    AuthRepo::revoke_admin_sessions(conn, admin_id).await?;
+                    anyhow::bail!("account is cancelled")
+                }
+                Some(_) => admin_id,
+                None => anyhow::bail!("admin {} not found", admin_id),
+            },
+            None => {
+                match AuthService::record_login_failure(conn, Audience::Admin, username, ip).await?
+                {
+                    Some(locked) => return Err(locked.into()),
+                    None => anyhow::bail!("login failed, username or password incorrect"),
+                }
+            }
+        };
+
+        MfaService::start_login(conn, admin_id, username).await
+    }
+
+    pub async fn get_admin_detail(conn: &mut Conn, admin: &AuthedAdmin) -> anyhow::Result<Admin> {
+        match AdminRepo::get_admin_detail_by_id(conn, admin.id).await? {
+            Some(admin) => Ok(admin),
+            None => anyhow::bail!("admin {} not found", admin.id),
+        }
+    }
+
+    pub async fn change_password(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        old_password: &str,
+        new_password: &str,
+    ) -> anyhow::Result<()> {
+        if admin.is_api_key() {
+            anyhow::bail!("api keys cannot change the account password");
+        }
+        match AdminRepo::get_admin_password(conn, &admin.username).await? {
+            Some((_, hash)) => match verify_admin_password(old_password, &hash).await? {
+                PasswordVerification::Invalid => anyhow::bail!("old password incorrect"),
+                _ => {
+                    let hash = hash_password(new_password).await?;
+                    AdminRepo::update_admin_password(conn, admin.id, &hash).await?;
+                    AuthRepo::revoke_other_admin_sessions(conn, admin.id, admin.session_id).await
+                }
+            },
+            None => anyhow::bail!("admin {} not found", admin.username),
+        }
+    }
+
+    pub async fn get_admin_list(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+    ) -> anyhow::Result<Vec<Admin>> {
+        match admin.has_permission(Permission::AdminManage) {
+            true => AdminRepo::get_user_list(conn).await,
+            false => anyhow::bail!("permission denied: admin.manage required to get admin list"),
+        }
+    }
+
+    pub async fn update_admin_status(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        admin_id: u32,
+        status: AdminStatus,
+    ) -> anyhow::Result<()> {
+        match admin.has_permission(Permission::AdminManage) {
             true => {
-                let token = generate_token(username).await?;
-                Ok(token)
+                if admin_id == admin.id {
+                    anyhow::bail!("cannot change the status of your own account");
+                }
+                let before = match AdminRepo::get_admin_detail_by_id(conn, admin_id).await? {
+                    Some(target) => target.status,
+                    None => anyhow::bail!("admin {} not found", admin_id),
+                };
+
+                UtilsRepo::transaction(conn).await?;
+                match AdminService::apply_admin_status(conn, admin, admin_id, &before, &status)
+                    .await
+                {
+                    Ok(_) => {
+                        UtilsRepo::commit(conn).await?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!(e)
+                    }
+                }
+            }
+            false => {
+                anyhow::bail!("permission denied: admin.manage required to update admin status")
             }
-            false => anyhow::bail!("login failed, username or password incorrect"),
         }
     }
 
-    pub async fn get_admin_detail(conn: &mut Conn, token: &Token) -> anyhow::Result<Admin> {
-        let username = validate_token(token).await?;
-        match AdminRepo::get_admin_detail(conn, &username).await? {
-            Some(admin) => Ok(admin),
-            None => anyhow::bail!("admin {} not found", username),
+    async fn apply_admin_status(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        admin_id: u32,
+        before: &AdminStatus,
+        status: &AdminStatus,
+    ) -> anyhow::Result<()> {
+        AdminRepo::update_admin_status(conn, admin_id, status).await?;
+        if *status == AdminStatus::Cancelled {
+            AuthRepo::revoke_admin_sessions(conn, admin_id).await?;
+        }
+        AuditService::append(
+            conn,
+            admin,
+            "admin.status",
+            "admin",
+            admin_id,
+            Some(json!({ "status": before.to_string() })),
+            Some(json!({ "status": status.to_string() })),
+        )
+        .await
+    }
+
+    pub async fn update_admin_role(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        admin_id: u32,
+        role: AdminRole,
+    ) -> anyhow::Result<()> {
+        match admin.has_permission(Permission::AdminManage) {
+            true => {
+                if admin_id == admin.id {
+                    anyhow::bail!("cannot change the role of your own account");
+                }
+                let before = match AdminRepo::get_admin_detail_by_id(conn, admin_id).await? {
+                    Some(target) => target.role,
+                    None => anyhow::bail!("admin {} not found", admin_id),
+                };
+
+                UtilsRepo::transaction(conn).await?;
+                match AdminService::apply_admin_role(conn, admin, admin_id, &before, &role).await {
+                    Ok(_) => {
+                        UtilsRepo::commit(conn).await?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!(e)
+                    }
+                }
+            }
+            false => {
+                anyhow::bail!("permission denied: admin.manage required to update admin role")
+            }
+        }
+    }
+
+    async fn apply_admin_role(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        admin_id: u32,
+        before: &AdminRole,
+        role: &AdminRole,
+    ) -> anyhow::Result<()> {
+        AdminRepo::update_admin_role(conn, admin_id, role).await?;
+        RoleRepo::set_admin_roles(conn, admin_id, &[role.to_string()]).await?;
+        AuditService::append(
+            conn,
+            admin,
+            "admin.role",
+            "admin",
+            admin_id,
+            Some(json!({ "role": before.to_string() })),
+            Some(json!({ "role": role.to_string() })),
+        )
+        .await
+    }
+
+    pub async fn reset_admin_password(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        admin_id: u32,
+    ) -> anyhow::Result<String> {
+        match admin.has_permission(Permission::AdminManage) {
+            true => {
+                if AdminRepo::get_admin_detail_by_id(conn, admin_id)
+                    .await?
+                    .is_none()
+                {
+                    anyhow::bail!("admin {} not found", admin_id);
+                }
+
+                let password = generate_secret();
+                let hash = hash_password(&password).await?;
+                UtilsRepo::transaction(conn).await?;
+                match AdminService::apply_admin_password_reset(conn, admin, admin_id, &hash).await {
+                    Ok(_) => {
+                        UtilsRepo::commit(conn).await?;
+                        Ok(password)
+                    }
+                    Err(e) => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!(e)
+                    }
+                }
+            }
+            false => {
+                anyhow::bail!("permission denied: admin.manage required to reset admin password")
+            }
         }
     }
 
+    async fn apply_admin_password_reset(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        admin_id: u32,
+        hash: &str,
+    ) -> anyhow::Result<()> {
+        AdminRepo::update_admin_password(conn, admin_id, hash).await?;
+        AuthRepo::revoke_admin_sessions(conn, admin_id).await?;
+        AuditService::append(
+            conn,
+            admin,
+            "admin.password_reset",
+            "admin",
+            admin_id,
+            None,
+            None,
+        )
+        .await
+    }
+
     pub async fn get_book_detail(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         book_id: u32,
     ) -> anyhow::Result<(Book, Vec<Location>)> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => match BookRepo::get_book_detail(conn, book_id).await? {
+        match admin.has_permission(Permission::CatalogRead) {
+            true => match BookRepo::get_book_detail(conn, book_id).await? {
                 Some(book) => {
                     let locations = StockRepo::get_location_list_by_book(conn, book_id).await?;
                     Ok((book, locations))
                 }
                 None => anyhow::bail!("book {} not found", book_id),
             },
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can get book detail")
+            false => {
+                anyhow::bail!("permission denied: catalog.read required to get book detail")
             }
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn add_book(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         isbn: &str,
         title: &str,
-        authors: &Vec<u32>,
-        keywords: &Vec<u32>,
-        series: &Vec<(u32, u32)>,
-        suppliers: &Vec<u32>,
+        authors: &[u32],
+        keywords: &[u32],
+        series: &[(u32, u32)],
+        suppliers: &[u32],
         publisher: u32,
         price: BigDecimal,
         catalog: &str,
         cover: &str,
         is_onstore: bool,
     ) -> anyhow::Result<u32> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => {
+        match admin.has_permission(Permission::CatalogWrite) {
+            true => {
+                let isbn = &BookService::check_book_isbn(conn, isbn, None).await?;
+                let after = book_value(
+                    isbn, title, authors, keywords, series, suppliers, publisher, &price, catalog,
+                    cover, is_onstore,
+                );
                 UtilsRepo::transaction(conn).await?;
-                match BookRepo::add_book(
-                    conn, isbn, title, authors, keywords, series, suppliers, publisher, price,
-                    catalog, cover, is_onstore,
+                match AdminService::insert_book(
+                    conn, admin, isbn, title, authors, keywords, series, suppliers, publisher,
+                    price, catalog, cover, is_onstore, after,
                 )
-                .await?
+                .await
                 {
-                    Some(book_id) => {
+                    Ok(book_id) => {
                         UtilsRepo::commit(conn).await?;
+                        SuggestService::invalidate();
                         Ok(book_id)
                     }
-                    None => {
+                    Err(e) => {
                         UtilsRepo::rollback(conn).await?;
-                        anyhow::bail!("add book failed")
+                        anyhow::bail!(e)
                     }
                 }
             }
-            (_, _, false) => anyhow::bail!("permission denied: only staff or admin can add book"),
+            false => anyhow::bail!("permission denied: catalog.write required to add book"),
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
+    async fn insert_book(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        isbn: &str,
+        title: &str,
+        authors: &[u32],
+        keywords: &[u32],
+        series: &[(u32, u32)],
+        suppliers: &[u32],
+        publisher: u32,
+        price: BigDecimal,
+        catalog: &str,
+        cover: &str,
+        is_onstore: bool,
+        after: Value,
+    ) -> anyhow::Result<u32> {
+        let book_id = match BookRepo::add_book(
+            conn, isbn, title, authors, keywords, series, suppliers, publisher, price, catalog,
+            cover, is_onstore,
+        )
+        .await?
+        {
+            Some(book_id) => book_id,
+            None => anyhow::bail!("add book failed"),
+        };
+        AuditService::append(conn, admin, "book.add", "book", book_id, None, Some(after)).await?;
+        Ok(book_id)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub async fn update_book(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
+        store: &CoverStore,
         book_id: u32,
         isbn: &str,
         title: &str,
-        authors: &Vec<u32>,
-        keywords: &Vec<u32>,
-        series: &Vec<(u32, u32)>,
-        suppliers: &Vec<u32>,
+        authors: &[u32],
+        keywords: &[u32],
+        series: &[(u32, u32)],
+        suppliers: &[u32],
         publisher: u32,
         price: BigDecimal,
         catalog: &str,
         cover: &str,
         is_onstore: bool,
     ) -> anyhow::Result<()> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => {
+        match admin.has_permission(Permission::CatalogWrite) {
+            true => {
+                let (before, old_cover) = match BookRepo::get_book_detail(conn, book_id).await? {
+                    Some(book) => (book_snapshot(&book), book.cover),
+                    None => anyhow::bail!("book {} not found", book_id),
+                };
+                let isbn = &BookService::check_book_isbn(conn, isbn, Some(book_id)).await?;
+                let after = book_value(
+                    isbn, title, authors, keywords, series, suppliers, publisher, &price, catalog,
+                    cover, is_onstore,
+                );
                 UtilsRepo::transaction(conn).await?;
-                match BookRepo::update_book(
-                    conn, book_id, isbn, title, authors, keywords, series, suppliers, publisher,
-                    price, catalog, cover, is_onstore,
+                match AdminService::apply_book_update(
+                    conn, admin, book_id, isbn, title, authors, keywords, series, suppliers,
+                    publisher, price, catalog, cover, is_onstore, before, after,
                 )
                 .await
                 {
                     Ok(_) => {
                         UtilsRepo::commit(conn).await?;
+                        SuggestService::invalidate();
                     }
                     Err(e) => {
                         UtilsRepo::rollback(conn).await?;
                         anyhow::bail!(e);
                     }
                 }
+                if old_cover != cover {
+                    AdminService::remove_orphan_cover(conn, store, &old_cover).await?;
+                }
                 Ok(())
             }
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can update book")
+            false => {
+                anyhow::bail!("permission denied: catalog.write required to update book")
             }
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
+    async fn apply_book_update(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        book_id: u32,
+        isbn: &str,
+        title: &str,
+        authors: &[u32],
+        keywords: &[u32],
+        series: &[(u32, u32)],
+        suppliers: &[u32],
+        publisher: u32,
+        price: BigDecimal,
+        catalog: &str,
+        cover: &str,
+        is_onstore: bool,
+        before: Value,
+        after: Value,
+    ) -> anyhow::Result<()> {
+        BookRepo::update_book(
+            conn, book_id, isbn, title, authors, keywords, series, suppliers, publisher, price,
+            catalog, cover, is_onstore,
+        )
+        .await?;
+        AuditService::append(
+            conn,
+            admin,
+            "book.update",
+            "book",
+            book_id,
+            Some(before),
+            Some(after),
+        )
+        .await
+    }
+
+    pub async fn update_book_cover(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        store: &CoverStore,
+        book_id: u32,
+        content_type: &str,
+        data: Bytes,
+    ) -> anyhow::Result<String> {
+        match admin.has_permission(Permission::CatalogWrite) {
+            true => {
+                let book = match BookRepo::get_book_detail(conn, book_id).await? {
+                    Some(book) => book,
+                    None => anyhow::bail!("book {} not found", book_id),
+                };
+                let cover = store.save(&book.isbn, content_type, data).await?;
+                UtilsRepo::transaction(conn).await?;
+                match AdminService::apply_book_cover(conn, admin, book_id, &book.cover, &cover)
+                    .await
+                {
+                    Ok(_) => UtilsRepo::commit(conn).await?,
+                    Err(e) => {
+                        UtilsRepo::rollback(conn).await?;
+                        if cover != book.cover {
+                            store.remove(&cover)?;
+                        }
+                        anyhow::bail!(e);
+                    }
+                }
+                if cover != book.cover {
+                    AdminService::remove_orphan_cover(conn, store, &book.cover).await?;
+                }
+                Ok(cover)
+            }
+            false => {
+                anyhow::bail!("permission denied: catalog.write required to update book cover")
+            }
+        }
+    }
+
+    async fn apply_book_cover(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        book_id: u32,
+        before: &str,
+        cover: &str,
+    ) -> anyhow::Result<()> {
+        BookRepo::update_book_cover(conn, book_id, cover).await?;
+        AuditService::append(
+            conn,
+            admin,
+            "book.cover",
+            "book",
+            book_id,
+            Some(json!({ "cover": before })),
+            Some(json!({ "cover": cover })),
+        )
+        .await
+    }
+
+    async fn remove_orphan_cover(
+        conn: &mut Conn,
+        store: &CoverStore,
+        cover: &str,
+    ) -> anyhow::Result<()> {
+        if cover.is_empty() || BookRepo::get_cover_usage(conn, cover).await? > 0 {
+            return Ok(());
+        }
+        if let Err(e) = store.remove(cover) {
+            log::warn!("error removing orphaned cover {}: {}", cover, e);
+        }
+        Ok(())
+    }
+
     pub async fn get_customer_list(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
     ) -> anyhow::Result<Vec<Customer>> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => UserRepo::get_user_list(conn).await,
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can get customer list")
+        match admin.has_permission(Permission::CustomerRead) {
+            true => UserRepo::get_user_list(conn).await,
+            false => {
+                anyhow::bail!("permission denied: customer.read required to get customer list")
             }
         }
     }
 
     pub async fn set_customer_balance(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         customer_id: u32,
         balance: BigDecimal,
     ) -> anyhow::Result<()> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => UserRepo::set_user_balance(conn, customer_id, balance).await,
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can set customer balance")
+        match admin.has_permission(Permission::CustomerBalance) {
+            true => {
+                let before = match UserRepo::get_user_detail_by_id(conn, customer_id).await? {
+                    Some(customer) => customer.account_balance.to_string(),
+                    None => anyhow::bail!("customer {} not found", customer_id),
+                };
+                UtilsRepo::transaction(conn).await?;
+                match AdminService::apply_customer_balance(
+                    conn,
+                    admin,
+                    customer_id,
+                    before,
+                    balance,
+                )
+                .await
+                {
+                    Ok(_) => {
+                        UtilsRepo::commit(conn).await?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!(e)
+                    }
+                }
+            }
+            false => {
+                anyhow::bail!(
+                    "permission denied: customer.balance required to set customer balance"
+                )
             }
         }
     }
 
+    async fn apply_customer_balance(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        customer_id: u32,
+        before: String,
+        balance: BigDecimal,
+    ) -> anyhow::Result<()> {
+        let after = balance.to_string();
+        UserRepo::set_user_balance(conn, customer_id, balance).await?;
+        AuditService::append(
+            conn,
+            admin,
+            "customer.balance",
+            "customer",
+            customer_id,
+            Some(json!({ "account_balance": before })),
+            Some(json!({ "account_balance": after })),
+        )
+        .await
+    }
+
     pub async fn set_customer_credit_level(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         customer_id: u32,
         credit_level: u32,
     ) -> anyhow::Result<()> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => UserRepo::set_user_credit_level(conn, customer_id, credit_level).await,
-            (_, _, false) => {
+        match admin.has_permission(Permission::CustomerCredit) {
+            true => {
+                let before = match UserRepo::get_user_detail_by_id(conn, customer_id).await? {
+                    Some(customer) => customer.credit_level,
+                    None => anyhow::bail!("customer {} not found", customer_id),
+                };
+                UtilsRepo::transaction(conn).await?;
+                match AdminService::apply_customer_credit_level(
+                    conn,
+                    admin,
+                    customer_id,
+                    before,
+                    credit_level,
+                )
+                .await
+                {
+                    Ok(_) => {
+                        UtilsRepo::commit(conn).await?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!(e)
+                    }
+                }
+            }
+            false => {
                 anyhow::bail!(
-                    "permission denied: only staff or admin can set customer credit level"
+                    "permission denied: customer.credit required to set customer credit level"
                 )
             }
         }
     }
 
+    async fn apply_customer_credit_level(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        customer_id: u32,
+        before: u32,
+        credit_level: u32,
+    ) -> anyhow::Result<()> {
+        UserRepo::set_user_credit_level(conn, customer_id, credit_level).await?;
+        AuditService::append(
+            conn,
+            admin,
+            "customer.credit",
+            "customer",
+            customer_id,
+            Some(json!({ "credit_level": before })),
+            Some(json!({ "credit_level": credit_level })),
+        )
+        .await
+    }
+
     pub async fn get_customer_order_list(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
     ) -> anyhow::Result<Vec<Order>> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => OrderRepo::get_order_list_all(conn).await,
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can get customer order list")
+        match admin.has_permission(Permission::OrderRead) {
+            true => OrderRepo::get_order_list_all(conn).await,
+            false => {
+                anyhow::bail!("permission denied: order.read required to get customer order list")
             }
         }
     }
 
     pub async fn ship_order_auto(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         order_id: u32,
     ) -> anyhow::Result<()> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => {
+        match admin.has_permission(Permission::OrderShip) {
+            true => {
+                let before = match OrderRepo::get_order_status(conn, order_id).await? {
+                    Some((_, shipping_status)) => shipping_status.to_string(),
+                    None => anyhow::bail!("order {} not found", order_id),
+                };
                 UtilsRepo::transaction(conn).await?;
-                match OrderRepo::ship_order_automatic(conn, order_id).await {
+                match AdminService::apply_order_shipment(conn, admin, order_id, before).await {
                     Ok(_) => {
                         UtilsRepo::commit(conn).await?;
+                        Ok(())
                     }
                     Err(e) => {
                         UtilsRepo::rollback(conn).await?;
-                        anyhow::bail!(e);
+                        anyhow::bail!(e)
                     }
                 }
-                Ok(())
             }
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can ship order")
+            false => {
+                anyhow::bail!("permission denied: order.ship required to ship order")
             }
         }
     }
 
+    async fn apply_order_shipment(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        order_id: u32,
+        before: String,
+    ) -> anyhow::Result<()> {
+        OrderRepo::ship_order_automatic(conn, order_id).await?;
+        let after = match OrderRepo::get_order_status(conn, order_id).await? {
+            Some((_, shipping_status)) => shipping_status.to_string(),
+            None => anyhow::bail!("order {} not found", order_id),
+        };
+        AuditService::append(
+            conn,
+            admin,
+            "order.ship",
+            "order",
+            order_id,
+            Some(json!({ "shipping_status": before })),
+            Some(json!({ "shipping_status": after, "automatic": true })),
+        )
+        .await
+    }
+
     pub async fn get_shortage_list(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
     ) -> anyhow::Result<Vec<Shortage>> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => ShortageRepo::get_shortage_list(conn).await,
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can get shortage list")
+        match admin.has_permission(Permission::ShortageRead) {
+            true => ShortageRepo::get_shortage_list(conn).await,
+            false => {
+                anyhow::bail!("permission denied: shortage.read required to get shortage list")
             }
         }
     }
 
     pub async fn get_shortage_detail(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         shortage_id: u32,
     ) -> anyhow::Result<Shortage> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => match ShortageRepo::get_shortage_detail(conn, shortage_id).await? {
+        match admin.has_permission(Permission::ShortageRead) {
+            true => match ShortageRepo::get_shortage_detail(conn, shortage_id).await? {
                 Some(shortage) => Ok(shortage),
                 None => anyhow::bail!("shortage {} not found", shortage_id),
             },
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can get shortage detail")
+            false => {
+                anyhow::bail!("permission denied: shortage.read required to get shortage detail")
             }
         }
     }
 
     pub async fn search_user(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         search: &str,
         mode: &str,
     ) -> anyhow::Result<Vec<Customer>> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => match mode {
+        match admin.has_permission(Permission::CustomerRead) {
+            true => match mode {
                 "username" => UserRepo::search_user_by_username_natural(conn, search).await,
                 "name" => UserRepo::search_user_by_name_natural(conn, search).await,
                 _ => anyhow::bail!("invalid search mode"),
             },
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can search user")
+            false => {
+                anyhow::bail!("permission denied: customer.read required to search user")
             }
         }
     }
 
     pub async fn search_user_by_username(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         username: &str,
     ) -> anyhow::Result<Vec<Customer>> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => UserRepo::search_user_by_username_natural(conn, username).await,
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can search user by username")
+        match admin.has_permission(Permission::CustomerRead) {
+            true => UserRepo::search_user_by_username_natural(conn, username).await,
+            false => {
+                anyhow::bail!(
+                    "permission denied: customer.read required to search user by username"
+                )
             }
         }
     }
 
     pub async fn search_user_by_name(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         name: &str,
     ) -> anyhow::Result<Vec<Customer>> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => UserRepo::search_user_by_name_natural(conn, name).await,
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can search user by name")
+        match admin.has_permission(Permission::CustomerRead) {
+            true => UserRepo::search_user_by_name_natural(conn, name).await,
+            false => {
+                anyhow::bail!("permission denied: customer.read required to search user by name")
             }
         }
     }
 
-    pub async fn verify_admin(
-        conn: &mut Conn,
-        token: &Token,
-        role: AdminRole,
-    ) -> anyhow::Result<(u32, String, bool)> {
-        let username = validate_token(token).await?;
-        match AdminRepo::get_admin_detail(conn, &username).await? {
-            Some(admin) => Ok((admin.id, admin.username, admin.role >= role)),
-            None => anyhow::bail!("admin {} not found", username),
+    pub async fn verify_admin(conn: &mut Conn, token: &Token) -> anyhow::Result<AuthedAdmin> {
+        let admin_id = validate_token(token, Audience::Admin).await?;
+        let session_id = AuthService::verify_admin_session(conn, admin_id, token).await?;
+        match AdminRepo::get_admin_detail_by_id(conn, admin_id).await? {
+            Some(admin) if admin.status == AdminStatus::Cancelled => {
+                anyhow::bail!("account is cancelled")
+            }
+            Some(admin) => Ok(AuthedAdmin {
+                id: admin.id,
+                session_id,
+                username: admin.username,
+                role: admin.role,
+                permissions: RoleRepo::get_admin_permissions(conn, admin_id).await?,
+                api_key_id: None,
+            }),
+            None => anyhow::bail!("admin {} not found", admin_id),
         }
     }
-
-    pub async fn get_book_list(conn: &mut Conn) -> anyhow::Result<Vec<Book>> {
-        BookRepo::get_book_list(conn).await
-    }
 }
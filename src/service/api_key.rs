@@ -0,0 +1,188 @@
+use crate::entity::{AdminStatus, ApiKey, AuthedAdmin, Permission};
+use crate::repo::{AdminRepo, ApiKeyRepo, RoleRepo, UtilsRepo};
+use crate::service::AuditService;
+use crate::utils::{generate_api_key, hash_secret, parse_datetime};
+use mysql_async::Conn;
+use serde_json::json;
+
+const API_KEY_DISPLAY_PREFIX_LEN: usize = 12;
+
+pub struct ApiKeyService;
+
+impl ApiKeyService {
+    pub async fn create_api_key(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        name: &str,
+        scopes: &[String],
+        expires_at: &str,
+    ) -> anyhow::Result<(u32, String)> {
+        match admin.has_permission(Permission::AdminManage) {
+            true => {
+                if admin.is_api_key() {
+                    anyhow::bail!("api keys cannot create other api keys");
+                }
+                if name.trim().is_empty() {
+                    anyhow::bail!("api key name is required");
+                }
+                if scopes.is_empty() {
+                    anyhow::bail!("api key needs at least one scope");
+                }
+                let permissions = scopes
+                    .iter()
+                    .map(|scope| scope.parse())
+                    .collect::<anyhow::Result<Vec<Permission>>>()?;
+                parse_datetime(expires_at)?;
+
+                let key = generate_api_key();
+                let prefix = &key[..API_KEY_DISPLAY_PREFIX_LEN];
+                UtilsRepo::transaction(conn).await?;
+                match ApiKeyService::insert_api_key(
+                    conn,
+                    admin,
+                    name,
+                    prefix,
+                    &hash_secret(&key),
+                    expires_at,
+                    &permissions,
+                )
+                .await
+                {
+                    Ok(api_key_id) => {
+                        UtilsRepo::commit(conn).await?;
+                        Ok((api_key_id, key))
+                    }
+                    Err(e) => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!(e)
+                    }
+                }
+            }
+            false => anyhow::bail!("permission denied: admin.manage required to create api key"),
+        }
+    }
+
+    async fn insert_api_key(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        name: &str,
+        prefix: &str,
+        key_hash: &str,
+        expires_at: &str,
+        permissions: &[Permission],
+    ) -> anyhow::Result<u32> {
+        let api_key_id =
+            match ApiKeyRepo::create_api_key(conn, admin.id, name, prefix, key_hash, expires_at)
+                .await?
+            {
+                Some(api_key_id) => api_key_id,
+                None => anyhow::bail!("api key expiry must be in the future"),
+            };
+        ApiKeyRepo::set_api_key_permissions(conn, api_key_id, permissions).await?;
+        AuditService::append(
+            conn,
+            admin,
+            "api_key.create",
+            "api_key",
+            api_key_id,
+            None,
+            Some(json!({
+                "name": name,
+                "prefix": prefix,
+                "scopes": permissions.iter().map(|p| p.to_string()).collect::<Vec<_>>(),
+                "expires_at": expires_at,
+            })),
+        )
+        .await?;
+        Ok(api_key_id)
+    }
+
+    pub async fn get_api_key_list(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+    ) -> anyhow::Result<Vec<ApiKey>> {
+        match admin.has_permission(Permission::AdminManage) {
+            true => ApiKeyRepo::get_api_key_list(conn).await,
+            false => anyhow::bail!("permission denied: admin.manage required to get api key list"),
+        }
+    }
+
+    pub async fn revoke_api_key(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        api_key_id: u32,
+    ) -> anyhow::Result<()> {
+        match admin.has_permission(Permission::AdminManage) {
+            true => {
+                UtilsRepo::transaction(conn).await?;
+                match ApiKeyService::apply_api_key_revoke(conn, admin, api_key_id).await {
+                    Ok(_) => {
+                        UtilsRepo::commit(conn).await?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!(e)
+                    }
+                }
+            }
+            false => anyhow::bail!("permission denied: admin.manage required to revoke api key"),
+        }
+    }
+
+    async fn apply_api_key_revoke(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        api_key_id: u32,
+    ) -> anyhow::Result<()> {
+        if !ApiKeyRepo::revoke_api_key(conn, api_key_id).await? {
+            anyhow::bail!("api key {} not found or already revoked", api_key_id);
+        }
+        AuditService::append(
+            conn,
+            admin,
+            "api_key.revoke",
+            "api_key",
+            api_key_id,
+            None,
+            None,
+        )
+        .await
+    }
+
+    pub async fn verify_api_key(conn: &mut Conn, key: &str) -> anyhow::Result<AuthedAdmin> {
+        let api_key = match ApiKeyRepo::get_api_key_by_hash(conn, &hash_secret(key)).await? {
+            Some(api_key) => api_key,
+            None => anyhow::bail!("invalid api key"),
+        };
+        if api_key.revoked_at.is_some() {
+            anyhow::bail!("api key revoked");
+        }
+        if UtilsRepo::now(conn).await? >= api_key.expires_at {
+            anyhow::bail!("api key expired");
+        }
+
+        let owner = match AdminRepo::get_admin_detail_by_id(conn, api_key.admin_id).await? {
+            Some(owner) if owner.status == AdminStatus::Cancelled => {
+                anyhow::bail!("api key owner account is cancelled")
+            }
+            Some(owner) => owner,
+            None => anyhow::bail!("admin {} not found", api_key.admin_id),
+        };
+        let owner_permissions = RoleRepo::get_admin_permissions(conn, owner.id).await?;
+        ApiKeyRepo::touch_api_key(conn, api_key.id).await?;
+
+        Ok(AuthedAdmin {
+            id: owner.id,
+            session_id: 0,
+            username: owner.username,
+            role: owner.role,
+            permissions: api_key
+                .permissions
+                .into_iter()
+                .filter(|permission| owner_permissions.contains(permission))
+                .collect(),
+            api_key_id: Some(api_key.id),
+        })
+    }
+}
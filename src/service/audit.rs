@@ -0,0 +1,117 @@
+use crate::entity::{AuditFilter, AuditLog, AuthedAdmin, Permission};
+use crate::repo::{AuditRepo, UtilsRepo};
+use crate::utils::{parse_datetime, sha256_hex};
+use mysql_async::Conn;
+use serde_json::Value;
+
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+const AUDIT_PAGE_SIZE_MAX: u64 = 100;
+const AUDIT_VERIFY_BATCH: u64 = 500;
+
+fn audit_hash(log: &AuditLog) -> String {
+    let mut payload = vec![
+        log.prev_hash.clone(),
+        log.admin_id.to_string(),
+        log.action.clone(),
+        log.entity_type.clone(),
+        log.entity_id.to_string(),
+        log.before_value.clone().unwrap_or_default(),
+        log.after_value.clone().unwrap_or_default(),
+        log.created_at.to_string(),
+    ];
+    if let Some(api_key_id) = log.api_key_id {
+        payload.push(format!("api_key:{}", api_key_id));
+    }
+    sha256_hex(payload.join("\n").as_bytes())
+}
+
+pub struct AuditService;
+
+impl AuditService {
+    pub async fn append(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        action: &str,
+        entity_type: &str,
+        entity_id: u32,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) -> anyhow::Result<()> {
+        let prev_hash = match AuditRepo::lock_chain_head(conn).await? {
+            Some(hash) => hash,
+            None => anyhow::bail!("audit chain head missing"),
+        };
+        let mut log = AuditLog {
+            id: 0,
+            admin_id: admin.id,
+            api_key_id: admin.api_key_id,
+            action: action.to_string(),
+            entity_type: entity_type.to_string(),
+            entity_id,
+            before_value: before.map(|value| value.to_string()),
+            after_value: after.map(|value| value.to_string()),
+            created_at: UtilsRepo::now(conn).await?,
+            prev_hash,
+            hash: String::new(),
+        };
+        log.hash = audit_hash(&log);
+        match AuditRepo::add_audit_log(conn, &log).await? {
+            Some(_) => AuditRepo::set_chain_head(conn, &log.hash).await,
+            None => anyhow::bail!("failed to write audit log"),
+        }
+    }
+
+    pub async fn get_audit_logs(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        filter: &AuditFilter,
+        page: u64,
+        page_size: u64,
+    ) -> anyhow::Result<(Vec<AuditLog>, u64)> {
+        match admin.has_permission(Permission::AuditRead) {
+            true => {
+                for value in [&filter.from, &filter.to].into_iter().flatten() {
+                    parse_datetime(value)?;
+                }
+                let page_size = page_size.clamp(1, AUDIT_PAGE_SIZE_MAX);
+                let offset = match page.saturating_sub(1).checked_mul(page_size) {
+                    Some(offset) => offset,
+                    None => anyhow::bail!("page {} out of range", page),
+                };
+                let total = AuditRepo::get_audit_log_count(conn, filter).await?;
+                let logs = AuditRepo::get_audit_logs(conn, filter, offset, page_size).await?;
+                Ok((logs, total))
+            }
+            false => anyhow::bail!("permission denied: audit.read required to get audit logs"),
+        }
+    }
+
+    pub async fn verify_chain(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+    ) -> anyhow::Result<(u64, Option<u32>)> {
+        match admin.has_permission(Permission::AuditRead) {
+            true => {
+                let mut prev_hash = GENESIS_HASH.to_string();
+                let mut last_id = 0;
+                let mut checked = 0;
+                loop {
+                    let logs =
+                        AuditRepo::get_audit_chain(conn, last_id, AUDIT_VERIFY_BATCH).await?;
+                    if logs.is_empty() {
+                        return Ok((checked, None));
+                    }
+                    for log in logs {
+                        if log.prev_hash != prev_hash || audit_hash(&log) != log.hash {
+                            return Ok((checked, Some(log.id)));
+                        }
+                        checked += 1;
+                        last_id = log.id;
+                        prev_hash = log.hash;
+                    }
+                }
+            }
+            false => anyhow::bail!("permission denied: audit.read required to verify audit log"),
+        }
+    }
+}
@@ -1,39 +1,361 @@
+use crate::entity::{
+    AdminSession, AuthedAdmin, AuthedCustomer, CustomerSession, LoginFailureKind, Permission,
+};
 use crate::repo::{AuthRepo, UtilsRepo};
 use crate::service::UserService;
-use crate::utils::Token;
+use crate::utils::{generate_secret, generate_token, hash_secret, Audience, Subject, Token};
 use mysql_async::Conn;
 use mysql_common::time::Duration;
+use std::fmt::{Display, Formatter};
+
+const SESSION_IDLE_TIMEOUT: Duration = Duration::minutes(30);
+const REFRESH_TOKEN_VALIDITY: i64 = 60 * 60 * 24 * 30;
+const REFRESH_IDLE_TIMEOUT: Duration = Duration::days(7);
+const LOGIN_FAILURE_WINDOW: i64 = 60 * 60;
+const LOGIN_BACKOFF_THRESHOLD: u32 = 3;
+const LOGIN_LOCKOUT_THRESHOLD: u32 = 10;
+const LOGIN_LOCKOUT_DURATION: i64 = 60 * 15;
+
+#[derive(Debug)]
+pub struct LoginLocked {
+    pub retry_after: i64,
+}
+
+impl Display for LoginLocked {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "too many failed login attempts, retry after {} seconds",
+            self.retry_after
+        )
+    }
+}
+
+impl std::error::Error for LoginLocked {}
+
+fn login_delay(failures: u32) -> Option<i64> {
+    match failures {
+        f if f >= LOGIN_LOCKOUT_THRESHOLD => Some(LOGIN_LOCKOUT_DURATION),
+        f if f >= LOGIN_BACKOFF_THRESHOLD => {
+            Some((1i64 << (f - LOGIN_BACKOFF_THRESHOLD)).min(LOGIN_LOCKOUT_DURATION))
+        }
+        _ => None,
+    }
+}
+
+fn login_subjects<'a>(username: &'a str, ip: &'a str) -> Vec<(LoginFailureKind, &'a str)> {
+    [
+        (LoginFailureKind::Username, username),
+        (LoginFailureKind::Ip, ip),
+    ]
+    .into_iter()
+    .filter(|(_, subject)| !subject.is_empty())
+    .collect()
+}
 
 pub struct AuthService;
 
 impl AuthService {
-    pub async fn verify_user(conn: &mut Conn, token: &Token) -> anyhow::Result<(u32, String)> {
+    pub async fn create_user_session(
+        conn: &mut Conn,
+        customer_id: u32,
+        user_agent: &str,
+    ) -> anyhow::Result<(Token, String)> {
+        let token = generate_token(Subject::customer(customer_id)).await?;
+        let session_id = match AuthRepo::create_customer_session(
+            conn,
+            customer_id,
+            &token.token,
+            user_agent,
+            REFRESH_TOKEN_VALIDITY,
+        )
+        .await?
+        {
+            Some(session_id) => session_id,
+            None => anyhow::bail!("failed to create session"),
+        };
+        let refresh_token = generate_secret();
+        AuthRepo::create_refresh_token(conn, session_id, &hash_secret(&refresh_token)).await?;
+        Ok((token, refresh_token))
+    }
+
+    pub async fn create_admin_session(conn: &mut Conn, admin_id: u32) -> anyhow::Result<Token> {
+        let token = generate_token(Subject::admin(admin_id)).await?;
+        AuthRepo::create_admin_session(conn, admin_id, &token.token).await?;
+        Ok(token)
+    }
+
+    pub async fn refresh_user_session(
+        conn: &mut Conn,
+        refresh_token: &str,
+    ) -> anyhow::Result<(Token, String)> {
+        let record = match AuthRepo::get_refresh_token(conn, &hash_secret(refresh_token)).await? {
+            Some(record) => record,
+            None => anyhow::bail!("invalid refresh token"),
+        };
+        let session = match AuthRepo::get_customer_session_by_id(conn, record.session_id).await? {
+            Some(session) => session,
+            None => anyhow::bail!("session {} not found", record.session_id),
+        };
+
+        if record.used_at.is_some() {
+            AuthRepo::revoke_customer_session(conn, session.customer_id, session.id).await?;
+            anyhow::bail!("refresh token reused, session {} revoked", session.id);
+        }
+        if !session.is_online {
+            anyhow::bail!("session {} is logged out or revoked", session.id);
+        }
+        let now = UtilsRepo::now(conn).await?;
+        if now >= session.expires_at {
+            anyhow::bail!("session expired");
+        }
+        if now - session.last_used > REFRESH_IDLE_TIMEOUT {
+            AuthRepo::revoke_customer_session(conn, session.customer_id, session.id).await?;
+            anyhow::bail!("session expired");
+        }
+        UserService::check_user_status(conn, session.customer_id).await?;
+
+        let token = generate_token(Subject::customer(session.customer_id)).await?;
+        let refresh_token = generate_secret();
+        UtilsRepo::transaction(conn).await?;
+        match AuthService::apply_session_rotation(
+            conn,
+            record.id,
+            session.id,
+            &token.token,
+            &hash_secret(&refresh_token),
+        )
+        .await
+        {
+            Ok(true) => {
+                UtilsRepo::commit(conn).await?;
+                Ok((token, refresh_token))
+            }
+            Ok(false) => {
+                UtilsRepo::rollback(conn).await?;
+                AuthRepo::revoke_customer_session(conn, session.customer_id, session.id).await?;
+                anyhow::bail!("refresh token reused, session {} revoked", session.id)
+            }
+            Err(e) => {
+                UtilsRepo::rollback(conn).await?;
+                anyhow::bail!(e)
+            }
+        }
+    }
+
+    async fn apply_session_rotation(
+        conn: &mut Conn,
+        refresh_token_id: u32,
+        session_id: u32,
+        token: &str,
+        refresh_token_hash: &str,
+    ) -> anyhow::Result<bool> {
+        if !AuthRepo::use_refresh_token(conn, refresh_token_id).await? {
+            return Ok(false);
+        }
+        AuthRepo::rotate_customer_session(
+            conn,
+            session_id,
+            token,
+            refresh_token_hash,
+            REFRESH_TOKEN_VALIDITY,
+        )
+        .await?;
+        Ok(true)
+    }
+
+    pub async fn verify_user(conn: &mut Conn, token: &Token) -> anyhow::Result<AuthedCustomer> {
         let (customer_id, username) = UserService::verify_user(conn, token).await?;
-        match AuthRepo::get_auth_record(conn, customer_id).await? {
-            Some(record) => match record.is_online {
-                true => match record.token == token.token {
-                    true => {
-                        let now = UtilsRepo::now(conn).await?;
-                        let diff = now - record.last_used;
-                        if diff > Duration::minutes(30) {
-                            AuthRepo::update_auth_record(conn, customer_id, &token.token, false)
-                                .await?;
-                            anyhow::bail!("token expired");
-                        }
-                        AuthRepo::update_auth_record(conn, customer_id, &token.token, true).await?;
-                        Ok((customer_id, username))
+        match AuthRepo::get_customer_session(conn, &token.token).await? {
+            Some(session) if session.customer_id == customer_id => match session.is_online {
+                true => {
+                    if UtilsRepo::now(conn).await? >= session.expires_at {
+                        anyhow::bail!("session expired");
                     }
-                    false => anyhow::bail!("token mismatch"),
-                },
-                false => anyhow::bail!("user {} is offline", username),
+                    AuthRepo::touch_customer_session(conn, session.id).await?;
+                    Ok(AuthedCustomer {
+                        id: customer_id,
+                        session_id: session.id,
+                        username,
+                    })
+                }
+                false => anyhow::bail!("session {} is logged out or revoked", session.id),
             },
-            None => anyhow::bail!("auth record not found"),
+            _ => anyhow::bail!("session not found"),
         }
     }
 
-    pub async fn logout_user(conn: &mut Conn, token: &Token) -> anyhow::Result<()> {
-        let (customer_id, _) = AuthService::verify_user(conn, token).await?;
-        AuthRepo::update_auth_record(conn, customer_id, &token.token, false).await?;
+    pub async fn logout_user(conn: &mut Conn, customer: &AuthedCustomer) -> anyhow::Result<()> {
+        AuthRepo::revoke_customer_session(conn, customer.id, customer.session_id).await
+    }
+
+    pub async fn logout_user_everywhere(
+        conn: &mut Conn,
+        customer: &AuthedCustomer,
+    ) -> anyhow::Result<()> {
+        AuthRepo::revoke_customer_sessions(conn, customer.id).await
+    }
+
+    pub async fn get_user_sessions(
+        conn: &mut Conn,
+        customer: &AuthedCustomer,
+    ) -> anyhow::Result<Vec<CustomerSession>> {
+        AuthRepo::get_customer_sessions(conn, customer.id).await
+    }
+
+    pub async fn revoke_user_session(
+        conn: &mut Conn,
+        customer: &AuthedCustomer,
+        session_id: u32,
+    ) -> anyhow::Result<()> {
+        AuthRepo::revoke_customer_session(conn, customer.id, session_id).await
+    }
+
+    pub async fn verify_admin_session(
+        conn: &mut Conn,
+        admin_id: u32,
+        token: &Token,
+    ) -> anyhow::Result<u32> {
+        match AuthRepo::get_admin_session(conn, &token.token).await? {
+            Some(session) if session.admin_id == admin_id => match session.is_online {
+                true => {
+                    let now = UtilsRepo::now(conn).await?;
+                    if now - session.last_used > SESSION_IDLE_TIMEOUT {
+                        AuthRepo::revoke_admin_session(conn, session.id).await?;
+                        anyhow::bail!("session expired");
+                    }
+                    AuthRepo::touch_admin_session(conn, session.id).await?;
+                    Ok(session.id)
+                }
+                false => anyhow::bail!("session {} is logged out or revoked", session.id),
+            },
+            _ => anyhow::bail!("admin session not found"),
+        }
+    }
+
+    pub async fn logout_admin(conn: &mut Conn, admin: &AuthedAdmin) -> anyhow::Result<()> {
+        if admin.is_api_key() {
+            anyhow::bail!("api keys have no session to log out");
+        }
+        AuthRepo::revoke_admin_session(conn, admin.session_id).await
+    }
+
+    pub async fn get_admin_sessions(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        admin_id: u32,
+    ) -> anyhow::Result<Vec<AdminSession>> {
+        match admin.has_permission(Permission::AdminManage) {
+            true => {
+                let now = UtilsRepo::now(conn).await?;
+                let sessions = AuthRepo::get_admin_sessions(conn, admin_id).await?;
+                Ok(sessions
+                    .into_iter()
+                    .filter(|session| now - session.last_used <= SESSION_IDLE_TIMEOUT)
+                    .collect())
+            }
+            false => {
+                anyhow::bail!("permission denied: admin.manage required to view admin sessions")
+            }
+        }
+    }
+
+    pub async fn revoke_admin_session(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        session_id: u32,
+    ) -> anyhow::Result<()> {
+        match admin.has_permission(Permission::AdminManage) {
+            true => AuthRepo::revoke_admin_session(conn, session_id).await,
+            false => {
+                anyhow::bail!("permission denied: admin.manage required to revoke admin sessions")
+            }
+        }
+    }
+
+    pub async fn revoke_admin_sessions(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        admin_id: u32,
+    ) -> anyhow::Result<()> {
+        match admin.has_permission(Permission::AdminManage) {
+            true => AuthRepo::revoke_admin_sessions(conn, admin_id).await,
+            false => {
+                anyhow::bail!("permission denied: admin.manage required to revoke admin sessions")
+            }
+        }
+    }
+
+    pub async fn check_login(
+        conn: &mut Conn,
+        scope: Audience,
+        username: &str,
+        ip: &str,
+    ) -> anyhow::Result<()> {
+        let mut retry_after = 0;
+        for (kind, subject) in login_subjects(username, ip) {
+            if let Some(seconds) = AuthRepo::get_login_lock(conn, scope, kind, subject).await? {
+                retry_after = retry_after.max(seconds.max(1));
+            }
+        }
+        match retry_after {
+            0 => Ok(()),
+            retry_after => Err(LoginLocked { retry_after }.into()),
+        }
+    }
+
+    pub async fn record_login_failure(
+        conn: &mut Conn,
+        scope: Audience,
+        username: &str,
+        ip: &str,
+    ) -> anyhow::Result<Option<LoginLocked>> {
+        let mut retry_after = 0;
+        for (kind, subject) in login_subjects(username, ip) {
+            let failures =
+                AuthRepo::record_login_failure(conn, scope, kind, subject, LOGIN_FAILURE_WINDOW)
+                    .await?;
+            if let Some(seconds) = login_delay(failures) {
+                AuthRepo::lock_login(conn, scope, kind, subject, seconds).await?;
+                retry_after = retry_after.max(seconds);
+            }
+        }
+        match retry_after {
+            0 => Ok(None),
+            retry_after => Ok(Some(LoginLocked { retry_after })),
+        }
+    }
+
+    pub async fn clear_login_failures(
+        conn: &mut Conn,
+        scope: Audience,
+        username: &str,
+    ) -> anyhow::Result<()> {
+        AuthRepo::clear_login_failures(conn, scope, LoginFailureKind::Username, username).await?;
         Ok(())
     }
+
+    pub async fn unlock_login(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        scope: Audience,
+        username: &str,
+    ) -> anyhow::Result<()> {
+        match admin.has_permission(Permission::AdminManage) {
+            true => {
+                match AuthRepo::clear_login_failures(
+                    conn,
+                    scope,
+                    LoginFailureKind::Username,
+                    username,
+                )
+                .await?
+                {
+                    true => Ok(()),
+                    false => anyhow::bail!("{} {} is not locked", scope, username),
+                }
+            }
+            false => anyhow::bail!("permission denied: admin.manage required to unlock accounts"),
+        }
+    }
 }
@@ -1,22 +1,109 @@
-use crate::entity::{AdminRole, Author, Book, Keyword, Publisher, Series};
+use crate::entity::{
+    AuthedAdmin, AuthedCustomer, Author, Book, BookFilter, BookSort, Keyword, Permission,
+    Publisher, Series,
+};
 use crate::repo::BookRepo;
-use crate::service::AdminService;
-use crate::utils::Token;
+use crate::service::{SuggestService, UserService};
+use crate::utils::{normalize_isbn, SearchQuery};
 use mysql_async::Conn;
 use mysql_common::bigdecimal::BigDecimal;
 
+const BOOK_PAGE_SIZE_MAX: u64 = 100;
+
 pub struct BookService;
 
 impl BookService {
     pub async fn get_book_detail(conn: &mut Conn, book_id: u32) -> anyhow::Result<Book> {
         match BookRepo::get_book_detail(conn, book_id).await? {
-            None => anyhow::bail!("book {} not found", book_id),
-            Some(book) => Ok(book),
+            Some(book) if book.is_onstore => Ok(book),
+            _ => anyhow::bail!("book {} not found", book_id),
+        }
+    }
+
+    pub async fn get_book_by_isbn(conn: &mut Conn, isbn: &str) -> anyhow::Result<Option<Book>> {
+        match BookRepo::get_book_id_by_isbn(conn, &normalize_isbn(isbn)?).await? {
+            Some(book_id) => Ok(Some(BookService::get_book_detail(conn, book_id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn check_book_isbn(
+        conn: &mut Conn,
+        isbn: &str,
+        book_id: Option<u32>,
+    ) -> anyhow::Result<String> {
+        let isbn = normalize_isbn(isbn)?;
+        match BookRepo::get_book_id_by_isbn(conn, &isbn).await? {
+            Some(other_id) if Some(other_id) != book_id => {
+                anyhow::bail!("isbn {} already used by book {}", isbn, other_id)
+            }
+            _ => Ok(isbn),
+        }
+    }
+
+    pub async fn get_book_list(
+        conn: &mut Conn,
+        filter: &BookFilter,
+        sort: BookSort,
+        page: u64,
+        page_size: u64,
+    ) -> anyhow::Result<(Vec<Book>, u64)> {
+        BookService::check_book_filter(filter)?;
+        let page_size = page_size.clamp(1, BOOK_PAGE_SIZE_MAX);
+        let offset = match page.saturating_sub(1).checked_mul(page_size) {
+            Some(offset) => offset,
+            None => anyhow::bail!("page {} out of range", page),
+        };
+        let total = BookRepo::get_book_count(conn, filter).await?;
+        let book_ids = BookRepo::get_book_page_ids(conn, filter, sort, offset, page_size).await?;
+        let books = BookRepo::get_books_by_ids(conn, &book_ids).await?;
+        Ok((books, total))
+    }
+
+    pub async fn search_books(
+        conn: &mut Conn,
+        query: &str,
+        filter: &BookFilter,
+        page: u64,
+        page_size: u64,
+    ) -> anyhow::Result<(Vec<(Book, f64)>, u64)> {
+        BookService::check_book_filter(filter)?;
+        let search = SearchQuery::parse(query);
+        if search.is_empty() {
+            anyhow::bail!("search query is empty");
         }
+        let page_size = page_size.clamp(1, BOOK_PAGE_SIZE_MAX);
+        let offset = match page.saturating_sub(1).checked_mul(page_size) {
+            Some(offset) => offset,
+            None => anyhow::bail!("page {} out of range", page),
+        };
+        let total = BookRepo::get_search_count(conn, &search, filter).await?;
+        let scores = BookRepo::search_book_ids(conn, &search, filter, offset, page_size).await?;
+        let book_ids = scores
+            .iter()
+            .map(|(book_id, _)| *book_id)
+            .collect::<Vec<_>>();
+        let books = BookRepo::get_books_by_ids(conn, &book_ids)
+            .await?
+            .into_iter()
+            .map(|book| {
+                let score = scores
+                    .iter()
+                    .find(|(book_id, _)| *book_id == book.id)
+                    .map_or(0.0, |(_, score)| *score);
+                (book, score)
+            })
+            .collect();
+        Ok((books, total))
     }
 
-    pub async fn get_book_list(conn: &mut Conn) -> anyhow::Result<Vec<Book>> {
-        BookRepo::get_book_list(conn).await
+    fn check_book_filter(filter: &BookFilter) -> anyhow::Result<()> {
+        for value in [&filter.min_price, &filter.max_price].into_iter().flatten() {
+            if value.parse::<BigDecimal>().is_err() {
+                anyhow::bail!("invalid price: {}", value);
+            }
+        }
+        Ok(())
     }
 
     pub async fn get_keyword_list(conn: &mut Conn) -> anyhow::Result<Vec<Keyword>> {
@@ -35,107 +122,81 @@ impl BookService {
         BookRepo::get_series_list(conn).await
     }
 
-    pub async fn add_keyword(conn: &mut Conn, token: &Token, keyword: &str) -> anyhow::Result<u32> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => match BookRepo::add_keyword(conn, keyword).await? {
-                Some(keyword_id) => Ok(keyword_id),
+    pub async fn add_keyword(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        keyword: &str,
+    ) -> anyhow::Result<u32> {
+        match admin.has_permission(Permission::CatalogWrite) {
+            true => match BookRepo::add_keyword(conn, keyword).await? {
+                Some(keyword_id) => {
+                    SuggestService::invalidate();
+                    Ok(keyword_id)
+                }
                 None => anyhow::bail!("add keyword failed"),
             },
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can add keyword")
+            false => {
+                anyhow::bail!("permission denied: catalog.write required to add keyword")
             }
         }
     }
 
-    pub async fn add_author(conn: &mut Conn, token: &Token, author: &str) -> anyhow::Result<u32> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => match BookRepo::add_author(conn, author).await? {
-                Some(author_id) => Ok(author_id),
-                None => anyhow::bail!("add author failed"),
-            },
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can add author")
-            }
-        }
-    }
-
-    pub async fn add_publisher(
+    pub async fn create_price_inquiry(
         conn: &mut Conn,
-        token: &Token,
-        publisher: &str,
+        customer: &AuthedCustomer,
+        book_title: &str,
+        isbn: &str,
+        expected_price: &str,
     ) -> anyhow::Result<u32> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => match BookRepo::add_publisher(conn, publisher).await? {
-                Some(publisher_id) => Ok(publisher_id),
-                None => anyhow::bail!("add publisher failed"),
-            },
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can add publisher")
-            }
+        let expected_price: BigDecimal = match expected_price.parse() {
+            Ok(expected_price) => expected_price,
+            Err(_) => anyhow::bail!("invalid expected price {}", expected_price),
+        };
+        let isbn = normalize_isbn(isbn)?;
+        UserService::check_user_status(conn, customer.id).await?;
+        UserService::check_email_verified(conn, customer.id).await?;
+        match BookRepo::create_price_inquiry(conn, customer.id, book_title, &isbn, expected_price)
+            .await?
+        {
+            Some(inquiry_id) => Ok(inquiry_id),
+            None => anyhow::bail!("create price inquiry failed"),
         }
     }
 
-    pub async fn add_book(
+    pub async fn add_author(
         conn: &mut Conn,
-        token: &Token,
-        isbn: &str,
-        title: &str,
-        authors: &Vec<u32>,
-        keywords: &Vec<u32>,
-        series: &Vec<(u32, u32)>,
-        suppliers: &Vec<u32>,
-        publisher: u32,
-        price: BigDecimal,
-        catalog: &str,
-        cover: &str,
-        is_onstore: bool,
+        admin: &AuthedAdmin,
+        author: &str,
     ) -> anyhow::Result<u32> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => {
-                match BookRepo::add_book(
-                    conn, isbn, title, authors, keywords, series, suppliers, publisher, price,
-                    catalog, cover, is_onstore,
-                )
-                .await?
-                {
-                    Some(book_id) => Ok(book_id),
-                    None => anyhow::bail!("add book failed"),
+        match admin.has_permission(Permission::CatalogWrite) {
+            true => match BookRepo::add_author(conn, author).await? {
+                Some(author_id) => {
+                    SuggestService::invalidate();
+                    Ok(author_id)
                 }
+                None => anyhow::bail!("add author failed"),
+            },
+            false => {
+                anyhow::bail!("permission denied: catalog.write required to add author")
             }
-            (_, _, false) => anyhow::bail!("permission denied: only staff or admin can add book"),
         }
     }
 
-    pub async fn update_book(
+    pub async fn add_publisher(
         conn: &mut Conn,
-        token: &Token,
-        book_id: u32,
-        isbn: &str,
-        title: &str,
-        authors: &Vec<u32>,
-        keywords: &Vec<u32>,
-        series: &Vec<(u32, u32)>,
-        suppliers: &Vec<u32>,
-        publisher: u32,
-        price: BigDecimal,
-        catalog: &str,
-        cover: &str,
-        is_onstore: bool,
-    ) -> anyhow::Result<()> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => {
-                match BookRepo::update_book(
-                    conn, book_id, isbn, title, authors, keywords, series, suppliers, publisher,
-                    price, catalog, cover, is_onstore,
-                )
-                .await?
-                {
-                    Some(_) => Ok(()),
-                    None => anyhow::bail!("update book failed"),
+        admin: &AuthedAdmin,
+        publisher: &str,
+    ) -> anyhow::Result<u32> {
+        match admin.has_permission(Permission::CatalogWrite) {
+            true => match BookRepo::add_publisher(conn, publisher).await? {
+                Some(publisher_id) => {
+                    SuggestService::invalidate();
+                    Ok(publisher_id)
                 }
-            }
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can update book")
+                None => anyhow::bail!("add publisher failed"),
+            },
+            false => {
+                anyhow::bail!("permission denied: catalog.write required to add publisher")
             }
         }
     }
@@ -0,0 +1,459 @@
+use crate::entity::{AdminLoginChallenge, AdminMfa, AdminStatus, AuthedAdmin, Permission};
+use crate::repo::{AdminRepo, AuthRepo, MfaRepo, SettingRepo, UtilsRepo};
+use crate::service::{AuditService, AuthService};
+use crate::utils::{
+    base32_encode, generate_recovery_codes, generate_secret, generate_totp_secret, hash_secret,
+    normalize_recovery_code, open_totp_secret, seal_totp_secret, totp_uri, verify_totp, Audience,
+    Token,
+};
+use mysql_async::Conn;
+use serde_json::json;
+
+const MFA_REQUIRED_SETTING: &str = "admin_mfa_required";
+const LOGIN_CHALLENGE_VALIDITY: i64 = 60 * 5;
+const LOGIN_CHALLENGE_ATTEMPTS: u32 = 5;
+
+pub enum AdminLogin {
+    Token(Token),
+    MfaRequired(String),
+    MfaEnrollmentRequired(String),
+}
+
+pub struct MfaSetup {
+    pub secret: String,
+    pub uri: String,
+}
+
+pub struct MfaService;
+
+impl MfaService {
+    pub async fn is_mfa_required(conn: &mut Conn) -> anyhow::Result<bool> {
+        match SettingRepo::get_setting(conn, MFA_REQUIRED_SETTING).await? {
+            Some(value) => Ok(value == "true"),
+            None => Ok(false),
+        }
+    }
+
+    pub async fn set_mfa_required(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        required: bool,
+    ) -> anyhow::Result<()> {
+        match admin.has_permission(Permission::AdminManage) {
+            true => {
+                let before = MfaService::is_mfa_required(conn).await?;
+                UtilsRepo::transaction(conn).await?;
+                match MfaService::apply_mfa_policy(conn, admin, before, required).await {
+                    Ok(_) => {
+                        UtilsRepo::commit(conn).await?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!(e)
+                    }
+                }
+            }
+            false => {
+                anyhow::bail!("permission denied: admin.manage required to set two-factor policy")
+            }
+        }
+    }
+
+    async fn apply_mfa_policy(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        before: bool,
+        required: bool,
+    ) -> anyhow::Result<()> {
+        SettingRepo::set_setting(conn, MFA_REQUIRED_SETTING, &required.to_string()).await?;
+        if required {
+            MfaRepo::revoke_admin_sessions_without_mfa(conn).await?;
+        }
+        AuditService::append(
+            conn,
+            admin,
+            "mfa.policy",
+            "setting",
+            0,
+            Some(json!({ "required": before })),
+            Some(json!({ "required": required })),
+        )
+        .await
+    }
+
+    pub async fn get_mfa_status(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+    ) -> anyhow::Result<(bool, bool, u64)> {
+        let enabled = matches!(
+            MfaRepo::get_admin_mfa(conn, admin.id).await?,
+            Some(mfa) if mfa.is_enabled
+        );
+        let required = MfaService::is_mfa_required(conn).await?;
+        let recovery_codes = MfaRepo::get_recovery_code_count(conn, admin.id).await?;
+        Ok((enabled, required, recovery_codes))
+    }
+
+    pub async fn setup_mfa(conn: &mut Conn, admin: &AuthedAdmin) -> anyhow::Result<MfaSetup> {
+        if admin.is_api_key() {
+            anyhow::bail!("api keys cannot manage two-factor authentication");
+        }
+        MfaService::provision(conn, admin.id, &admin.username).await
+    }
+
+    pub async fn enable_mfa(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        code: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        if admin.is_api_key() {
+            anyhow::bail!("api keys cannot manage two-factor authentication");
+        }
+        let mfa = match MfaRepo::get_admin_mfa(conn, admin.id).await? {
+            Some(mfa) if mfa.is_enabled => {
+                anyhow::bail!("two-factor authentication is already enabled")
+            }
+            Some(mfa) => mfa,
+            None => anyhow::bail!("two-factor authentication has not been set up"),
+        };
+        MfaService::activate(conn, &mfa, code, Some(admin)).await
+    }
+
+    pub async fn disable_mfa(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        code: &str,
+    ) -> anyhow::Result<()> {
+        if admin.is_api_key() {
+            anyhow::bail!("api keys cannot manage two-factor authentication");
+        }
+        if MfaService::is_mfa_required(conn).await? {
+            anyhow::bail!("two-factor authentication is mandatory");
+        }
+        match MfaRepo::get_admin_mfa(conn, admin.id).await? {
+            Some(mfa) if mfa.is_enabled => match MfaService::verify_code(conn, &mfa, code).await? {
+                true => {
+                    UtilsRepo::transaction(conn).await?;
+                    match MfaService::apply_mfa_disable(conn, admin).await {
+                        Ok(_) => {
+                            UtilsRepo::commit(conn).await?;
+                            Ok(())
+                        }
+                        Err(e) => {
+                            UtilsRepo::rollback(conn).await?;
+                            anyhow::bail!(e)
+                        }
+                    }
+                }
+                false => anyhow::bail!("invalid two-factor code"),
+            },
+            _ => anyhow::bail!("two-factor authentication is not enabled"),
+        }
+    }
+
+    pub async fn regenerate_recovery_codes(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        code: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        if admin.is_api_key() {
+            anyhow::bail!("api keys cannot manage two-factor authentication");
+        }
+        match MfaRepo::get_admin_mfa(conn, admin.id).await? {
+            Some(mfa) if mfa.is_enabled => match MfaService::verify_totp(conn, &mfa, code).await? {
+                Some(_) => {
+                    UtilsRepo::transaction(conn).await?;
+                    match MfaService::apply_recovery_codes(conn, admin).await {
+                        Ok(recovery_codes) => {
+                            UtilsRepo::commit(conn).await?;
+                            Ok(recovery_codes)
+                        }
+                        Err(e) => {
+                            UtilsRepo::rollback(conn).await?;
+                            anyhow::bail!(e)
+                        }
+                    }
+                }
+                None => anyhow::bail!("invalid two-factor code"),
+            },
+            _ => anyhow::bail!("two-factor authentication is not enabled"),
+        }
+    }
+
+    pub async fn reset_admin_mfa(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        admin_id: u32,
+    ) -> anyhow::Result<()> {
+        match admin.has_permission(Permission::AdminManage) {
+            true => {
+                if AdminRepo::get_admin_detail_by_id(conn, admin_id)
+                    .await?
+                    .is_none()
+                {
+                    anyhow::bail!("admin {} not found", admin_id);
+                }
+                UtilsRepo::transaction(conn).await?;
+                match MfaService::apply_mfa_reset(conn, admin, admin_id).await {
+                    Ok(_) => {
+                        UtilsRepo::commit(conn).await?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!(e)
+                    }
+                }
+            }
+            false => {
+                anyhow::bail!(
+                    "permission denied: admin.manage required to reset two-factor authentication"
+                )
+            }
+        }
+    }
+
+    async fn apply_mfa_disable(conn: &mut Conn, admin: &AuthedAdmin) -> anyhow::Result<()> {
+        MfaRepo::delete_admin_mfa(conn, admin.id).await?;
+        AuditService::append(conn, admin, "mfa.disable", "admin", admin.id, None, None).await
+    }
+
+    async fn apply_mfa_reset(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        admin_id: u32,
+    ) -> anyhow::Result<()> {
+        MfaRepo::delete_admin_mfa(conn, admin_id).await?;
+        AuthRepo::revoke_admin_sessions(conn, admin_id).await?;
+        AuditService::append(conn, admin, "mfa.reset", "admin", admin_id, None, None).await
+    }
+
+    async fn apply_recovery_codes(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+    ) -> anyhow::Result<Vec<String>> {
+        let recovery_codes = MfaService::issue_recovery_codes(conn, admin.id).await?;
+        AuditService::append(
+            conn,
+            admin,
+            "mfa.recovery_codes",
+            "admin",
+            admin.id,
+            None,
+            None,
+        )
+        .await?;
+        Ok(recovery_codes)
+    }
+
+    pub async fn start_login(
+        conn: &mut Conn,
+        admin_id: u32,
+        username: &str,
+    ) -> anyhow::Result<AdminLogin> {
+        match MfaRepo::get_admin_mfa(conn, admin_id).await? {
+            Some(mfa) if mfa.is_enabled => Ok(AdminLogin::MfaRequired(
+                MfaService::create_challenge(conn, admin_id).await?,
+            )),
+            _ => match MfaService::is_mfa_required(conn).await? {
+                true => Ok(AdminLogin::MfaEnrollmentRequired(
+                    MfaService::create_challenge(conn, admin_id).await?,
+                )),
+                false => {
+                    AuthService::clear_login_failures(conn, Audience::Admin, username).await?;
+                    Ok(AdminLogin::Token(
+                        AuthService::create_admin_session(conn, admin_id).await?,
+                    ))
+                }
+            },
+        }
+    }
+
+    pub async fn setup_login_mfa(conn: &mut Conn, challenge: &str) -> anyhow::Result<MfaSetup> {
+        let challenge = MfaService::get_challenge(conn, challenge).await?;
+        match AdminRepo::get_admin_detail_by_id(conn, challenge.admin_id).await? {
+            Some(admin) => MfaService::provision(conn, admin.id, &admin.username).await,
+            None => anyhow::bail!("admin {} not found", challenge.admin_id),
+        }
+    }
+
+    pub async fn complete_login(
+        conn: &mut Conn,
+        challenge: &str,
+        code: &str,
+        ip: &str,
+    ) -> anyhow::Result<(Token, Option<Vec<String>>)> {
+        let challenge = MfaService::get_challenge(conn, challenge).await?;
+        let username = match AdminRepo::get_admin_detail_by_id(conn, challenge.admin_id).await? {
+            Some(admin) if admin.status == AdminStatus::Cancelled => {
+                anyhow::bail!("account is cancelled")
+            }
+            Some(admin) => admin.username,
+            None => anyhow::bail!("admin {} not found", challenge.admin_id),
+        };
+        AuthService::check_login(conn, Audience::Admin, &username, ip).await?;
+
+        let recovery_codes = match MfaRepo::get_admin_mfa(conn, challenge.admin_id).await? {
+            Some(mfa) if mfa.is_enabled => match MfaService::verify_code(conn, &mfa, code).await? {
+                true => {
+                    MfaService::use_challenge(conn, &challenge).await?;
+                    None
+                }
+                false => {
+                    MfaService::fail_login(conn, &challenge, &username, ip).await?;
+                    anyhow::bail!("invalid two-factor code")
+                }
+            },
+            Some(mfa) => match MfaService::activate(conn, &mfa, code, None).await {
+                Ok(recovery_codes) => {
+                    MfaService::use_challenge(conn, &challenge).await?;
+                    Some(recovery_codes)
+                }
+                Err(e) => {
+                    MfaService::fail_login(conn, &challenge, &username, ip).await?;
+                    anyhow::bail!(e)
+                }
+            },
+            None => anyhow::bail!("two-factor authentication must be set up before login"),
+        };
+
+        AuthService::clear_login_failures(conn, Audience::Admin, &username).await?;
+        let token = AuthService::create_admin_session(conn, challenge.admin_id).await?;
+        Ok((token, recovery_codes))
+    }
+
+    async fn fail_login(
+        conn: &mut Conn,
+        challenge: &AdminLoginChallenge,
+        username: &str,
+        ip: &str,
+    ) -> anyhow::Result<()> {
+        MfaRepo::fail_login_challenge(conn, challenge.id).await?;
+        match AuthService::record_login_failure(conn, Audience::Admin, username, ip).await? {
+            Some(locked) => Err(locked.into()),
+            None => Ok(()),
+        }
+    }
+
+    async fn provision(conn: &mut Conn, admin_id: u32, username: &str) -> anyhow::Result<MfaSetup> {
+        if let Some(mfa) = MfaRepo::get_admin_mfa(conn, admin_id).await? {
+            if mfa.is_enabled {
+                anyhow::bail!("two-factor authentication is already enabled");
+            }
+        }
+        let secret = generate_totp_secret();
+        MfaRepo::save_admin_mfa_secret(conn, admin_id, &seal_totp_secret(&secret)?).await?;
+        Ok(MfaSetup {
+            secret: base32_encode(&secret),
+            uri: totp_uri(username, &secret),
+        })
+    }
+
+    async fn activate(
+        conn: &mut Conn,
+        mfa: &AdminMfa,
+        code: &str,
+        admin: Option<&AuthedAdmin>,
+    ) -> anyhow::Result<Vec<String>> {
+        let secret = open_totp_secret(&mfa.secret)?;
+        let step = match verify_totp(&secret, code, None) {
+            Some(step) => step,
+            None => anyhow::bail!("invalid two-factor code"),
+        };
+
+        UtilsRepo::transaction(conn).await?;
+        match MfaService::apply_mfa_activation(conn, mfa, step, admin).await {
+            Ok(recovery_codes) => {
+                UtilsRepo::commit(conn).await?;
+                Ok(recovery_codes)
+            }
+            Err(e) => {
+                UtilsRepo::rollback(conn).await?;
+                anyhow::bail!(e)
+            }
+        }
+    }
+
+    async fn apply_mfa_activation(
+        conn: &mut Conn,
+        mfa: &AdminMfa,
+        step: i64,
+        admin: Option<&AuthedAdmin>,
+    ) -> anyhow::Result<Vec<String>> {
+        if !MfaRepo::enable_admin_mfa(conn, mfa.admin_id, step).await? {
+            anyhow::bail!("two-factor authentication is already enabled");
+        }
+        let recovery_codes = MfaService::issue_recovery_codes(conn, mfa.admin_id).await?;
+        if let Some(admin) = admin {
+            AuditService::append(conn, admin, "mfa.enable", "admin", admin.id, None, None).await?;
+        }
+        Ok(recovery_codes)
+    }
+
+    async fn verify_totp(
+        conn: &mut Conn,
+        mfa: &AdminMfa,
+        code: &str,
+    ) -> anyhow::Result<Option<i64>> {
+        let secret = open_totp_secret(&mfa.secret)?;
+        match verify_totp(&secret, code, mfa.last_step) {
+            Some(step) => match MfaRepo::use_totp_step(conn, mfa.admin_id, step).await? {
+                true => Ok(Some(step)),
+                false => Ok(None),
+            },
+            None => Ok(None),
+        }
+    }
+
+    async fn verify_code(conn: &mut Conn, mfa: &AdminMfa, code: &str) -> anyhow::Result<bool> {
+        match MfaService::verify_totp(conn, mfa, code).await? {
+            Some(_) => Ok(true),
+            None => {
+                let code_hash = hash_secret(&normalize_recovery_code(code));
+                MfaRepo::use_recovery_code(conn, mfa.admin_id, &code_hash).await
+            }
+        }
+    }
+
+    async fn issue_recovery_codes(conn: &mut Conn, admin_id: u32) -> anyhow::Result<Vec<String>> {
+        let recovery_codes = generate_recovery_codes();
+        let code_hashes = recovery_codes
+            .iter()
+            .map(|code| hash_secret(code))
+            .collect::<Vec<_>>();
+        MfaRepo::set_recovery_codes(conn, admin_id, &code_hashes).await?;
+        Ok(recovery_codes)
+    }
+
+    async fn create_challenge(conn: &mut Conn, admin_id: u32) -> anyhow::Result<String> {
+        let challenge = generate_secret();
+        MfaRepo::create_login_challenge(
+            conn,
+            admin_id,
+            &hash_secret(&challenge),
+            LOGIN_CHALLENGE_VALIDITY,
+        )
+        .await?;
+        Ok(challenge)
+    }
+
+    async fn get_challenge(
+        conn: &mut Conn,
+        challenge: &str,
+    ) -> anyhow::Result<AdminLoginChallenge> {
+        match MfaRepo::get_login_challenge(conn, &hash_secret(challenge), LOGIN_CHALLENGE_ATTEMPTS)
+            .await?
+        {
+            Some(challenge) => Ok(challenge),
+            None => anyhow::bail!("invalid or expired login challenge"),
+        }
+    }
+
+    async fn use_challenge(conn: &mut Conn, challenge: &AdminLoginChallenge) -> anyhow::Result<()> {
+        match MfaRepo::use_login_challenge(conn, challenge.id, LOGIN_CHALLENGE_ATTEMPTS).await? {
+            true => Ok(()),
+            false => anyhow::bail!("invalid or expired login challenge"),
+        }
+    }
+}
@@ -1,20 +1,30 @@
 mod admin;
+mod api_key;
+mod audit;
 mod auth;
 mod book;
 mod index;
+mod mfa;
 mod order;
 mod purchase_order;
+mod role;
 mod shortage;
 mod stock;
+mod suggest;
 mod supplier;
 mod user;
 
 pub use admin::AdminService;
-pub use auth::AuthService;
+pub use api_key::ApiKeyService;
+pub use audit::AuditService;
+pub use auth::{AuthService, LoginLocked};
 pub use book::BookService;
-pub use order::OrderService;
+pub use mfa::{AdminLogin, MfaService, MfaSetup};
+pub use order::{OrderRejected, OrderService};
 pub use purchase_order::PurchaseOrderService;
+pub use role::RoleService;
 pub use shortage::ShortageService;
 pub use stock::StockService;
+pub use suggest::SuggestService;
 pub use supplier::SupplierService;
-pub use user::UserService;
+pub use user::{BalanceAdjustment, UserExport, UserService};
@@ -1,36 +1,112 @@
-use crate::entity::{AdminRole, Book, Order, OrderPaymentStatus};
-use crate::repo::{OrderRepo, StockRepo, UtilsRepo};
-use crate::service::{AdminService, AuthService};
-use crate::utils::Token;
+use crate::entity::{AuthedAdmin, AuthedCustomer, Book, Order, OrderPaymentStatus, Permission};
+use crate::repo::{BookRepo, OrderRepo, StockRepo, UtilsRepo};
+use crate::service::{AuditService, UserService};
 use mysql_async::Conn;
+use serde_json::json;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub struct OrderRejected {
+    pub items: Vec<(u32, String)>,
+}
+
+impl Display for OrderRejected {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "order rejected, {} item(s) cannot be ordered",
+            self.items.len()
+        )
+    }
+}
+
+impl std::error::Error for OrderRejected {}
 
 pub struct OrderService;
 
 impl OrderService {
     pub async fn create_order(
         conn: &mut Conn,
-        token: &Token,
+        customer: &AuthedCustomer,
         books: &Vec<(u32, u32)>,
     ) -> anyhow::Result<u32> {
-        let (_, username) = AuthService::verify_user(conn, token).await?;
-        match OrderRepo::create_order(conn, &username, books).await? {
+        UserService::check_user_status(conn, customer.id).await?;
+        UserService::check_email_verified(conn, customer.id).await?;
+        if books.is_empty() {
+            anyhow::bail!("order has no items");
+        }
+        let mut book_ids = HashSet::new();
+        let mut rejected = Vec::new();
+        for (book_id, _) in books.iter() {
+            if !book_ids.insert(*book_id) && !rejected.iter().any(|(id, _)| id == book_id) {
+                rejected.push((
+                    *book_id,
+                    format!("book {} is listed more than once", book_id),
+                ));
+            }
+        }
+        if !rejected.is_empty() {
+            return Err(OrderRejected { items: rejected }.into());
+        }
+
+        UtilsRepo::transaction(conn).await?;
+        match OrderService::insert_order(conn, customer, books).await {
+            Ok(order) => {
+                UtilsRepo::commit(conn).await?;
+                Ok(order)
+            }
+            Err(e) => {
+                UtilsRepo::rollback(conn).await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn insert_order(
+        conn: &mut Conn,
+        customer: &AuthedCustomer,
+        books: &Vec<(u32, u32)>,
+    ) -> anyhow::Result<u32> {
+        let mut items = books.iter().collect::<Vec<_>>();
+        items.sort_by_key(|(book_id, _)| *book_id);
+
+        let mut rejected = Vec::new();
+        for (book_id, quantity) in items {
+            match BookRepo::lock_book_onstore(conn, *book_id).await? {
+                None => rejected.push((*book_id, format!("book {} not found", book_id))),
+                Some(false) => {
+                    rejected.push((*book_id, format!("book {} is not on store", book_id)))
+                }
+                Some(true) if *quantity == 0 => {
+                    rejected.push((*book_id, "quantity must be at least 1".to_string()))
+                }
+                Some(true) => {}
+            }
+        }
+        if !rejected.is_empty() {
+            return Err(OrderRejected { items: rejected }.into());
+        }
+
+        match OrderRepo::create_order(conn, &customer.username, books).await? {
             Some(order) => Ok(order),
             None => anyhow::bail!("failed to create order"),
         }
     }
 
-    pub async fn get_order_list(conn: &mut Conn, token: &Token) -> anyhow::Result<Vec<Order>> {
-        let (_, username) = AuthService::verify_user(conn, token).await?;
-        OrderRepo::get_order_list(conn, &username).await
+    pub async fn get_order_list(
+        conn: &mut Conn,
+        customer: &AuthedCustomer,
+    ) -> anyhow::Result<Vec<Order>> {
+        OrderRepo::get_order_list(conn, &customer.username).await
     }
 
     pub async fn get_order_detail(
         conn: &mut Conn,
-        token: &Token,
+        customer: &AuthedCustomer,
         order_id: u32,
     ) -> anyhow::Result<(Order, Vec<Book>)> {
-        let (_, username) = AuthService::verify_user(conn, token).await?;
-        match OrderRepo::get_order_detail(conn, &username, order_id).await? {
+        match OrderRepo::get_order_detail(conn, &customer.username, order_id).await? {
             (Some(order), books) => Ok((order, books)),
             (None, _) => anyhow::bail!("order {} not found", order_id),
         }
@@ -38,12 +114,12 @@ impl OrderService {
 
     pub async fn ship_order(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         order_id: u32,
         stock_location: &Vec<(u32, u32, u32)>,
     ) -> anyhow::Result<()> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => {
+        match admin.has_permission(Permission::OrderShip) {
+            true => {
                 for (book_id, location_id, quantity) in stock_location.iter() {
                     match StockRepo::get_book_quantity(conn, *location_id, *book_id).await? {
                         Some(stock) => {
@@ -60,22 +136,82 @@ impl OrderService {
                         }
                     }
                 }
-                OrderRepo::ship_order(conn, order_id, stock_location).await
+                let before = match OrderRepo::get_order_status(conn, order_id).await? {
+                    Some((_, shipping_status)) => shipping_status.to_string(),
+                    None => anyhow::bail!("order {} not found", order_id),
+                };
+                UtilsRepo::transaction(conn).await?;
+                match OrderService::apply_order_shipment(
+                    conn,
+                    admin,
+                    order_id,
+                    stock_location,
+                    &before,
+                )
+                .await
+                {
+                    Ok(_) => {
+                        UtilsRepo::commit(conn).await?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!(e)
+                    }
+                }
             }
-            (_, _, false) => anyhow::bail!("permission denied: only staff or admin can ship order"),
+            false => anyhow::bail!("permission denied: order.ship required to ship order"),
         }
     }
 
+    async fn apply_order_shipment(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        order_id: u32,
+        stock_location: &Vec<(u32, u32, u32)>,
+        before: &str,
+    ) -> anyhow::Result<()> {
+        OrderRepo::ship_order(conn, order_id, stock_location).await?;
+        AuditService::append(
+            conn,
+            admin,
+            "order.ship",
+            "order",
+            order_id,
+            Some(json!({ "shipping_status": before })),
+            Some(json!({
+                "shipping_status": "shipped",
+                "stock_locations": stock_location,
+            })),
+        )
+        .await
+    }
+
     pub async fn update_order_payment_status(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         order_id: u32,
         status: OrderPaymentStatus,
     ) -> anyhow::Result<()> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => {
+        match admin.has_permission(Permission::OrderShip) {
+            true => {
+                let before = match OrderRepo::get_order_status(conn, order_id).await? {
+                    Some((payment_status, _)) => payment_status.to_string(),
+                    None => anyhow::bail!("order {} not found", order_id),
+                };
+                if status == OrderPaymentStatus::Paid {
+                    if let Some(customer_id) = OrderRepo::get_order_customer(conn, order_id).await?
+                    {
+                        UserService::check_user_status(conn, customer_id).await?;
+                    }
+                }
+                let after = status.to_string();
                 UtilsRepo::transaction(conn).await?;
-                match OrderRepo::update_order_payment_status(conn, order_id, status).await {
+                match OrderService::apply_order_payment_status(
+                    conn, admin, order_id, status, &before, &after,
+                )
+                .await
+                {
                     Ok(_) => {
                         UtilsRepo::commit(conn).await?;
                         Ok(())
@@ -86,9 +222,30 @@ impl OrderService {
                     }
                 }
             }
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can update order status")
+            false => {
+                anyhow::bail!("permission denied: order.ship required to update order status")
             }
         }
     }
+
+    async fn apply_order_payment_status(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        order_id: u32,
+        status: OrderPaymentStatus,
+        before: &str,
+        after: &str,
+    ) -> anyhow::Result<()> {
+        OrderRepo::update_order_payment_status(conn, order_id, status).await?;
+        AuditService::append(
+            conn,
+            admin,
+            "order.payment",
+            "order",
+            order_id,
+            Some(json!({ "payment_status": before })),
+            Some(json!({ "payment_status": after })),
+        )
+        .await
+    }
 }
@@ -1,8 +1,8 @@
-use crate::entity::{AdminRole, PurchaseOrder, PurchaseOrderStatus, SupplierCatalog};
+use crate::entity::{AuthedAdmin, Permission, PurchaseOrder, PurchaseOrderStatus, SupplierCatalog};
 use crate::repo::{PurchaseOrderRepo, StockRepo, UtilsRepo};
-use crate::service::AdminService;
-use crate::utils::Token;
+use crate::service::AuditService;
 use mysql_async::Conn;
+use serde_json::json;
 use std::collections::HashMap;
 
 pub struct PurchaseOrderService;
@@ -10,12 +10,12 @@ pub struct PurchaseOrderService;
 impl PurchaseOrderService {
     pub async fn confirm_purchase_order(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         purchase_order_id: u32,
         stocks: &Vec<(u32, Vec<(u32, u32)>)>,
     ) -> anyhow::Result<()> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => {
+        match admin.has_permission(Permission::ProcurementApprove) {
+            true => {
                 let (purchase_order, supplier_catalogs) =
                     match PurchaseOrderRepo::get_purchase_order_detail(conn, purchase_order_id)
                         .await?
@@ -66,8 +66,10 @@ impl PurchaseOrderService {
                     };
                 }
             }
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only admin can confirm purchase order")
+            false => {
+                anyhow::bail!(
+                    "permission denied: procurement.approve required to confirm purchase order"
+                )
             }
         }
 
@@ -76,11 +78,11 @@ impl PurchaseOrderService {
 
     pub async fn get_purchase_order_detail(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         purchase_order_id: u32,
     ) -> anyhow::Result<(PurchaseOrder, Vec<SupplierCatalog>)> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => {
+        match admin.has_permission(Permission::ProcurementRead) {
+            true => {
                 match PurchaseOrderRepo::get_purchase_order_detail(conn, purchase_order_id).await? {
                     Some((purchase_order, supplier_catalogs)) => {
                         Ok((purchase_order, supplier_catalogs))
@@ -88,57 +90,86 @@ impl PurchaseOrderService {
                     None => anyhow::bail!("purchase order {} not found", purchase_order_id),
                 }
             }
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only admin can get purchase order detail")
+            false => {
+                anyhow::bail!(
+                    "permission denied: procurement.read required to get purchase order detail"
+                )
             }
         }
     }
 
     pub async fn get_purchase_order_list(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
     ) -> anyhow::Result<Vec<PurchaseOrder>> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => PurchaseOrderRepo::get_purchase_order_list(conn).await,
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only admin can get purchase order list")
+        match admin.has_permission(Permission::ProcurementRead) {
+            true => PurchaseOrderRepo::get_purchase_order_list(conn).await,
+            false => {
+                anyhow::bail!(
+                    "permission denied: procurement.read required to get purchase order list"
+                )
             }
         }
     }
 
     pub async fn create_purchase_order(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         shortage_id: u32,
     ) -> anyhow::Result<u32> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => {
+        match admin.has_permission(Permission::ProcurementCreate) {
+            true => {
                 UtilsRepo::transaction(conn).await?;
-                match PurchaseOrderRepo::create_purchase_order(conn, shortage_id).await? {
-                    Some(purchase_order_id) => {
+                match PurchaseOrderService::insert_purchase_order(conn, admin, shortage_id).await {
+                    Ok(purchase_order_id) => {
                         UtilsRepo::commit(conn).await?;
                         Ok(purchase_order_id)
                     }
-                    None => {
+                    Err(e) => {
                         UtilsRepo::rollback(conn).await?;
-                        anyhow::bail!("create purchase order failed")
+                        anyhow::bail!(e)
                     }
                 }
             }
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only admin can create purchase order")
+            false => {
+                anyhow::bail!(
+                    "permission denied: procurement.create required to create purchase order"
+                )
             }
         }
     }
 
+    async fn insert_purchase_order(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        shortage_id: u32,
+    ) -> anyhow::Result<u32> {
+        let purchase_order_id =
+            match PurchaseOrderRepo::create_purchase_order(conn, shortage_id).await? {
+                Some(purchase_order_id) => purchase_order_id,
+                None => anyhow::bail!("create purchase order failed"),
+            };
+        AuditService::append(
+            conn,
+            admin,
+            "purchase_order.create",
+            "purchase_order",
+            purchase_order_id,
+            None,
+            Some(json!({ "shortage_id": shortage_id })),
+        )
+        .await?;
+        Ok(purchase_order_id)
+    }
+
     pub async fn complete_purchase_order(
         conn: &mut Conn,
-        token: &Token,
-        stock: &Vec<(u32, u32, u32)>,
+        admin: &AuthedAdmin,
+        stock: &[(u32, u32, u32)],
         purchase_order_id: u32,
     ) -> anyhow::Result<()> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => {
+        match admin.has_permission(Permission::ProcurementApprove) {
+            true => {
                 let mut book_total_quantity = HashMap::new();
                 for (book_id, _, quantity) in stock.iter() {
                     if !PurchaseOrderRepo::check_book_in_purchase_order(
@@ -183,8 +214,10 @@ impl PurchaseOrderService {
                     Err(_) => anyhow::bail!("complete purchase order failed"),
                 }
             }
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only admin can complete purchase order")
+            false => {
+                anyhow::bail!(
+                    "permission denied: procurement.approve required to complete purchase order"
+                )
             }
         }
     }
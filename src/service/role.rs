@@ -0,0 +1,115 @@
+use crate::entity::{AdminRole, AuthedAdmin, Permission, Role};
+use crate::repo::{AdminRepo, RoleRepo, UtilsRepo};
+use crate::service::AuditService;
+use mysql_async::Conn;
+use serde_json::json;
+
+pub struct RoleService;
+
+impl RoleService {
+    pub async fn get_role_list(conn: &mut Conn, admin: &AuthedAdmin) -> anyhow::Result<Vec<Role>> {
+        match admin.has_permission(Permission::AdminManage) {
+            true => RoleRepo::get_role_list(conn).await,
+            false => anyhow::bail!("permission denied: admin.manage required to get role list"),
+        }
+    }
+
+    pub async fn save_role(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        name: &str,
+        description: &str,
+        permissions: &[Permission],
+    ) -> anyhow::Result<u32> {
+        match admin.has_permission(Permission::AdminManage) {
+            true => {
+                UtilsRepo::transaction(conn).await?;
+                match RoleRepo::save_role(conn, name, description, permissions).await {
+                    Ok(Some(role_id)) => {
+                        UtilsRepo::commit(conn).await?;
+                        Ok(role_id)
+                    }
+                    Ok(None) => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!("save role failed")
+                    }
+                    Err(e) => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!(e)
+                    }
+                }
+            }
+            false => anyhow::bail!("permission denied: admin.manage required to save role"),
+        }
+    }
+
+    pub async fn get_admin_roles(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        admin_id: u32,
+    ) -> anyhow::Result<Vec<String>> {
+        match admin.has_permission(Permission::AdminManage) {
+            true => RoleRepo::get_admin_roles(conn, admin_id).await,
+            false => anyhow::bail!("permission denied: admin.manage required to get admin roles"),
+        }
+    }
+
+    pub async fn set_admin_roles(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        admin_id: u32,
+        roles: &[String],
+    ) -> anyhow::Result<()> {
+        match admin.has_permission(Permission::AdminManage) {
+            true => {
+                if admin_id == admin.id {
+                    anyhow::bail!("cannot change the roles of your own account");
+                }
+                if AdminRepo::get_admin_detail_by_id(conn, admin_id)
+                    .await?
+                    .is_none()
+                {
+                    anyhow::bail!("admin {} not found", admin_id);
+                }
+
+                UtilsRepo::transaction(conn).await?;
+                match RoleService::apply_admin_roles(conn, admin, admin_id, roles).await {
+                    Ok(_) => {
+                        UtilsRepo::commit(conn).await?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!(e)
+                    }
+                }
+            }
+            false => anyhow::bail!("permission denied: admin.manage required to set admin roles"),
+        }
+    }
+
+    async fn apply_admin_roles(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        admin_id: u32,
+        roles: &[String],
+    ) -> anyhow::Result<()> {
+        let before = RoleRepo::get_admin_roles(conn, admin_id).await?;
+        RoleRepo::set_admin_roles(conn, admin_id, roles).await?;
+        let role = match roles.iter().any(|role| role == "admin") {
+            true => AdminRole::Admin,
+            false => AdminRole::Staff,
+        };
+        AdminRepo::update_admin_role(conn, admin_id, &role).await?;
+        AuditService::append(
+            conn,
+            admin,
+            "admin.roles",
+            "admin",
+            admin_id,
+            Some(json!({ "roles": before })),
+            Some(json!({ "roles": roles, "role": role.to_string() })),
+        )
+        .await
+    }
+}
@@ -1,7 +1,5 @@
-use crate::entity::{AdminRole, Shortage};
+use crate::entity::{AuthedAdmin, Permission, Shortage};
 use crate::repo::{ShortageRepo, SupplierRepo};
-use crate::service::AdminService;
-use crate::utils::Token;
 use mysql_async::Conn;
 
 pub struct ShortageService;
@@ -9,11 +7,11 @@ pub struct ShortageService;
 impl ShortageService {
     pub async fn create_book_shortage(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         book_suppliers: &Vec<(u32, u32, u32)>,
     ) -> anyhow::Result<u32> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => {
+        match admin.has_permission(Permission::ShortageWrite) {
+            true => {
                 for (book_id, supplier_id, quantity) in book_suppliers.iter() {
                     let suppliers =
                         SupplierRepo::get_available_suppliers(conn, *book_id, *quantity).await?;
@@ -32,20 +30,20 @@ impl ShortageService {
                     None => anyhow::bail!("create shortage failed"),
                 }
             }
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can create shortage")
+            false => {
+                anyhow::bail!("permission denied: shortage.write required to create shortage")
             }
         }
     }
 
     pub async fn get_shortage_list(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
     ) -> anyhow::Result<Vec<Shortage>> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => ShortageRepo::get_shortage_list(conn).await,
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can view shortage list")
+        match admin.has_permission(Permission::ShortageRead) {
+            true => ShortageRepo::get_shortage_list(conn).await,
+            false => {
+                anyhow::bail!("permission denied: shortage.read required to view shortage list")
             }
         }
     }
@@ -53,15 +51,15 @@ impl ShortageService {
     pub async fn get_shortage_detail(
         conn: &mut Conn,
         shortage_id: u32,
-        token: &Token,
+        admin: &AuthedAdmin,
     ) -> anyhow::Result<Shortage> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => match ShortageRepo::get_shortage_detail(conn, shortage_id).await? {
+        match admin.has_permission(Permission::ShortageRead) {
+            true => match ShortageRepo::get_shortage_detail(conn, shortage_id).await? {
                 Some(shortage) => Ok(shortage),
                 None => anyhow::bail!("shortage {} not found", shortage_id),
             },
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can view shortage")
+            false => {
+                anyhow::bail!("permission denied: shortage.read required to view shortage")
             }
         }
     }
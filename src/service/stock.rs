@@ -1,62 +1,155 @@
-use crate::entity::{AdminRole, Location};
-use crate::repo::StockRepo;
-use crate::service::AdminService;
-use crate::utils::Token;
+use crate::entity::{AuthedAdmin, Location, Permission};
+use crate::repo::{StockRepo, UtilsRepo};
+use crate::service::AuditService;
 use mysql_async::Conn;
+use serde_json::json;
 
 pub struct StockService;
 
 impl StockService {
     pub async fn get_location_list(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
     ) -> anyhow::Result<Vec<Location>> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => StockRepo::get_location_list(conn).await,
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can get location list")
+        match admin.has_permission(Permission::StockRead) {
+            true => StockRepo::get_location_list(conn).await,
+            false => {
+                anyhow::bail!("permission denied: stock.read required to get location list")
             }
         }
     }
 
     pub async fn change_stock(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         book_id: u32,
         location_id: u32,
         quantity: i32,
     ) -> anyhow::Result<()> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => {
-                if quantity >= 0 {
-                    StockRepo::in_stock(conn, book_id, location_id, quantity as u32).await
-                } else {
-                    StockRepo::out_stock(conn, book_id, location_id, (-quantity) as u32).await
+        match admin.has_permission(Permission::StockAdjust) {
+            true => {
+                UtilsRepo::transaction(conn).await?;
+                match StockService::apply_stock_change(conn, admin, book_id, location_id, quantity)
+                    .await
+                {
+                    Ok(_) => {
+                        UtilsRepo::commit(conn).await?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!(e)
+                    }
                 }
             }
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can change stock")
+            false => {
+                anyhow::bail!("permission denied: stock.adjust required to change stock")
             }
         }
     }
 
+    async fn apply_stock_change(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        book_id: u32,
+        location_id: u32,
+        quantity: i32,
+    ) -> anyhow::Result<()> {
+        let before = StockRepo::get_book_quantity(conn, book_id, location_id).await?;
+        if quantity >= 0 {
+            StockRepo::in_stock(conn, book_id, location_id, quantity as u32).await?;
+        } else {
+            StockRepo::out_stock(conn, book_id, location_id, (-quantity) as u32).await?;
+        }
+        let after = StockRepo::get_book_quantity(conn, book_id, location_id).await?;
+        AuditService::append(
+            conn,
+            admin,
+            "stock.change",
+            "book",
+            book_id,
+            Some(json!({ "location_id": location_id, "quantity": before })),
+            Some(json!({ "location_id": location_id, "quantity": after })),
+        )
+        .await
+    }
+
     pub async fn transfer_stock(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         book_id: u32,
         from_location_id: u32,
         to_location_id: u32,
         quantity: u32,
     ) -> anyhow::Result<()> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => {
-                StockRepo::out_stock(conn, book_id, from_location_id, quantity).await?;
-                StockRepo::in_stock(conn, book_id, to_location_id, quantity).await
+        match admin.has_permission(Permission::StockAdjust) {
+            true => {
+                UtilsRepo::transaction(conn).await?;
+                match StockService::apply_stock_transfer(
+                    conn,
+                    admin,
+                    book_id,
+                    from_location_id,
+                    to_location_id,
+                    quantity,
+                )
+                .await
+                {
+                    Ok(_) => {
+                        UtilsRepo::commit(conn).await?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!(e)
+                    }
+                }
             }
-
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can transfer stock")
+            false => {
+                anyhow::bail!("permission denied: stock.adjust required to transfer stock")
             }
         }
     }
+
+    async fn apply_stock_transfer(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        book_id: u32,
+        from_location_id: u32,
+        to_location_id: u32,
+        quantity: u32,
+    ) -> anyhow::Result<()> {
+        let before = json!({
+            "from": {
+                "location_id": from_location_id,
+                "quantity": StockRepo::get_book_quantity(conn, book_id, from_location_id).await?,
+            },
+            "to": {
+                "location_id": to_location_id,
+                "quantity": StockRepo::get_book_quantity(conn, book_id, to_location_id).await?,
+            },
+        });
+        StockRepo::out_stock(conn, book_id, from_location_id, quantity).await?;
+        StockRepo::in_stock(conn, book_id, to_location_id, quantity).await?;
+        let after = json!({
+            "from": {
+                "location_id": from_location_id,
+                "quantity": StockRepo::get_book_quantity(conn, book_id, from_location_id).await?,
+            },
+            "to": {
+                "location_id": to_location_id,
+                "quantity": StockRepo::get_book_quantity(conn, book_id, to_location_id).await?,
+            },
+        });
+        AuditService::append(
+            conn,
+            admin,
+            "stock.transfer",
+            "book",
+            book_id,
+            Some(before),
+            Some(after),
+        )
+        .await
+    }
 }
@@ -0,0 +1,98 @@
+use crate::entity::Suggestion;
+use crate::repo::BookRepo;
+use mysql_async::Conn;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+const SUGGESTION_LIMIT_MAX: usize = 20;
+const SUGGESTION_CACHE_MAX_AGE: Duration = Duration::from_secs(60 * 5);
+
+static CATALOG_VERSION: AtomicU64 = AtomicU64::new(0);
+static SUGGESTION_CACHE: RwLock<Option<SuggestionIndex>> = RwLock::new(None);
+
+struct SuggestionIndex {
+    version: u64,
+    loaded_at: Instant,
+    entries: Arc<Vec<(String, Suggestion)>>,
+}
+
+pub struct SuggestService;
+
+impl SuggestService {
+    pub fn invalidate() {
+        CATALOG_VERSION.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub async fn suggest(
+        conn: &mut Conn,
+        query: &str,
+        limit: usize,
+    ) -> anyhow::Result<Vec<Suggestion>> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let entries = SuggestService::entries(conn).await?;
+        let mut matches = entries
+            .iter()
+            .filter_map(|(text, suggestion)| {
+                let rank = if text.starts_with(&query) {
+                    0
+                } else if text
+                    .split(|c: char| !c.is_alphanumeric())
+                    .any(|word| word.starts_with(&query))
+                {
+                    1
+                } else if text.contains(&query) {
+                    2
+                } else {
+                    return None;
+                };
+                Some((rank, suggestion))
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|(a_rank, a), (b_rank, b)| {
+            a_rank
+                .cmp(b_rank)
+                .then(a.kind.cmp(&b.kind))
+                .then(a.text.len().cmp(&b.text.len()))
+                .then(a.text.cmp(&b.text))
+        });
+        Ok(matches
+            .into_iter()
+            .take(limit.clamp(1, SUGGESTION_LIMIT_MAX))
+            .map(|(_, suggestion)| suggestion.clone())
+            .collect())
+    }
+
+    async fn entries(conn: &mut Conn) -> anyhow::Result<Arc<Vec<(String, Suggestion)>>> {
+        let version = CATALOG_VERSION.load(Ordering::SeqCst);
+        if let Some(index) = SUGGESTION_CACHE
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+        {
+            if index.version == version && index.loaded_at.elapsed() < SUGGESTION_CACHE_MAX_AGE {
+                return Ok(index.entries.clone());
+            }
+        }
+
+        let entries = Arc::new(
+            BookRepo::get_suggestion_list(conn)
+                .await?
+                .into_iter()
+                .map(|suggestion| (suggestion.text.to_lowercase(), suggestion))
+                .collect::<Vec<_>>(),
+        );
+        *SUGGESTION_CACHE
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(SuggestionIndex {
+            version,
+            loaded_at: Instant::now(),
+            entries: entries.clone(),
+        });
+        Ok(entries)
+    }
+}
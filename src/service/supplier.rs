@@ -1,7 +1,5 @@
-use crate::entity::{AdminRole, Supplier, SupplierCatalog};
+use crate::entity::{AuthedAdmin, Permission, Supplier, SupplierCatalog};
 use crate::repo::SupplierRepo;
-use crate::service::AdminService;
-use crate::utils::Token;
 use mysql_async::Conn;
 
 pub struct SupplierService;
@@ -24,17 +22,17 @@ impl SupplierService {
         email: &str,
         address: &str,
         fax: &str,
-        token: &Token,
+        admin: &AuthedAdmin,
     ) -> anyhow::Result<u32> {
-        match AdminService::verify_admin(conn, token, AdminRole::Admin).await? {
-            (_, _, true) => {
+        match admin.has_permission(Permission::SupplierWrite) {
+            true => {
                 match SupplierRepo::add_supplier(conn, name, telephone, email, address, fax).await?
                 {
                     None => anyhow::bail!("add supplier failed"),
                     Some(supplier_id) => Ok(supplier_id),
                 }
             }
-            (_, _, false) => anyhow::bail!("permission denied: only admin can add supplier"),
+            false => anyhow::bail!("permission denied: supplier.write required to add supplier"),
         }
     }
 
@@ -42,6 +40,7 @@ impl SupplierService {
         SupplierRepo::get_supplier_list(conn).await
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn update_supplier(
         conn: &mut Conn,
         supplier_id: u32,
@@ -50,10 +49,10 @@ impl SupplierService {
         email: &str,
         address: &str,
         fax: &str,
-        token: &Token,
+        admin: &AuthedAdmin,
     ) -> anyhow::Result<()> {
-        match AdminService::verify_admin(conn, token, AdminRole::Admin).await? {
-            (_, _, true) => {
+        match admin.has_permission(Permission::SupplierWrite) {
+            true => {
                 SupplierRepo::update_supplier(
                     conn,
                     supplier_id,
@@ -65,30 +64,30 @@ impl SupplierService {
                 )
                 .await
             }
-            (_, _, false) => anyhow::bail!("permission denied: only admin can update supplier"),
+            false => anyhow::bail!("permission denied: supplier.write required to update supplier"),
         }
     }
 
     pub async fn delete_supplier(
         conn: &mut Conn,
         supplier_id: u32,
-        token: &Token,
+        admin: &AuthedAdmin,
     ) -> anyhow::Result<()> {
-        match AdminService::verify_admin(conn, token, AdminRole::Admin).await? {
-            (_, _, true) => SupplierRepo::delete_supplier(conn, supplier_id).await,
-            (_, _, false) => anyhow::bail!("permission denied: only admin can delete supplier"),
+        match admin.has_permission(Permission::SupplierWrite) {
+            true => SupplierRepo::delete_supplier(conn, supplier_id).await,
+            false => anyhow::bail!("permission denied: supplier.write required to delete supplier"),
         }
     }
 
     pub async fn get_supplier_catalog_list(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
     ) -> anyhow::Result<Vec<SupplierCatalog>> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => SupplierRepo::get_catalog_list(conn).await,
-            (_, _, false) => {
+        match admin.has_permission(Permission::SupplierRead) {
+            true => SupplierRepo::get_catalog_list(conn).await,
+            false => {
                 anyhow::bail!(
-                    "permission denied: only staff or admin can get supplier catalog list"
+                    "permission denied: supplier.read required to get supplier catalog list"
                 )
             }
         }
@@ -97,12 +96,12 @@ impl SupplierService {
     pub async fn get_supplier_catalog_list_by_supplier(
         conn: &mut Conn,
         supplier_id: u32,
-        token: &Token,
+        admin: &AuthedAdmin,
     ) -> anyhow::Result<Vec<SupplierCatalog>> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => SupplierRepo::get_catalog_list_by_supplier(conn, supplier_id).await,
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can get supplier catalog")
+        match admin.has_permission(Permission::SupplierRead) {
+            true => SupplierRepo::get_catalog_list_by_supplier(conn, supplier_id).await,
+            false => {
+                anyhow::bail!("permission denied: supplier.read required to get supplier catalog")
             }
         }
     }
@@ -110,12 +109,12 @@ impl SupplierService {
     pub async fn get_supplier_catalog_list_by_book(
         conn: &mut Conn,
         book_id: u32,
-        token: &Token,
+        admin: &AuthedAdmin,
     ) -> anyhow::Result<Vec<SupplierCatalog>> {
-        match AdminService::verify_admin(conn, token, AdminRole::Staff).await? {
-            (_, _, true) => SupplierRepo::get_catalog_list_by_book(conn, book_id).await,
-            (_, _, false) => {
-                anyhow::bail!("permission denied: only staff or admin can get supplier catalog")
+        match admin.has_permission(Permission::SupplierRead) {
+            true => SupplierRepo::get_catalog_list_by_book(conn, book_id).await,
+            false => {
+                anyhow::bail!("permission denied: supplier.read required to get supplier catalog")
             }
         }
     }
@@ -1,70 +1,470 @@
-use crate::entity::{AdminRole, CreditRule, Customer, UserStatus};
-use crate::repo::{AuthRepo, UserRepo};
-use crate::service::{AdminService, AuthService};
-use crate::utils::{encrypt_password, generate_token, validate_token, Token};
+use crate::entity::{
+    AuditFilter, AuthedAdmin, AuthedCustomer, CreditRule, Customer, CustomerSession,
+    CustomerStatusChange, Order, Permission, PriceInquiry, UserStatus,
+};
+use crate::repo::{AuditRepo, AuthRepo, BookRepo, OrderRepo, UserRepo, UtilsRepo};
+use crate::service::{AuditService, AuthService};
+use crate::utils::{
+    generate_secret, hash_password, hash_secret, parse_datetime, validate_token,
+    verify_dummy_password, verify_password, Audience, Mailer, PasswordVerification, Token,
+};
 use mysql_async::Conn;
+use mysql_common::time::PrimitiveDateTime;
+use serde_json::{json, Value};
+
+const PASSWORD_RESET_VALIDITY: i64 = 60 * 30;
+const EMAIL_VERIFICATION_VALIDITY: i64 = 60 * 60 * 24;
+const EMAIL_COOLDOWN: i64 = 60;
+
+pub struct BalanceAdjustment {
+    pub date: PrimitiveDateTime,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+pub struct UserExport {
+    pub customer: Customer,
+    pub orders: Vec<Order>,
+    pub price_inquiries: Vec<PriceInquiry>,
+    pub sessions: Vec<CustomerSession>,
+    pub status_history: Vec<CustomerStatusChange>,
+    pub staff_balance_adjustments: Vec<BalanceAdjustment>,
+}
 
 pub struct UserService;
 
 impl UserService {
-    pub async fn login(conn: &mut Conn, username: &str, password: &str) -> anyhow::Result<Token> {
-        let password = encrypt_password(password).await?;
+    pub async fn login(
+        conn: &mut Conn,
+        username: &str,
+        password: &str,
+        user_agent: &str,
+        ip: &str,
+    ) -> anyhow::Result<(Token, String)> {
+        AuthService::check_login(conn, Audience::Customer, username, ip).await?;
+
+        let user_id = match UserRepo::get_user_password(conn, username).await? {
+            Some((user_id, hash)) => match verify_password(password, &hash).await? {
+                PasswordVerification::Valid => Some(user_id),
+                PasswordVerification::Outdated => {
+                    let hash = hash_password(password).await?;
+                    UserRepo::update_user_password(conn, user_id, &hash).await?;
+                    Some(user_id)
+                }
+                PasswordVerification::Invalid => None,
+            },
+            None => {
+                verify_dummy_password(password).await?;
+                None
+            }
+        };
 
-        match UserRepo::login(conn, username, &password).await? {
+        match user_id {
             Some(user_id) => {
-                let token = generate_token(username).await?;
-                AuthRepo::create_auth_record(conn, user_id, &token.token).await?;
-                Ok(token)
+                AuthService::clear_login_failures(conn, Audience::Customer, username).await?;
+                if let Err(e) = UserService::check_user_status(conn, user_id).await {
+                    AuthRepo::revoke_customer_sessions(conn, user_id).await?;
+                    anyhow::bail!(e);
+                }
+                AuthService::create_user_session(conn, user_id, user_agent).await
+            }
+            None => {
+                match AuthService::record_login_failure(conn, Audience::Customer, username, ip)
+                    .await?
+                {
+                    Some(locked) => Err(locked.into()),
+                    None => anyhow::bail!("login failed, username or password incorrect"),
+                }
             }
-            None => anyhow::bail!("login failed, username or password incorrect"),
         }
     }
 
     pub async fn register(
         conn: &mut Conn,
+        mailer: &dyn Mailer,
         username: &str,
         password: &str,
         name: &str,
-    ) -> anyhow::Result<Token> {
-        let password = encrypt_password(password).await?;
+        email: &str,
+        user_agent: &str,
+    ) -> anyhow::Result<(Token, String)> {
+        UserService::check_email_format(email)?;
+        let password = hash_password(password).await?;
 
-        match UserRepo::register(conn, username, &password, name).await? {
+        match UserRepo::register(conn, username, &password, name, email).await? {
             Some(user_id) => {
-                let token = generate_token(username).await?;
-                AuthRepo::create_auth_record(conn, user_id, &token.token).await?;
-                Ok(token)
+                if let Err(e) =
+                    UserService::send_email_verification(conn, mailer, user_id, name, email).await
+                {
+                    log::error!(
+                        "error sending verification email to user {}: {}",
+                        user_id,
+                        e
+                    );
+                }
+                AuthService::create_user_session(conn, user_id, user_agent).await
             }
-
             None => anyhow::bail!("register failed"),
         }
     }
 
     pub async fn update_user_profile(
         conn: &mut Conn,
-        token: &Token,
+        mailer: &dyn Mailer,
+        customer: &AuthedCustomer,
         username: &str,
         name: &str,
         email: &str,
         address: &str,
-    ) -> anyhow::Result<Token> {
-        let (customer_id, _) = AuthService::verify_user(conn, token).await?;
+    ) -> anyhow::Result<()> {
+        let old_email = match UserRepo::get_user_detail_by_id(conn, customer.id).await? {
+            Some(user) => user.email,
+            None => anyhow::bail!("user {} not found", customer.id),
+        };
+        if old_email == email {
+            return UserRepo::update_user_profile(
+                conn,
+                customer.id,
+                username,
+                name,
+                address,
+                email,
+            )
+            .await;
+        }
+
+        UserService::check_email_format(email)?;
+        UtilsRepo::transaction(conn).await?;
+        let token = match UserService::apply_email_change(
+            conn,
+            customer.id,
+            username,
+            name,
+            address,
+            email,
+        )
+        .await
+        {
+            Ok(token) => {
+                UtilsRepo::commit(conn).await?;
+                token
+            }
+            Err(e) => {
+                UtilsRepo::rollback(conn).await?;
+                anyhow::bail!(e)
+            }
+        };
+        UserService::mail_email_verification(mailer, name, email, &token)
+    }
+
+    async fn apply_email_change(
+        conn: &mut Conn,
+        customer_id: u32,
+        username: &str,
+        name: &str,
+        address: &str,
+        email: &str,
+    ) -> anyhow::Result<String> {
         UserRepo::update_user_profile(conn, customer_id, username, name, address, email).await?;
-        let token = generate_token(username).await?;
-        AuthRepo::update_auth_record(conn, customer_id, &token.token, true).await?;
+        UserRepo::clear_email_verified(conn, customer_id).await?;
+        UserService::issue_email_verification(conn, customer_id, email).await
+    }
+
+    pub async fn request_email_verification(
+        conn: &mut Conn,
+        mailer: &dyn Mailer,
+        customer: &AuthedCustomer,
+    ) -> anyhow::Result<()> {
+        let user = match UserRepo::get_user_detail_by_id(conn, customer.id).await? {
+            Some(user) => user,
+            None => anyhow::bail!("user {} not found", customer.id),
+        };
+        if user.email_verified_at.is_some() {
+            anyhow::bail!("email address is already verified");
+        }
+        if user.email.is_empty() {
+            anyhow::bail!("no email address on this account");
+        }
+        UserService::send_email_verification(conn, mailer, user.id, &user.name, &user.email).await
+    }
+
+    pub async fn verify_email(conn: &mut Conn, token: &str) -> anyhow::Result<()> {
+        let verification_token =
+            match AuthRepo::get_email_verification_token(conn, &hash_secret(token)).await? {
+                Some(verification_token) => verification_token,
+                None => anyhow::bail!("invalid or expired verification token"),
+            };
+        if !AuthRepo::use_email_verification_token(conn, verification_token.id).await? {
+            anyhow::bail!("invalid or expired verification token");
+        }
+        match UserRepo::set_email_verified(
+            conn,
+            verification_token.customer_id,
+            &verification_token.email,
+        )
+        .await?
+        {
+            true => Ok(()),
+            false => anyhow::bail!("email address has changed since the verification was sent"),
+        }
+    }
+
+    pub async fn check_email_verified(conn: &mut Conn, customer_id: u32) -> anyhow::Result<()> {
+        match UserRepo::get_user_detail_by_id(conn, customer_id).await? {
+            Some(user) if user.email_verified_at.is_some() => Ok(()),
+            Some(_) => anyhow::bail!("email address is not verified"),
+            None => anyhow::bail!("user {} not found", customer_id),
+        }
+    }
+
+    async fn send_email_verification(
+        conn: &mut Conn,
+        mailer: &dyn Mailer,
+        customer_id: u32,
+        name: &str,
+        email: &str,
+    ) -> anyhow::Result<()> {
+        let token = UserService::issue_email_verification(conn, customer_id, email).await?;
+        UserService::mail_email_verification(mailer, name, email, &token)
+    }
+
+    async fn issue_email_verification(
+        conn: &mut Conn,
+        customer_id: u32,
+        email: &str,
+    ) -> anyhow::Result<String> {
+        if AuthRepo::has_recent_email_verification_token(conn, customer_id, EMAIL_COOLDOWN).await? {
+            anyhow::bail!("a verification email was sent less than a minute ago, retry later");
+        }
+        let token = generate_secret();
+        AuthRepo::create_email_verification_token(
+            conn,
+            customer_id,
+            email,
+            &hash_secret(&token),
+            EMAIL_VERIFICATION_VALIDITY,
+        )
+        .await?;
         Ok(token)
     }
 
-    pub async fn get_user_detail(conn: &mut Conn, token: &Token) -> anyhow::Result<Customer> {
-        let (_, username) = AuthService::verify_user(conn, token).await?;
+    fn mail_email_verification(
+        mailer: &dyn Mailer,
+        name: &str,
+        email: &str,
+        token: &str,
+    ) -> anyhow::Result<()> {
+        mailer.send(
+            email,
+            "Verify your bookstore email address",
+            &format!(
+                "Hello {},\r\n\r\nUse the following token to verify your email address. It expires in {} hours and can only be used once.\r\n\r\n{}\r\n\r\nUntil your email address is verified you will not be able to place orders or price inquiries.",
+                name,
+                EMAIL_VERIFICATION_VALIDITY / 3600,
+                token
+            ),
+        )
+    }
+
+    fn check_email_format(email: &str) -> anyhow::Result<()> {
+        match email.split_once('@') {
+            Some((local, domain))
+                if !local.is_empty()
+                    && domain.contains('.')
+                    && !domain.starts_with('.')
+                    && !domain.ends_with('.')
+                    && !email.contains(char::is_whitespace) =>
+            {
+                Ok(())
+            }
+            _ => anyhow::bail!("invalid email address {}", email),
+        }
+    }
+
+    pub async fn change_password(
+        conn: &mut Conn,
+        customer: &AuthedCustomer,
+        old_password: &str,
+        new_password: &str,
+    ) -> anyhow::Result<()> {
+        match UserRepo::get_user_password(conn, &customer.username).await? {
+            Some((_, hash)) => match verify_password(old_password, &hash).await? {
+                PasswordVerification::Invalid => anyhow::bail!("old password incorrect"),
+                _ => {
+                    let hash = hash_password(new_password).await?;
+                    UserRepo::update_user_password(conn, customer.id, &hash).await?;
+                    AuthRepo::revoke_other_customer_sessions(conn, customer.id, customer.session_id)
+                        .await
+                }
+            },
+            None => anyhow::bail!("user {} not found", customer.username),
+        }
+    }
+
+    pub async fn request_password_reset(
+        conn: &mut Conn,
+        mailer: &dyn Mailer,
+        username: &str,
+    ) -> anyhow::Result<()> {
+        let user = match UserRepo::get_user_detail(conn, username).await? {
+            Some(user) if !user.email.is_empty() => user,
+            _ => return Ok(()),
+        };
+        if AuthRepo::has_recent_password_reset_token(conn, user.id, EMAIL_COOLDOWN).await? {
+            return Ok(());
+        }
+
+        let token = generate_secret();
+        AuthRepo::create_password_reset_token(
+            conn,
+            user.id,
+            &hash_secret(&token),
+            PASSWORD_RESET_VALIDITY,
+        )
+        .await?;
+        mailer.send(
+            &user.email,
+            "Reset your bookstore password",
+            &format!(
+                "Hello {},\r\n\r\nUse the following token to reset your password. It expires in {} minutes and can only be used once.\r\n\r\n{}\r\n\r\nIf you did not request a password reset, you can ignore this email.",
+                user.name,
+                PASSWORD_RESET_VALIDITY / 60,
+                token
+            ),
+        )
+    }
+
+    pub async fn reset_password(
+        conn: &mut Conn,
+        token: &str,
+        password: &str,
+    ) -> anyhow::Result<()> {
+        let reset_token =
+            match AuthRepo::get_password_reset_token(conn, &hash_secret(token)).await? {
+                Some(reset_token) => reset_token,
+                None => anyhow::bail!("invalid or expired reset token"),
+            };
+        match AuthRepo::use_password_reset_token(conn, reset_token.id).await? {
+            true => {
+                let hash = hash_password(password).await?;
+                UserRepo::update_user_password(conn, reset_token.customer_id, &hash).await?;
+                AuthRepo::revoke_customer_sessions(conn, reset_token.customer_id).await
+            }
+            false => anyhow::bail!("invalid or expired reset token"),
+        }
+    }
 
-        match UserRepo::get_user_detail(conn, &username).await? {
+    pub async fn get_user_detail(
+        conn: &mut Conn,
+        customer: &AuthedCustomer,
+    ) -> anyhow::Result<Customer> {
+        match UserRepo::get_user_detail(conn, &customer.username).await? {
             Some(user) => Ok(user),
-            None => anyhow::bail!("user {} not found", username),
+            None => anyhow::bail!("user {} not found", customer.username),
+        }
+    }
+
+    pub async fn export_user_data(
+        conn: &mut Conn,
+        customer: &AuthedCustomer,
+    ) -> anyhow::Result<UserExport> {
+        let customer_data = match UserRepo::get_user_detail_by_id(conn, customer.id).await? {
+            Some(user) => user,
+            None => anyhow::bail!("user {} not found", customer.id),
+        };
+
+        let mut orders = OrderRepo::get_order_list(conn, &customer.username).await?;
+        for order in orders.iter_mut() {
+            order.items = OrderRepo::get_order_items(conn, order.id).await?;
+        }
+        let price_inquiries =
+            BookRepo::get_price_inquiry_list_by_customer(conn, customer.id).await?;
+        let sessions = AuthRepo::get_customer_session_history(conn, customer.id).await?;
+        let status_history = UserRepo::get_user_status_history(conn, customer.id).await?;
+
+        let filter = AuditFilter {
+            action: Some("customer.balance".to_string()),
+            entity_type: Some("customer".to_string()),
+            entity_id: Some(customer.id),
+            ..Default::default()
+        };
+        let total = AuditRepo::get_audit_log_count(conn, &filter).await?;
+        let staff_balance_adjustments = AuditRepo::get_audit_logs(conn, &filter, 0, total)
+            .await?
+            .into_iter()
+            .rev()
+            .map(|log| BalanceAdjustment {
+                date: log.created_at,
+                before: UserService::audit_balance(log.before_value.as_deref()),
+                after: UserService::audit_balance(log.after_value.as_deref()),
+            })
+            .collect();
+
+        Ok(UserExport {
+            customer: customer_data,
+            orders,
+            price_inquiries,
+            sessions,
+            status_history,
+            staff_balance_adjustments,
+        })
+    }
+
+    fn audit_balance(value: Option<&str>) -> Option<String> {
+        let value: Value = serde_json::from_str(value?).ok()?;
+        value["account_balance"].as_str().map(str::to_string)
+    }
+
+    pub async fn close_account(
+        conn: &mut Conn,
+        customer: &AuthedCustomer,
+        password: &str,
+    ) -> anyhow::Result<()> {
+        match UserRepo::get_user_password(conn, &customer.username).await? {
+            Some((_, hash)) => {
+                if let PasswordVerification::Invalid = verify_password(password, &hash).await? {
+                    anyhow::bail!("password incorrect");
+                }
+            }
+            None => anyhow::bail!("user {} not found", customer.username),
         }
+        let old_status = match UserRepo::get_user_status(conn, customer.id).await? {
+            Some((old_status, _)) => old_status,
+            None => anyhow::bail!("user {} not found", customer.id),
+        };
+
+        UtilsRepo::transaction(conn).await?;
+        match UserService::apply_account_closure(conn, customer.id, &old_status).await {
+            Ok(_) => UtilsRepo::commit(conn).await,
+            Err(e) => {
+                UtilsRepo::rollback(conn).await?;
+                anyhow::bail!(e)
+            }
+        }
+    }
+
+    async fn apply_account_closure(
+        conn: &mut Conn,
+        customer_id: u32,
+        old_status: &UserStatus,
+    ) -> anyhow::Result<()> {
+        UserService::apply_user_status(
+            conn,
+            None,
+            customer_id,
+            old_status,
+            &UserStatus::Cancelled,
+            "closed by customer",
+            None,
+        )
+        .await?;
+        UserRepo::anonymize_user(conn, customer_id).await?;
+        AuthRepo::delete_email_verification_tokens(conn, customer_id).await?;
+        AuthRepo::invalidate_password_reset_tokens(conn, customer_id).await
     }
 
     pub async fn get_user_profile(conn: &mut Conn, username: &str) -> anyhow::Result<Customer> {
-        match UserRepo::get_user_detail(conn, &username).await? {
+        match UserRepo::get_user_detail(conn, username).await? {
             Some(user) => Ok(Customer {
                 username: user.username,
                 name: user.name,
@@ -76,12 +476,13 @@ impl UserService {
         }
     }
 
-    pub async fn get_credit_rule(conn: &mut Conn, token: &Token) -> anyhow::Result<CreditRule> {
-        let (_, username) = AuthService::verify_user(conn, token).await?;
-
-        let credit_level = match UserRepo::get_user_credit_level(conn, &username).await? {
+    pub async fn get_credit_rule(
+        conn: &mut Conn,
+        customer: &AuthedCustomer,
+    ) -> anyhow::Result<CreditRule> {
+        let credit_level = match UserRepo::get_user_credit_level(conn, &customer.username).await? {
             Some(credit_level) => credit_level,
-            None => anyhow::bail!("user {} not found", username),
+            None => anyhow::bail!("user {} not found", customer.username),
         };
 
         match UserRepo::get_credit_rule(conn, credit_level).await? {
@@ -90,42 +491,160 @@ impl UserService {
         }
     }
 
-    pub async fn update_user_credit_level(
+    pub async fn update_user_status(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         customer_id: u32,
-        credit_level: u32,
+        status: UserStatus,
+        reason: &str,
+        expires_at: Option<&str>,
     ) -> anyhow::Result<()> {
-        match AdminService::verify_admin(conn, token, AdminRole::Admin).await? {
-            (_, _, true) => {
-                UserRepo::update_user_credit_level(conn, customer_id, credit_level).await?;
-                Ok(())
+        match admin.has_permission(Permission::CustomerStatus) {
+            true => {
+                if let Some(expires_at) = expires_at {
+                    if status != UserStatus::Banned {
+                        anyhow::bail!("only a ban can have an expiry date");
+                    }
+                    parse_datetime(expires_at)?;
+                }
+                let old_status = match UserRepo::get_user_status(conn, customer_id).await? {
+                    Some((old_status, _)) => old_status,
+                    None => anyhow::bail!("user {} not found", customer_id),
+                };
+
+                UtilsRepo::transaction(conn).await?;
+                match UserService::apply_admin_user_status(
+                    conn,
+                    admin,
+                    customer_id,
+                    &old_status,
+                    &status,
+                    reason,
+                    expires_at,
+                )
+                .await
+                {
+                    Ok(_) => {
+                        UtilsRepo::commit(conn).await?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        UtilsRepo::rollback(conn).await?;
+                        anyhow::bail!(e)
+                    }
+                }
+            }
+            false => {
+                anyhow::bail!("permission denied: customer.status required to update user status")
             }
-            (_, _, false) => anyhow::bail!("permission denied: only admin can update credit level"),
         }
     }
 
-    pub async fn update_user_status(
+    async fn apply_admin_user_status(
         conn: &mut Conn,
-        token: &Token,
+        admin: &AuthedAdmin,
         customer_id: u32,
-        status: UserStatus,
+        old_status: &UserStatus,
+        status: &UserStatus,
+        reason: &str,
+        expires_at: Option<&str>,
     ) -> anyhow::Result<()> {
-        match AdminService::verify_admin(conn, token, AdminRole::Admin).await? {
-            (_, _, true) => {
-                UserRepo::update_user_status(conn, customer_id, status).await?;
-                Ok(())
+        UserService::apply_user_status(
+            conn,
+            Some(admin.id),
+            customer_id,
+            old_status,
+            status,
+            reason,
+            expires_at,
+        )
+        .await?;
+        AuditService::append(
+            conn,
+            admin,
+            "customer.status",
+            "customer",
+            customer_id,
+            Some(json!({ "status": old_status.to_string() })),
+            Some(json!({
+                "status": status.to_string(),
+                "reason": reason,
+                "expires_at": expires_at,
+            })),
+        )
+        .await
+    }
+
+    async fn apply_user_status(
+        conn: &mut Conn,
+        admin_id: Option<u32>,
+        customer_id: u32,
+        old_status: &UserStatus,
+        status: &UserStatus,
+        reason: &str,
+        expires_at: Option<&str>,
+    ) -> anyhow::Result<()> {
+        UserRepo::update_user_status(conn, customer_id, *status, expires_at).await?;
+        UserRepo::add_user_status_history(
+            conn,
+            customer_id,
+            admin_id,
+            old_status,
+            status,
+            reason,
+            expires_at,
+        )
+        .await?;
+        match status {
+            UserStatus::Active => Ok(()),
+            _ => AuthRepo::revoke_customer_sessions(conn, customer_id).await,
+        }
+    }
+
+    pub async fn check_user_status(conn: &mut Conn, customer_id: u32) -> anyhow::Result<()> {
+        let now = UtilsRepo::now(conn).await?;
+        match UserRepo::get_user_status(conn, customer_id).await? {
+            Some((UserStatus::Active, _)) => Ok(()),
+            Some((UserStatus::Banned, Some(expires_at))) if now >= expires_at => {
+                UserService::apply_user_status(
+                    conn,
+                    None,
+                    customer_id,
+                    &UserStatus::Banned,
+                    &UserStatus::Active,
+                    "temporary ban expired",
+                    None,
+                )
+                .await
+            }
+            Some((UserStatus::Banned, Some(expires_at))) => {
+                anyhow::bail!("account is banned until {}", expires_at)
             }
-            (_, _, false) => anyhow::bail!("permission denied: only admin can update user status"),
+            Some((UserStatus::Banned, None)) => anyhow::bail!("account is banned"),
+            Some((UserStatus::Cancelled, _)) => anyhow::bail!("account is cancelled"),
+            None => anyhow::bail!("user {} not found", customer_id),
+        }
+    }
+
+    pub async fn get_user_status_history(
+        conn: &mut Conn,
+        admin: &AuthedAdmin,
+        customer_id: u32,
+    ) -> anyhow::Result<Vec<CustomerStatusChange>> {
+        match admin.has_permission(Permission::CustomerRead) {
+            true => UserRepo::get_user_status_history(conn, customer_id).await,
+            false => anyhow::bail!(
+                "permission denied: customer.read required to get user status history"
+            ),
         }
     }
 
     pub async fn verify_user(conn: &mut Conn, token: &Token) -> anyhow::Result<(u32, String)> {
-        let username = validate_token(token).await?;
+        let user_id = validate_token(token, Audience::Customer).await?;
 
-        match UserRepo::get_user_id(conn, &username).await? {
-            Some(user_id) => Ok((user_id, username)),
-            None => anyhow::bail!("user {} not found", username),
+        match UserRepo::get_username(conn, user_id).await? {
+            Some(username) => Ok((user_id, username)),
+            None => anyhow::bail!("user {} not found", user_id),
         }
     }
 }
@@ -0,0 +1,178 @@
+use crate::utils::{is_valid_isbn13, sha256_hex};
+use actix_web::web::Bytes;
+use image::imageops::FilterType;
+use image::{ImageFormat, ImageReader, Limits};
+use std::io::{Cursor, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+const COVER_DIR_ENV: &str = "BOOKSTORE_COVER_DIR";
+const DEFAULT_COVER_DIR: &str = "assets/covers";
+pub const COVER_URL_PREFIX: &str = "/assets/covers";
+pub const COVER_SIZE_MAX: usize = 5 * 1024 * 1024;
+const COVER_DIMENSION_MAX: u32 = 4000;
+const COVER_ALLOC_MAX: u64 = 64 * 1024 * 1024;
+const COVER_FORMATS: [ImageFormat; 3] = [ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::WebP];
+const COVER_RENDITIONS: [(&str, u32, u32); 2] = [("medium", 600, 900), ("thumb", 150, 225)];
+
+struct CoverUpload {
+    format: ImageFormat,
+    hash: String,
+    original: Vec<u8>,
+    renditions: Vec<(&'static str, Vec<u8>)>,
+}
+
+impl CoverUpload {
+    fn process(content_type: &str, data: &[u8]) -> anyhow::Result<Self> {
+        if data.is_empty() {
+            anyhow::bail!("cover image is empty");
+        }
+        if data.len() > COVER_SIZE_MAX {
+            anyhow::bail!("cover image exceeds {} bytes", COVER_SIZE_MAX);
+        }
+        let format = match ImageFormat::from_mime_type(content_type) {
+            Some(format) if COVER_FORMATS.contains(&format) => format,
+            _ => anyhow::bail!("unsupported cover content type: {}", content_type),
+        };
+        match image::guess_format(data) {
+            Ok(guessed) if guessed == format => {}
+            _ => anyhow::bail!("cover image does not match content type {}", content_type),
+        }
+
+        let mut limits = Limits::default();
+        limits.max_image_width = Some(COVER_DIMENSION_MAX);
+        limits.max_image_height = Some(COVER_DIMENSION_MAX);
+        limits.max_alloc = Some(COVER_ALLOC_MAX);
+        let mut reader = ImageReader::with_format(Cursor::new(data), format);
+        reader.limits(limits);
+        let image = match reader.decode() {
+            Ok(image) => image,
+            Err(e) => anyhow::bail!("invalid cover image: {}", e),
+        };
+
+        let mut renditions = Vec::new();
+        for (suffix, width, height) in COVER_RENDITIONS {
+            let rendition = match image.width() > width || image.height() > height {
+                true => image.resize(width, height, FilterType::Lanczos3),
+                false => image.clone(),
+            };
+            let mut encoded = Cursor::new(Vec::new());
+            rendition.write_to(&mut encoded, format)?;
+            renditions.push((suffix, encoded.into_inner()));
+        }
+
+        Ok(CoverUpload {
+            format,
+            hash: sha256_hex(data),
+            original: data.to_vec(),
+            renditions,
+        })
+    }
+
+    fn file_name(&self, isbn: &str) -> String {
+        match is_valid_isbn13(isbn) {
+            true => format!("{}-{}", isbn, &self.hash[..12]),
+            false => self.hash[..32].to_string(),
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        self.format
+            .extensions_str()
+            .first()
+            .copied()
+            .unwrap_or("img")
+    }
+
+    fn write(&self, dir: &Path, name: &str) -> anyhow::Result<String> {
+        let extension = self.extension();
+        fs::write(dir.join(format!("{}.{}", name, extension)), &self.original)?;
+        for (suffix, data) in &self.renditions {
+            fs::write(dir.join(format!("{}-{}.{}", name, suffix, extension)), data)?;
+        }
+        Ok(format!("{}/{}.{}", COVER_URL_PREFIX, name, extension))
+    }
+}
+
+fn is_upload_name(file_name: &str) -> bool {
+    let (name, extension) = match file_name.rsplit_once('.') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let is_hex = |s: &str| s.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'));
+    let known_extension = COVER_FORMATS
+        .iter()
+        .any(|format| format.extensions_str().first() == Some(&extension));
+    let known_name = match name.split_once('-') {
+        Some((isbn, hash)) => is_valid_isbn13(isbn) && hash.len() == 12 && is_hex(hash),
+        None => name.len() == 32 && is_hex(name),
+    };
+    known_extension && known_name
+}
+
+pub struct CoverStore {
+    dir: PathBuf,
+}
+
+impl CoverStore {
+    pub fn new(dir: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(CoverStore { dir })
+    }
+
+    pub fn from_env() -> anyhow::Result<Self> {
+        CoverStore::new(env::var(COVER_DIR_ENV).unwrap_or(DEFAULT_COVER_DIR.to_string()))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub async fn save(
+        &self,
+        isbn: &str,
+        content_type: &str,
+        data: Bytes,
+    ) -> anyhow::Result<String> {
+        let dir = self.dir.clone();
+        let isbn = isbn.to_string();
+        let content_type = content_type.to_string();
+        actix_web::web::block(move || {
+            let upload = CoverUpload::process(&content_type, &data)?;
+            upload.write(&dir, &upload.file_name(&isbn))
+        })
+        .await?
+    }
+
+    pub fn remove(&self, cover: &str) -> anyhow::Result<()> {
+        let file_name = match cover
+            .strip_prefix(COVER_URL_PREFIX)
+            .and_then(|name| name.strip_prefix('/'))
+        {
+            Some(name) if is_upload_name(name) => name,
+            _ => return Ok(()),
+        };
+        let mut files = vec![file_name.to_string()];
+        for (suffix, _, _) in COVER_RENDITIONS {
+            files.push(cover_rendition(file_name, suffix));
+        }
+        for file in files {
+            match fs::remove_file(self.dir.join(&file)) {
+                Ok(_) => log::info!("cover image {} removed", file),
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn cover_rendition(cover: &str, suffix: &str) -> String {
+    match cover.rsplit_once('.') {
+        Some((name, extension)) if !name.is_empty() && !extension.contains('/') => {
+            format!("{}-{}.{}", name, suffix, extension)
+        }
+        _ => format!("{}-{}", cover, suffix),
+    }
+}
@@ -0,0 +1,16 @@
+use mysql_async::OptsBuilder;
+use std::env;
+
+pub fn database_opts_from_env() -> OptsBuilder {
+    OptsBuilder::default()
+        .db_name(env::var("SQL_DB").ok())
+        .user(env::var("SQL_USER").ok())
+        .pass(env::var("SQL_PWD").ok())
+        .ip_or_hostname(env::var("SQL_HOSTNAME").unwrap_or("localhost".to_string()))
+        .tcp_port(
+            env::var("SQL_PORT")
+                .unwrap_or("3306".to_string())
+                .parse()
+                .unwrap_or(3306),
+        )
+}
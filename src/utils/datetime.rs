@@ -0,0 +1,11 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+pub fn parse_datetime(value: &str) -> anyhow::Result<NaiveDateTime> {
+    match NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
+        Ok(datetime) => Ok(datetime),
+        Err(_) => match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            Ok(date) => Ok(date.and_time(Default::default())),
+            Err(_) => anyhow::bail!("invalid datetime {}, expected YYYY-MM-DD [HH:MM:SS]", value),
+        },
+    }
+}
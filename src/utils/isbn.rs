@@ -0,0 +1,275 @@
+type IsbnRange = (u32, u32, usize);
+
+const ISBN_RANGES: [(&str, &str, &[IsbnRange]); 6] = [
+    (
+        "978",
+        "0",
+        &[
+            (0, 1999999, 2),
+            (2000000, 2279999, 3),
+            (2280000, 2289999, 4),
+            (2290000, 6479999, 3),
+            (6480000, 6489999, 7),
+            (6490000, 6999999, 3),
+            (7000000, 8499999, 4),
+            (8500000, 8999999, 5),
+            (9000000, 9499999, 6),
+            (9500000, 9999999, 7),
+        ],
+    ),
+    (
+        "978",
+        "1",
+        &[
+            (0, 999999, 2),
+            (1000000, 3999999, 3),
+            (4000000, 5499999, 4),
+            (5500000, 8697999, 5),
+            (8698000, 9989999, 6),
+            (9990000, 9999999, 7),
+        ],
+    ),
+    (
+        "978",
+        "2",
+        &[
+            (0, 1999999, 2),
+            (2000000, 3499999, 3),
+            (3500000, 3999999, 5),
+            (4000000, 6999999, 3),
+            (7000000, 8399999, 4),
+            (8400000, 8999999, 5),
+            (9000000, 9499999, 6),
+            (9500000, 9999999, 7),
+        ],
+    ),
+    (
+        "978",
+        "3",
+        &[
+            (0, 299999, 2),
+            (300000, 339999, 3),
+            (340000, 369999, 4),
+            (370000, 399999, 5),
+            (400000, 1999999, 2),
+            (2000000, 6999999, 3),
+            (7000000, 8499999, 4),
+            (8500000, 8999999, 5),
+            (9000000, 9499999, 6),
+            (9500000, 9539999, 7),
+            (9540000, 9699999, 5),
+            (9700000, 9849999, 7),
+            (9850000, 9999999, 5),
+        ],
+    ),
+    (
+        "978",
+        "4",
+        &[
+            (0, 1999999, 2),
+            (2000000, 6999999, 3),
+            (7000000, 8499999, 4),
+            (8500000, 8999999, 5),
+            (9000000, 9499999, 6),
+            (9500000, 9999999, 7),
+        ],
+    ),
+    (
+        "978",
+        "7",
+        &[
+            (0, 999999, 2),
+            (1000000, 4999999, 3),
+            (5000000, 7999999, 4),
+            (8000000, 8999999, 5),
+            (9000000, 9999999, 6),
+        ],
+    ),
+];
+
+fn digits(isbn: &str) -> Vec<u32> {
+    isbn.chars()
+        .map(|c| match c {
+            'X' => 10,
+            c => c.to_digit(10).unwrap_or(u32::MAX),
+        })
+        .collect()
+}
+
+fn isbn10_check_digit(digits: &[u32]) -> char {
+    let sum: u32 = digits
+        .iter()
+        .take(9)
+        .enumerate()
+        .map(|(i, d)| (10 - i as u32) * d)
+        .sum();
+    match (11 - sum % 11) % 11 {
+        10 => 'X',
+        d => char::from_digit(d, 10).unwrap_or('0'),
+    }
+}
+
+fn isbn13_check_digit(digits: &[u32]) -> char {
+    let sum: u32 = digits
+        .iter()
+        .take(12)
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { *d } else { 3 * d })
+        .sum();
+    char::from_digit((10 - sum % 10) % 10, 10).unwrap_or('0')
+}
+
+pub fn strip_isbn(isbn: &str) -> String {
+    isbn.chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+pub fn is_valid_isbn10(isbn: &str) -> bool {
+    let isbn = strip_isbn(isbn);
+    let digits = digits(&isbn);
+    digits.len() == 10
+        && digits[..9].iter().all(|d| *d < 10)
+        && digits[9] <= 10
+        && isbn.ends_with(isbn10_check_digit(&digits))
+}
+
+pub fn is_valid_isbn13(isbn: &str) -> bool {
+    let isbn = strip_isbn(isbn);
+    let digits = digits(&isbn);
+    digits.len() == 13
+        && digits.iter().all(|d| *d < 10)
+        && (isbn.starts_with("978") || isbn.starts_with("979"))
+        && isbn.ends_with(isbn13_check_digit(&digits))
+}
+
+pub fn isbn10_to_isbn13(isbn: &str) -> anyhow::Result<String> {
+    if !is_valid_isbn10(isbn) {
+        anyhow::bail!("invalid isbn-10: {}", isbn);
+    }
+    let body = format!("978{}", &strip_isbn(isbn)[..9]);
+    Ok(format!("{}{}", body, isbn13_check_digit(&digits(&body))))
+}
+
+pub fn isbn13_to_isbn10(isbn: &str) -> anyhow::Result<String> {
+    if !is_valid_isbn13(isbn) {
+        anyhow::bail!("invalid isbn-13: {}", isbn);
+    }
+    let isbn = strip_isbn(isbn);
+    if !isbn.starts_with("978") {
+        anyhow::bail!("isbn {} has no isbn-10 form", isbn);
+    }
+    let body = &isbn[3..12];
+    Ok(format!("{}{}", body, isbn10_check_digit(&digits(body))))
+}
+
+pub fn normalize_isbn(isbn: &str) -> anyhow::Result<String> {
+    match strip_isbn(isbn).len() {
+        10 => isbn10_to_isbn13(isbn),
+        13 if is_valid_isbn13(isbn) => Ok(strip_isbn(isbn)),
+        _ => anyhow::bail!("invalid isbn: {}", isbn),
+    }
+}
+
+pub fn format_isbn13(isbn: &str) -> Option<String> {
+    let isbn = strip_isbn(isbn);
+    if !is_valid_isbn13(&isbn) {
+        return None;
+    }
+    for (prefix, group, ranges) in ISBN_RANGES {
+        let rest = match isbn.strip_prefix(prefix) {
+            Some(rest) => rest,
+            None => continue,
+        };
+        let rest = match rest.strip_prefix(group) {
+            Some(rest) => &rest[..rest.len() - 1],
+            None => continue,
+        };
+        let value: u32 = format!("{:0<7}", rest)[..7].parse().unwrap_or(0);
+        if let Some((_, _, len)) = ranges
+            .iter()
+            .find(|(low, high, _)| *low <= value && value <= *high)
+        {
+            if *len < rest.len() {
+                return Some(format!(
+                    "{}-{}-{}-{}-{}",
+                    prefix,
+                    group,
+                    &rest[..*len],
+                    &rest[*len..],
+                    &isbn[12..]
+                ));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn isbn10_with_x_check_digit() {
+        assert!(is_valid_isbn10("080442957X"));
+        assert!(is_valid_isbn10("0-8044-2957-x"));
+        assert!(!is_valid_isbn10("0804429579"));
+        assert!(!is_valid_isbn10("08044295X7"));
+    }
+
+    #[test]
+    fn isbn13_check_digit() {
+        assert!(is_valid_isbn13("978-0-306-40615-7"));
+        assert!(!is_valid_isbn13("9780306406158"));
+        assert!(!is_valid_isbn13("9770306406157"));
+    }
+
+    #[test]
+    fn converts_between_isbn10_and_isbn13() {
+        assert_eq!(isbn10_to_isbn13("080442957X").unwrap(), "9780804429573");
+        assert_eq!(isbn13_to_isbn10("9780804429573").unwrap(), "080442957X");
+        assert_eq!(isbn13_to_isbn10("9780306406157").unwrap(), "0306406152");
+        assert!(isbn10_to_isbn13("0804429579").is_err());
+    }
+
+    #[test]
+    fn isbn979_has_no_isbn10_form() {
+        assert!(is_valid_isbn13("9791090636071"));
+        assert!(isbn13_to_isbn10("9791090636071").is_err());
+    }
+
+    #[test]
+    fn normalizes_to_isbn13() {
+        assert_eq!(normalize_isbn("0-8044-2957-X").unwrap(), "9780804429573");
+        assert_eq!(
+            normalize_isbn("978 0 306 40615 7").unwrap(),
+            "9780306406157"
+        );
+        assert_eq!(normalize_isbn("9791090636071").unwrap(), "9791090636071");
+        assert!(normalize_isbn("9780306406158").is_err());
+        assert!(normalize_isbn("12345").is_err());
+    }
+
+    #[test]
+    fn formats_each_range_group() {
+        let cases = [
+            ("9780306406157", "978-0-306-40615-7"),
+            ("9781509972913", "978-1-5099-7291-3"),
+            ("9782070408504", "978-2-07-040850-4"),
+            ("9783161484100", "978-3-16-148410-0"),
+            ("9784101010014", "978-4-10-101001-4"),
+            ("9787020002207", "978-7-02-000220-7"),
+        ];
+        for (isbn, formatted) in cases {
+            assert_eq!(format_isbn13(isbn).as_deref(), Some(formatted));
+        }
+    }
+
+    #[test]
+    fn format_without_matching_range() {
+        assert_eq!(format_isbn13("9785170900145"), None);
+        assert_eq!(format_isbn13("9791090636071"), None);
+        assert_eq!(format_isbn13("9780306406158"), None);
+    }
+}
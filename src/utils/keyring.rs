@@ -0,0 +1,209 @@
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::{env, fs};
+
+const KEY_LEN: usize = 32;
+const KEYS_ENV: &str = "BOOKSTORE_KEYS";
+const KEY_FILE_ENV: &str = "BOOKSTORE_KEY_FILE";
+
+static KEY_RING: OnceLock<KeyRing> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum KeyPurpose {
+    Token,
+    Password,
+    AdminPassword,
+    Mfa,
+}
+
+impl FromStr for KeyPurpose {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "token" => Ok(KeyPurpose::Token),
+            "password" => Ok(KeyPurpose::Password),
+            "admin_password" => Ok(KeyPurpose::AdminPassword),
+            "mfa" => Ok(KeyPurpose::Mfa),
+            _ => anyhow::bail!("invalid key purpose: {}", s),
+        }
+    }
+}
+
+impl Display for KeyPurpose {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyPurpose::Token => write!(f, "token"),
+            KeyPurpose::Password => write!(f, "password"),
+            KeyPurpose::AdminPassword => write!(f, "admin_password"),
+            KeyPurpose::Mfa => write!(f, "mfa"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum KeyStatus {
+    Active,
+    Retired(DateTime<Utc>),
+}
+
+pub struct Key {
+    pub id: String,
+    pub purpose: KeyPurpose,
+    pub status: KeyStatus,
+    material: [u8; KEY_LEN],
+}
+
+impl Key {
+    pub fn material(&self) -> &[u8; KEY_LEN] {
+        &self.material
+    }
+
+    pub fn is_usable(&self, now: DateTime<Utc>) -> bool {
+        match self.status {
+            KeyStatus::Active => true,
+            KeyStatus::Retired(until) => now < until,
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s.split_whitespace().collect::<Vec<_>>();
+        let (purpose, id, material, status) = match fields.as_slice() {
+            [purpose, id, material] => (purpose, id, material, KeyStatus::Active),
+            [purpose, id, material, "active"] => (purpose, id, material, KeyStatus::Active),
+            [purpose, id, material, until] => {
+                let until = DateTime::parse_from_rfc3339(until)?;
+                (purpose, id, material, KeyStatus::Retired(until.into()))
+            }
+            _ => anyhow::bail!(
+                "invalid key entry: expected `<purpose> <id> <key> [active|<rfc3339>]`"
+            ),
+        };
+
+        if id.is_empty() || id.contains('.') {
+            anyhow::bail!("invalid key id: {}", id);
+        }
+
+        let material = BASE64_STANDARD.decode(material)?;
+        if material.len() != KEY_LEN {
+            anyhow::bail!(
+                "key {} must be {} bytes, got {}",
+                id,
+                KEY_LEN,
+                material.len()
+            );
+        }
+        let mut buffer = [0u8; KEY_LEN];
+        buffer.copy_from_slice(&material);
+
+        Ok(Key {
+            id: id.to_string(),
+            purpose: KeyPurpose::from_str(purpose)?,
+            status,
+            material: buffer,
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct KeyRing {
+    keys: Vec<Key>,
+}
+
+impl KeyRing {
+    pub fn from_env() -> anyhow::Result<KeyRing> {
+        let mut source = String::new();
+        if let Ok(path) = env::var(KEY_FILE_ENV) {
+            source.push_str(&fs::read_to_string(&path)?);
+            source.push('\n');
+        }
+        if let Ok(keys) = env::var(KEYS_ENV) {
+            source.push_str(&keys);
+        }
+        if source.trim().is_empty() {
+            anyhow::bail!(
+                "no keys configured, set {} or {} (see bookstore.keys.example)",
+                KEYS_ENV,
+                KEY_FILE_ENV
+            );
+        }
+        KeyRing::parse(&source)
+    }
+
+    pub fn parse(source: &str) -> anyhow::Result<KeyRing> {
+        let mut key_ring = KeyRing::default();
+        for entry in source.split(['\n', ';']) {
+            let entry = entry.trim();
+            if entry.is_empty() || entry.starts_with('#') {
+                continue;
+            }
+            key_ring.add(entry.parse()?)?;
+        }
+
+        if key_ring.active(KeyPurpose::Token).is_none() {
+            anyhow::bail!("an active token key is required");
+        }
+        if key_ring.active(KeyPurpose::Mfa).is_none() {
+            anyhow::bail!("an active mfa key is required");
+        }
+        Ok(key_ring)
+    }
+
+    pub fn add(&mut self, key: Key) -> anyhow::Result<()> {
+        if self
+            .keys
+            .iter()
+            .any(|k| k.purpose == key.purpose && k.id == key.id)
+        {
+            anyhow::bail!("duplicate {} key: {}", key.purpose, key.id);
+        }
+        if key.status == KeyStatus::Active && self.active(key.purpose).is_some() {
+            anyhow::bail!("more than one active {} key", key.purpose);
+        }
+        self.keys.push(key);
+        Ok(())
+    }
+
+    pub fn install(self) -> anyhow::Result<()> {
+        match KEY_RING.set(self) {
+            Ok(_) => Ok(()),
+            Err(_) => anyhow::bail!("key ring already installed"),
+        }
+    }
+
+    pub fn global() -> anyhow::Result<&'static KeyRing> {
+        match KEY_RING.get() {
+            Some(key_ring) => Ok(key_ring),
+            None => anyhow::bail!("key ring not installed"),
+        }
+    }
+
+    pub fn active(&self, purpose: KeyPurpose) -> Option<&Key> {
+        self.keys
+            .iter()
+            .find(|k| k.purpose == purpose && k.status == KeyStatus::Active)
+    }
+
+    pub fn find(&self, purpose: KeyPurpose, id: &str) -> Option<&Key> {
+        let now = Utc::now();
+        self.keys
+            .iter()
+            .find(|k| k.purpose == purpose && k.id == id && k.is_usable(now))
+    }
+
+    pub fn usable(&self, purpose: KeyPurpose) -> Vec<&Key> {
+        let now = Utc::now();
+        self.keys
+            .iter()
+            .filter(|k| k.purpose == purpose && k.is_usable(now))
+            .collect()
+    }
+}
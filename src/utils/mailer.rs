@@ -0,0 +1,49 @@
+use chrono::Utc;
+use rand::Rng;
+use std::path::PathBuf;
+use std::{env, fs};
+
+const OUTBOX_DIR_ENV: &str = "BOOKSTORE_OUTBOX_DIR";
+const DEFAULT_OUTBOX_DIR: &str = "outbox";
+
+pub trait Mailer: Send + Sync {
+    fn send(&self, to: &str, subject: &str, body: &str) -> anyhow::Result<()>;
+}
+
+pub struct FileOutbox {
+    dir: PathBuf,
+}
+
+impl FileOutbox {
+    pub fn new(dir: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(FileOutbox { dir })
+    }
+
+    pub fn from_env() -> anyhow::Result<Self> {
+        FileOutbox::new(env::var(OUTBOX_DIR_ENV).unwrap_or(DEFAULT_OUTBOX_DIR.to_string()))
+    }
+}
+
+impl Mailer for FileOutbox {
+    fn send(&self, to: &str, subject: &str, body: &str) -> anyhow::Result<()> {
+        let now = Utc::now();
+        let id: u32 = rand::thread_rng().gen();
+        let path = self.dir.join(format!(
+            "{}-{:08x}.eml",
+            now.format("%Y%m%dT%H%M%S%.3fZ"),
+            id
+        ));
+        let message = format!(
+            "Date: {}\r\nTo: {}\r\nSubject: {}\r\n\r\n{}\r\n",
+            now.to_rfc2822(),
+            to,
+            subject,
+            body
+        );
+        fs::write(&path, message)?;
+        log::info!("mail to {} written to {}", to, path.display());
+        Ok(())
+    }
+}
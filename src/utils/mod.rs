@@ -1,5 +1,32 @@
+mod cover;
+mod database;
+mod datetime;
+mod isbn;
+mod keyring;
+mod mailer;
 mod password;
+mod search;
+mod secret;
 mod token;
+mod totp;
 
-pub use password::{encrypt_admin_password, encrypt_password};
-pub use token::{decrypt_token, generate_token, validate_token, Token};
+pub use cover::{cover_rendition, CoverStore, COVER_SIZE_MAX, COVER_URL_PREFIX};
+pub use database::database_opts_from_env;
+pub use datetime::parse_datetime;
+pub use isbn::{
+    format_isbn13, is_valid_isbn10, is_valid_isbn13, isbn10_to_isbn13, isbn13_to_isbn10,
+    normalize_isbn, strip_isbn,
+};
+pub use keyring::{Key, KeyPurpose, KeyRing, KeyStatus};
+pub use mailer::{FileOutbox, Mailer};
+pub use password::{
+    hash_password, verify_admin_password, verify_dummy_password, verify_password,
+    PasswordVerification,
+};
+pub use search::{boolean_term, SearchQuery};
+pub use secret::{generate_api_key, generate_secret, hash_secret, sha256_hex, API_KEY_PREFIX};
+pub use token::{decrypt_token, generate_token, validate_token, Audience, Subject, Token};
+pub use totp::{
+    base32_decode, base32_encode, generate_recovery_codes, generate_totp_secret,
+    normalize_recovery_code, open_totp_secret, seal_totp_secret, totp_code, totp_uri, verify_totp,
+};
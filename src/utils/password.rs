@@ -1,29 +1,119 @@
-use crate::{AES_ADMIN_PWD_KEY, AES_PWD_KEY};
+use crate::utils::{KeyPurpose, KeyRing};
 use base64::prelude::BASE64_STANDARD;
 use base64::Engine;
-use ring::aead;
+use rand::Rng;
+use ring::{aead, constant_time, pbkdf2};
+use std::num::NonZeroU32;
 
-pub async fn encrypt_password(password: &str) -> anyhow::Result<String> {
-    let key = aead::UnboundKey::new(&aead::AES_256_GCM, AES_PWD_KEY).unwrap();
-    let sealing_key = aead::LessSafeKey::new(key);
-    let nonce = aead::Nonce::assume_unique_for_key([0; 12]);
+const PASSWORD_SCHEME: &str = "pbkdf2-sha256";
+const PASSWORD_ITERATIONS: u32 = 600_000;
+const PASSWORD_SALT_LEN: usize = 16;
+const PASSWORD_HASH_LEN: usize = 32;
+const DUMMY_PASSWORD_HASH: &str =
+    "pbkdf2-sha256$600000$X2WlmUqaHxlvRzyLpTXtkg==$LIeZlojrQi25yX5ON8wEkHHGcQczyZpmEDNnjGEqDlI=";
 
-    let mut password = password.to_string();
-    let mut password = unsafe { password.as_bytes_mut() };
+#[derive(Debug, Eq, PartialEq)]
+pub enum PasswordVerification {
+    Valid,
+    Outdated,
+    Invalid,
+}
 
-    match sealing_key.seal_in_place_separate_tag(nonce, aead::Aad::empty(), &mut password) {
-        Ok(_) => Ok(BASE64_STANDARD.encode(&password)),
-        Err(_) => Err(anyhow::anyhow!("failed to encrypt password")),
+pub async fn hash_password(password: &str) -> anyhow::Result<String> {
+    let mut salt = [0u8; PASSWORD_SALT_LEN];
+    rand::thread_rng().fill(&mut salt);
+
+    let password = password.to_string();
+    let hash = actix_web::web::block(move || {
+        let mut hash = [0u8; PASSWORD_HASH_LEN];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            NonZeroU32::new(PASSWORD_ITERATIONS).unwrap(),
+            &salt,
+            password.as_bytes(),
+            &mut hash,
+        );
+        hash
+    })
+    .await?;
+
+    Ok(format!(
+        "{}${}${}${}",
+        PASSWORD_SCHEME,
+        PASSWORD_ITERATIONS,
+        BASE64_STANDARD.encode(salt),
+        BASE64_STANDARD.encode(hash)
+    ))
+}
+
+pub async fn verify_password(password: &str, stored: &str) -> anyhow::Result<PasswordVerification> {
+    verify(password, stored, KeyPurpose::Password).await
+}
+
+pub async fn verify_admin_password(
+    password: &str,
+    stored: &str,
+) -> anyhow::Result<PasswordVerification> {
+    verify(password, stored, KeyPurpose::AdminPassword).await
+}
+
+pub async fn verify_dummy_password(password: &str) -> anyhow::Result<()> {
+    verify(password, DUMMY_PASSWORD_HASH, KeyPurpose::Password).await?;
+    Ok(())
+}
+
+async fn verify(
+    password: &str,
+    stored: &str,
+    legacy_purpose: KeyPurpose,
+) -> anyhow::Result<PasswordVerification> {
+    let mut parts = stored.split('$');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(PASSWORD_SCHEME), Some(iterations), Some(salt), Some(hash)) => {
+            let iterations: u32 = iterations.parse()?;
+            let salt = BASE64_STANDARD.decode(salt)?;
+            let hash = BASE64_STANDARD.decode(hash)?;
+            let password = password.to_string();
+
+            let valid = actix_web::web::block(move || match NonZeroU32::new(iterations) {
+                Some(iterations) => pbkdf2::verify(
+                    pbkdf2::PBKDF2_HMAC_SHA256,
+                    iterations,
+                    &salt,
+                    password.as_bytes(),
+                    &hash,
+                )
+                .is_ok(),
+                None => false,
+            })
+            .await?;
+
+            match (valid, iterations < PASSWORD_ITERATIONS) {
+                (true, false) => Ok(PasswordVerification::Valid),
+                (true, true) => Ok(PasswordVerification::Outdated),
+                (false, _) => Ok(PasswordVerification::Invalid),
+            }
+        }
+        _ => {
+            for key in KeyRing::global()?.usable(legacy_purpose) {
+                let legacy = legacy_encrypt(password, key.material())?;
+                if constant_time::verify_slices_are_equal(legacy.as_bytes(), stored.as_bytes())
+                    .is_ok()
+                {
+                    return Ok(PasswordVerification::Outdated);
+                }
+            }
+            Ok(PasswordVerification::Invalid)
+        }
     }
 }
 
-pub async fn encrypt_admin_password(password: &str) -> anyhow::Result<String> {
-    let key = aead::UnboundKey::new(&aead::AES_256_GCM, AES_ADMIN_PWD_KEY).unwrap();
+fn legacy_encrypt(password: &str, key: &[u8; 32]) -> anyhow::Result<String> {
+    let key = aead::UnboundKey::new(&aead::AES_256_GCM, key).unwrap();
     let sealing_key = aead::LessSafeKey::new(key);
     let nonce = aead::Nonce::assume_unique_for_key([0; 12]);
 
-    let mut password = password.to_string();
-    let mut password = unsafe { password.as_bytes_mut() };
+    let mut password = password.as_bytes().to_vec();
 
     match sealing_key.seal_in_place_separate_tag(nonce, aead::Aad::empty(), &mut password) {
         Ok(_) => Ok(BASE64_STANDARD.encode(&password)),
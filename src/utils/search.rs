@@ -0,0 +1,67 @@
+#[derive(Debug, Default)]
+pub struct SearchQuery {
+    pub required: Vec<String>,
+    pub excluded: Vec<String>,
+    pub optional: Vec<String>,
+}
+
+impl SearchQuery {
+    pub fn parse(query: &str) -> SearchQuery {
+        let mut search = SearchQuery::default();
+        let mut chars = query.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c.is_whitespace() {
+                continue;
+            }
+            let (operator, first) = match c {
+                '+' | '-' => (Some(c), chars.next()),
+                _ => (None, Some(c)),
+            };
+            let mut term = String::new();
+            match first {
+                Some('"') => {
+                    for c in chars.by_ref() {
+                        if c == '"' {
+                            break;
+                        }
+                        term.push(c);
+                    }
+                }
+                Some(c) if !c.is_whitespace() => {
+                    term.push(c);
+                    while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                        term.push(c);
+                    }
+                }
+                _ => {}
+            }
+            let term = term.split_whitespace().collect::<Vec<_>>().join(" ");
+            if term.is_empty() {
+                continue;
+            }
+            match operator {
+                Some('+') => search.required.push(term),
+                Some('-') => search.excluded.push(term),
+                _ => search.optional.push(term),
+            }
+        }
+        search
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.required.is_empty() && self.excluded.is_empty() && self.optional.is_empty()
+    }
+
+    pub fn positive_terms(&self) -> String {
+        self.required
+            .iter()
+            .chain(self.optional.iter())
+            .map(|term| boolean_term(term))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+pub fn boolean_term(term: &str) -> String {
+    format!("\"{}\"", term.replace('"', " "))
+}
@@ -0,0 +1,29 @@
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::Rng;
+use ring::digest;
+
+const SECRET_LEN: usize = 32;
+pub const API_KEY_PREFIX: &str = "bsk_";
+
+pub fn generate_secret() -> String {
+    let mut secret = [0u8; SECRET_LEN];
+    rand::thread_rng().fill(&mut secret);
+    BASE64_URL_SAFE_NO_PAD.encode(secret)
+}
+
+pub fn generate_api_key() -> String {
+    format!("{}{}", API_KEY_PREFIX, generate_secret())
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    digest::digest(&digest::SHA256, data)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+pub fn hash_secret(secret: &str) -> String {
+    sha256_hex(secret.as_bytes())
+}
@@ -1,13 +1,17 @@
-use crate::{AES_TOKEN_KEY, COMPANY_NAME};
+use crate::utils::{KeyPurpose, KeyRing};
+use crate::COMPANY_NAME;
 use base64::prelude::BASE64_STANDARD;
 use base64::Engine;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Utc};
 use rand::Rng;
 use ring::aead;
 use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
 use std::ops::RangeFrom;
+use std::str::FromStr;
 
 const TOKEN_VALIDITY: i64 = 60 * 60 * 24;
+const ACCESS_TOKEN_VALIDITY: i64 = 60 * 15;
 
 pub struct Token {
     pub token: String,
@@ -15,35 +19,143 @@ pub struct Token {
     pub nonce: String,
 }
 
-pub async fn generate_token(username: &str) -> anyhow::Result<Token> {
-    let key = aead::UnboundKey::new(&aead::AES_256_GCM, AES_TOKEN_KEY).unwrap();
+impl FromStr for Token {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.rsplitn(3, '.');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(nonce), Some(tag), Some(token)) => Ok(Token {
+                token: token.to_string(),
+                tag: tag.to_string(),
+                nonce: nonce.to_string(),
+            }),
+            _ => anyhow::bail!("invalid token"),
+        }
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.token, self.tag, self.nonce)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Audience {
+    Customer,
+    Admin,
+}
+
+impl FromStr for Audience {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "customer" => Ok(Audience::Customer),
+            "admin" => Ok(Audience::Admin),
+            _ => anyhow::bail!("invalid token audience: {}", s),
+        }
+    }
+}
+
+impl Audience {
+    pub fn validity(&self) -> i64 {
+        match self {
+            Audience::Customer => ACCESS_TOKEN_VALIDITY,
+            Audience::Admin => TOKEN_VALIDITY,
+        }
+    }
+}
+
+impl Display for Audience {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Audience::Customer => write!(f, "customer"),
+            Audience::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Subject {
+    pub audience: Audience,
+    pub id: u32,
+}
+
+impl Subject {
+    pub fn customer(id: u32) -> Self {
+        Subject {
+            audience: Audience::Customer,
+            id,
+        }
+    }
+
+    pub fn admin(id: u32) -> Self {
+        Subject {
+            audience: Audience::Admin,
+            id,
+        }
+    }
+}
+
+impl FromStr for Subject {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((audience, id)) => Ok(Subject {
+                audience: audience.parse()?,
+                id: id.parse()?,
+            }),
+            None => anyhow::bail!("invalid token subject: {}", s),
+        }
+    }
+}
+
+impl Display for Subject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.audience, self.id)
+    }
+}
+
+pub async fn generate_token(subject: Subject) -> anyhow::Result<Token> {
+    let token_key = match KeyRing::global()?.active(KeyPurpose::Token) {
+        Some(key) => key,
+        None => anyhow::bail!("no active token key"),
+    };
+    let key = aead::UnboundKey::new(&aead::AES_256_GCM, token_key.material()).unwrap();
     let sealing_key = aead::LessSafeKey::new(key);
 
     let date = Utc::now();
 
-    let date = date + chrono::Duration::seconds(TOKEN_VALIDITY);
+    let date = date + chrono::Duration::seconds(subject.audience.validity());
 
-    let date = date.to_rfc3339();
+    let date = date.to_rfc3339_opts(SecondsFormat::Secs, true);
 
     let date = date.as_bytes();
 
     let date_buffer = &mut [0u8; 32];
     date_buffer[..date.len()].copy_from_slice(date);
 
-    let username = username.as_bytes();
+    let subject = subject.to_string();
 
     let company_name = COMPANY_NAME.as_slice();
 
-    let mut raw_token = [date_buffer, company_name, username].concat();
+    let mut raw_token = [date_buffer, company_name, subject.as_bytes()].concat();
 
     let mut rg = rand::thread_rng();
     let mut nonce_buffer = [0u8; 12];
     rg.fill(&mut nonce_buffer);
     let nonce = aead::Nonce::assume_unique_for_key(nonce_buffer);
 
-    match sealing_key.seal_in_place_separate_tag(nonce, aead::Aad::empty(), &mut raw_token) {
+    match sealing_key.seal_in_place_separate_tag(
+        nonce,
+        aead::Aad::from(token_key.id.as_bytes()),
+        &mut raw_token,
+    ) {
         Ok(tag) => Ok(Token {
-            token: BASE64_STANDARD.encode(&raw_token),
+            token: format!("{}.{}", token_key.id, BASE64_STANDARD.encode(&raw_token)),
             tag: BASE64_STANDARD.encode(tag.as_ref()),
             nonce: BASE64_STANDARD.encode(nonce_buffer),
         }),
@@ -51,23 +163,30 @@ pub async fn generate_token(username: &str) -> anyhow::Result<Token> {
     }
 }
 
-pub async fn decrypt_token(token: &Token) -> anyhow::Result<(String, DateTime<Utc>)> {
+pub async fn decrypt_token(token: &Token) -> anyhow::Result<(Subject, DateTime<Utc>)> {
     let tag = &token.tag;
     let nonce = &token.nonce;
-    let token = &token.token;
-    let key = aead::UnboundKey::new(&aead::AES_256_GCM, AES_TOKEN_KEY).unwrap();
+    let (key_id, token) = match token.token.split_once('.') {
+        Some(parts) => parts,
+        None => anyhow::bail!("invalid token"),
+    };
+    let token_key = match KeyRing::global()?.find(KeyPurpose::Token, key_id) {
+        Some(key) => key,
+        None => anyhow::bail!("invalid token: unknown or expired key {}", key_id),
+    };
+    let key = aead::UnboundKey::new(&aead::AES_256_GCM, token_key.material()).unwrap();
     let opening_key = aead::LessSafeKey::new(key);
 
     let mut token = BASE64_STANDARD.decode(token)?;
     let tag = BASE64_STANDARD.decode(tag)?;
-    if token.len() < 16 {
+    if token.len() < 41 || tag.len() != 16 {
         anyhow::bail!("invalid token");
     }
     let mut tag_buffer = [0u8; 16];
     tag_buffer.copy_from_slice(tag.as_slice());
     let tag = aead::Tag::from(tag_buffer);
     let nonce = BASE64_STANDARD.decode(nonce)?;
-    if nonce.len() < 12 {
+    if nonce.len() != 12 {
         anyhow::bail!("invalid token");
     }
     let mut nonce_buffer = [0u8; 12];
@@ -76,27 +195,34 @@ pub async fn decrypt_token(token: &Token) -> anyhow::Result<(String, DateTime<Ut
 
     match opening_key.open_in_place_separate_tag(
         nonce,
-        aead::Aad::empty(),
+        aead::Aad::from(key_id.as_bytes()),
         tag,
         &mut token,
         RangeFrom { start: 0 },
     ) {
         Ok(token) => {
-            let token = String::from_utf8_lossy(&token).to_string();
-            let date = DateTime::parse_from_rfc3339(&token[..32])?;
+            let date = String::from_utf8_lossy(&token[..32]);
+            let date = DateTime::parse_from_rfc3339(date.trim_end_matches('\0'))?;
+            if &token[32..41] != COMPANY_NAME.as_slice() {
+                anyhow::bail!("invalid token");
+            }
+            let subject = String::from_utf8_lossy(&token[41..]).parse()?;
 
-            Ok((token[41..].to_string(), DateTime::from(date)))
+            Ok((subject, DateTime::from(date)))
         }
         Err(e) => anyhow::bail!("invalid token : {}", e),
     }
 }
 
-pub async fn validate_token(token: &Token) -> anyhow::Result<String> {
+pub async fn validate_token(token: &Token, audience: Audience) -> anyhow::Result<u32> {
     match decrypt_token(token).await {
-        Ok((username, date)) => {
+        Ok((subject, _)) if subject.audience != audience => {
+            anyhow::bail!("invalid token: issued for {}", subject.audience)
+        }
+        Ok((subject, date)) => {
             let now = Utc::now();
             match now.timestamp().cmp(&date.timestamp()) {
-                Ordering::Less => Ok(username),
+                Ordering::Less => Ok(subject.id),
                 _ => anyhow::bail!("token expired"),
             }
         }
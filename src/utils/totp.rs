@@ -0,0 +1,264 @@
+use crate::utils::{KeyPurpose, KeyRing};
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use chrono::Utc;
+use rand::Rng;
+use ring::{aead, constant_time, hmac};
+
+const TOTP_PERIOD: i64 = 30;
+const TOTP_DIGITS: u32 = 6;
+const TOTP_SKEW: i64 = 1;
+const TOTP_SECRET_LEN: usize = 20;
+const TOTP_ISSUER: &str = "bookstore";
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_LEN: usize = 10;
+
+pub fn generate_totp_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; TOTP_SECRET_LEN];
+    rand::thread_rng().fill(secret.as_mut_slice());
+    secret
+}
+
+pub fn base32_encode(data: &[u8]) -> String {
+    let mut encoded = String::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in data {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    encoded
+}
+
+pub fn base32_decode(data: &str) -> anyhow::Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in data.trim_end_matches('=').bytes() {
+        let value = match BASE32_ALPHABET
+            .iter()
+            .position(|a| *a == c.to_ascii_uppercase())
+        {
+            Some(value) => value as u32,
+            None => anyhow::bail!("invalid base32 character: {}", c as char),
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(decoded)
+}
+
+pub fn totp_code(secret: &[u8], step: i64) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, secret);
+    let digest = hmac::sign(&key, &step.to_be_bytes());
+    let digest = digest.as_ref();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(TOTP_DIGITS),
+        width = TOTP_DIGITS as usize
+    )
+}
+
+pub fn verify_totp(secret: &[u8], code: &str, last_step: Option<i64>) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != TOTP_DIGITS as usize {
+        return None;
+    }
+    let current = Utc::now().timestamp() / TOTP_PERIOD;
+    (current - TOTP_SKEW..=current + TOTP_SKEW)
+        .filter(|step| last_step.is_none_or(|last_step| *step > last_step))
+        .find(|step| {
+            constant_time::verify_slices_are_equal(
+                totp_code(secret, *step).as_bytes(),
+                code.as_bytes(),
+            )
+            .is_ok()
+        })
+}
+
+pub fn totp_uri(account: &str, secret: &[u8]) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        TOTP_ISSUER,
+        percent_encode(account),
+        base32_encode(secret),
+        TOTP_ISSUER,
+        TOTP_DIGITS,
+        TOTP_PERIOD
+    )
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+pub fn generate_recovery_codes() -> Vec<String> {
+    let mut rg = rand::thread_rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let code = (0..RECOVERY_CODE_LEN)
+                .map(|_| BASE32_ALPHABET[rg.gen_range(0..32)].to_ascii_lowercase() as char)
+                .collect::<String>();
+            format!(
+                "{}-{}",
+                &code[..RECOVERY_CODE_LEN / 2],
+                &code[RECOVERY_CODE_LEN / 2..]
+            )
+        })
+        .collect()
+}
+
+pub fn normalize_recovery_code(code: &str) -> String {
+    let code = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect::<String>();
+    match code.len() {
+        RECOVERY_CODE_LEN => format!(
+            "{}-{}",
+            &code[..RECOVERY_CODE_LEN / 2],
+            &code[RECOVERY_CODE_LEN / 2..]
+        ),
+        _ => code,
+    }
+}
+
+pub fn seal_totp_secret(secret: &[u8]) -> anyhow::Result<String> {
+    let mfa_key = match KeyRing::global()?.active(KeyPurpose::Mfa) {
+        Some(key) => key,
+        None => anyhow::bail!("no active mfa key"),
+    };
+    let key = aead::UnboundKey::new(&aead::AES_256_GCM, mfa_key.material()).unwrap();
+    let sealing_key = aead::LessSafeKey::new(key);
+
+    let mut nonce_buffer = [0u8; 12];
+    rand::thread_rng().fill(&mut nonce_buffer);
+    let nonce = aead::Nonce::assume_unique_for_key(nonce_buffer);
+
+    let mut sealed = secret.to_vec();
+    match sealing_key.seal_in_place_append_tag(
+        nonce,
+        aead::Aad::from(mfa_key.id.as_bytes()),
+        &mut sealed,
+    ) {
+        Ok(_) => Ok(format!(
+            "{}.{}.{}",
+            mfa_key.id,
+            BASE64_STANDARD.encode(&sealed),
+            BASE64_STANDARD.encode(nonce_buffer)
+        )),
+        Err(_) => anyhow::bail!("failed to seal mfa secret"),
+    }
+}
+
+pub fn open_totp_secret(sealed: &str) -> anyhow::Result<Vec<u8>> {
+    let mut parts = sealed.splitn(3, '.');
+    let (key_id, sealed, nonce) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(key_id), Some(sealed), Some(nonce)) => (key_id, sealed, nonce),
+        _ => anyhow::bail!("invalid mfa secret"),
+    };
+    let mfa_key = match KeyRing::global()?.find(KeyPurpose::Mfa, key_id) {
+        Some(key) => key,
+        None => anyhow::bail!("unknown or expired mfa key {}", key_id),
+    };
+    let key = aead::UnboundKey::new(&aead::AES_256_GCM, mfa_key.material()).unwrap();
+    let opening_key = aead::LessSafeKey::new(key);
+
+    let mut sealed = BASE64_STANDARD.decode(sealed)?;
+    let nonce = BASE64_STANDARD.decode(nonce)?;
+    if nonce.len() != 12 {
+        anyhow::bail!("invalid mfa secret");
+    }
+    let mut nonce_buffer = [0u8; 12];
+    nonce_buffer.copy_from_slice(nonce.as_slice());
+    let nonce = aead::Nonce::assume_unique_for_key(nonce_buffer);
+
+    match opening_key.open_in_place(nonce, aead::Aad::from(key_id.as_bytes()), &mut sealed) {
+        Ok(secret) => Ok(secret.to_vec()),
+        Err(_) => anyhow::bail!("failed to open mfa secret"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RFC6238_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn totp_matches_rfc6238_sha1_vectors() {
+        let vectors = [
+            (59, "287082"),
+            (1111111109, "081804"),
+            (1111111111, "050471"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+            (20000000000, "353130"),
+        ];
+        for (time, code) in vectors {
+            assert_eq!(totp_code(RFC6238_SECRET, time / TOTP_PERIOD), code);
+        }
+    }
+
+    #[test]
+    fn base32_matches_rfc4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "MY"),
+            ("fo", "MZXQ"),
+            ("foo", "MZXW6"),
+            ("foob", "MZXW6YQ"),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI"),
+        ];
+        for (data, encoded) in vectors {
+            assert_eq!(base32_encode(data.as_bytes()), encoded);
+            assert_eq!(base32_decode(encoded).unwrap(), data.as_bytes());
+        }
+        assert_eq!(base32_decode("MZXW6YQ=").unwrap(), b"foob");
+        assert_eq!(base32_decode("mzxw6ytboi").unwrap(), b"foobar");
+        assert!(base32_decode("MZXW1").is_err());
+    }
+
+    #[test]
+    fn verify_totp_rejects_replayed_step() {
+        let step = Utc::now().timestamp() / TOTP_PERIOD;
+        let code = totp_code(RFC6238_SECRET, step);
+        let verified = verify_totp(RFC6238_SECRET, &code, None).unwrap();
+        assert!((verified - step).abs() <= TOTP_SKEW);
+        assert_eq!(verify_totp(RFC6238_SECRET, &code, Some(verified)), None);
+        assert_eq!(
+            verify_totp(RFC6238_SECRET, &code, Some(step + TOTP_SKEW)),
+            None
+        );
+        assert_eq!(verify_totp(RFC6238_SECRET, "12345", None), None);
+    }
+}